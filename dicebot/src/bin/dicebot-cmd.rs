@@ -42,6 +42,8 @@ async fn main() -> Result<(), BotError> {
         },
         username: "@localuser:example.com",
         message_body: &input,
+        in_reply_to: None,
+        bot_admin: false,
     };
 
     let message = command.execute(&context).await.message_html("fakeuser");
@@ -4,7 +4,7 @@ use crate::error::BotError;
 use crate::logic;
 use crate::matrix;
 use crate::{
-    commands::{execute_command, ExecutionResult, ResponseExtractor},
+    commands::{execute_command, macros, ExecutionResult, ResponseExtractor},
     models::Account,
 };
 use futures::stream::{self, StreamExt};
@@ -25,6 +25,14 @@ pub(super) async fn handle_single_result(
     let html = cmd_result.message_html(respond_to);
     let plain = cmd_result.message_plain(respond_to);
     matrix::send_message(client, room.room_id(), (&html, &plain), Some(event_id)).await;
+    send_attachment(client, cmd_result, room).await;
+}
+
+/// Upload the file produced by a command, if there is one.
+async fn send_attachment(client: &Client, cmd_result: &ExecutionResult, room: &Joined) {
+    if let Some(attachment) = cmd_result.as_ref().ok().and_then(|e| e.attachment()) {
+        matrix::send_file(client, room.room_id(), attachment).await;
+    }
 }
 
 /// Format failure messages nicely in either HTML or plain text. If
@@ -98,6 +106,10 @@ pub(super) async fn handle_multiple_results(
     };
 
     matrix::send_message(client, room.room_id(), (&message, &plain), None).await;
+
+    for (_, result) in results {
+        send_attachment(client, result, room).await;
+    }
 }
 
 /// Map an account's active room value to an actual matrix room, if
@@ -115,6 +127,29 @@ fn get_account_active_room(client: &Client, account: &Account) -> Result<Option<
     Ok(active_room)
 }
 
+/// Replace the commands that run macros in a message with the
+/// commands of the macros. Macros of the active room are available in
+/// secure rooms, like the active room's variables.
+pub(super) async fn expand_macros(
+    commands: Vec<String>,
+    db: &Database,
+    client: &Client,
+    origin_room: &Joined,
+    sender: &str,
+    max_commands: usize,
+) -> Result<Vec<String>, BotError> {
+    let origin_ctx = RoomContext::new(origin_room, sender).await?;
+    let account = logic::get_account(db, sender).await?;
+    let active_room = get_account_active_room(client, &account)?;
+    let active_room = active_room
+        .as_ref()
+        .filter(|_| origin_ctx.secure)
+        .unwrap_or(origin_room);
+
+    let room_id = active_room.room_id().as_str();
+    macros::expand_macros(db, sender, room_id, commands, max_commands).await
+}
+
 /// Execute a single command in the list of commands. Can fail if the
 /// Account value cannot be created/fetched from the database, or if
 /// room display names cannot be calculated. Otherwise, the success or
@@ -125,6 +160,8 @@ async fn execute_single_command(
     client: &Client,
     origin_room: &Joined,
     sender: &str,
+    in_reply_to: Option<&EventId>,
+    bot_admin: bool,
 ) -> ExecutionResult {
     let origin_ctx = RoomContext::new(origin_room, sender).await?;
     let account = logic::get_account(db, sender).await?;
@@ -140,14 +177,19 @@ async fn execute_single_command(
 
     let active_ctx = RoomContext::new(active_room, sender).await?;
 
+    //Variable changes are recorded as made with the command word.
+    let via = command.split_whitespace().next().unwrap_or_default();
+
     let ctx = Context {
         account,
-        db: db.clone(),
+        db: db.with_change_source(sender, via),
         matrix_client: client.clone(),
         origin_room: origin_ctx,
         username: &sender,
         active_room: active_ctx,
         message_body: &command,
+        in_reply_to: in_reply_to.cloned(),
+        bot_admin,
     };
 
     execute_command(&ctx).await
@@ -162,10 +204,14 @@ pub(super) async fn execute(
     client: &Client,
     room: &Joined,
     sender: &str,
+    in_reply_to: Option<&EventId>,
+    bot_admin: bool,
 ) -> Vec<(String, ExecutionResult)> {
     stream::iter(commands)
         .then(|command| async move {
-            let result = execute_single_command(command, db, client, room, sender).await;
+            let result =
+                execute_single_command(command, db, client, room, sender, in_reply_to, bot_admin)
+                    .await;
             (command.to_owned(), result)
         })
         .collect()
@@ -2,10 +2,13 @@ use super::DiceBot;
 use crate::db::sqlite::Database;
 use crate::db::Rooms;
 use crate::error::BotError;
+use crate::matrix;
 use log::{debug, error, info, warn};
 use matrix_sdk::ruma::events::room::member::MemberEventContent;
-use matrix_sdk::ruma::events::room::message::{MessageType, TextMessageEventContent};
-use matrix_sdk::ruma::events::{StrippedStateEvent, SyncMessageEvent};
+use matrix_sdk::ruma::events::room::message::{MessageType, Relation, TextMessageEventContent};
+use matrix_sdk::ruma::events::room::tombstone::TombstoneEventContent;
+use matrix_sdk::ruma::events::{StrippedStateEvent, SyncMessageEvent, SyncStateEvent};
+use matrix_sdk::ruma::EventId;
 use matrix_sdk::Client;
 use matrix_sdk::{self, room::Room, ruma::events::room::message::MessageEventContent};
 use std::ops::Sub;
@@ -83,6 +86,14 @@ async fn should_process_message<'a>(
     Ok((msg_body, sender_username))
 }
 
+/// The event that a message replies to, if it is a reply.
+fn replied_to_event(event: &SyncMessageEvent<MessageEventContent>) -> Option<&EventId> {
+    match &event.content.relates_to {
+        Some(Relation::Reply { in_reply_to }) => Some(&in_reply_to.event_id),
+        _ => None,
+    }
+}
+
 async fn should_process_event(db: &Database, room_id: &str, event_id: &str) -> bool {
     db.should_process(room_id, event_id)
         .await
@@ -129,6 +140,12 @@ pub(super) async fn on_room_message(
         _ => return,
     };
 
+    //The bot's own messages are never commands, or it would answer
+    //itself forever in direct messages.
+    if &event.sender == room.own_user_id() {
+        return;
+    }
+
     let room_id = room.room_id().as_str();
     if !should_process_event(&bot.db, room_id, event.event_id.as_str()).await {
         return;
@@ -142,9 +159,40 @@ pub(super) async fn on_room_message(
         };
 
     let results = bot
-        .execute_commands(&room, &sender_username, &msg_body)
+        .execute_commands(&room, &sender_username, &msg_body, replied_to_event(&event))
         .await;
 
     bot.handle_results(&room, &sender_username, event.event_id.clone(), results)
         .await;
 }
+
+/// When a room is upgraded, follow it to the replacement room and
+/// let the players know how to bring their variables along.
+pub(super) async fn on_room_tombstone(
+    event: SyncStateEvent<TombstoneEventContent>,
+    room: Room,
+    bot: DiceBot,
+) {
+    let room = match room {
+        Room::Joined(joined_room) => joined_room,
+        _ => return,
+    };
+
+    let room_id = room.room_id().as_str();
+    if !should_process_event(&bot.db, room_id, event.event_id.as_str()).await {
+        return;
+    }
+
+    let new_room = &event.content.replacement_room;
+    info!("Room {} was upgraded to {}", room_id, new_room);
+
+    if let Err(e) = bot.client.join_room_by_id(new_room).await {
+        warn!("Could not join replacement room: {}", e.to_string())
+    }
+
+    let html = "This room was upgraded. A moderator can run <code>!variables migrate</code> \
+                in the new room to copy all variables and characters there.";
+
+    let plain = html2text::from_read(html.as_bytes(), html.len());
+    matrix::send_message(&bot.client, room.room_id(), (html, &plain), None).await;
+}
@@ -1,20 +1,25 @@
-use crate::commands::ExecutionResult;
+use crate::commands::{Execution, ExecutionResult};
 use crate::config::*;
 use crate::db::sqlite::Database;
-use crate::db::DbState;
+use crate::db::{DbState, Rooms};
 use crate::error::BotError;
+use crate::inline_rolls;
+use crate::models::RoomSettings;
 use crate::state::DiceBotState;
-use log::info;
+use log::{error, info};
 use matrix_sdk::room::Room;
 use matrix_sdk::ruma::events::room::message::MessageEventContent;
-use matrix_sdk::ruma::events::SyncMessageEvent;
+use matrix_sdk::ruma::events::room::tombstone::TombstoneEventContent;
+use matrix_sdk::ruma::events::{SyncMessageEvent, SyncStateEvent};
 use matrix_sdk::ruma::EventId;
 use matrix_sdk::{self, room::Joined, Client, SyncSettings};
 use std::clone::Clone;
 use std::sync::{Arc, RwLock};
+use triggers::CommandTriggers;
 
 mod command_execution;
 pub mod event_handlers;
+mod triggers;
 
 /// How many commands can be in one message. If the amount is higher
 /// than this, we reject execution.
@@ -96,6 +101,17 @@ impl DiceBot {
             })
             .await;
 
+        //on room upgrade: offer to migrate variables.
+        self.client
+            .register_event_handler({
+                let bot: DiceBot = self.clone();
+                move |event: SyncStateEvent<TombstoneEventContent>, room: Room| {
+                    let bot = bot.clone();
+                    async move { event_handlers::on_room_tombstone(event, room, bot).await }
+                }
+            })
+            .await;
+
         //auto-join handler
         self.client
             .register_event_handler(event_handlers::on_stripped_state_member)
@@ -123,16 +139,43 @@ impl DiceBot {
         room: &Joined,
         sender: &str,
         msg_body: &str,
+        in_reply_to: Option<&EventId>,
     ) -> Vec<(String, ExecutionResult)> {
-        let commands: Vec<&str> = msg_body
-            .lines()
-            .filter(|line| line.starts_with("!"))
-            .take(MAX_COMMANDS_PER_MESSAGE + 1)
-            .collect();
+        let settings = self.room_settings(room).await;
+        let triggers = self.command_triggers(room, &settings).await;
+        let commands = triggers::split_commands(msg_body, &triggers, MAX_COMMANDS_PER_MESSAGE);
+        if commands.is_empty() {
+            return self.execute_inline_rolls(msg_body, &settings);
+        }
+
+        let commands = match command_execution::expand_macros(
+            commands,
+            &self.db,
+            &self.client,
+            room,
+            sender,
+            MAX_COMMANDS_PER_MESSAGE,
+        )
+        .await
+        {
+            Ok(commands) => commands,
+            Err(e) => return vec![(msg_body.trim().to_owned(), Err(e))],
+        };
 
         //Up to 50 commands allowed, otherwise we send back an error.
         let results: Vec<(String, ExecutionResult)> = if commands.len() < MAX_COMMANDS_PER_MESSAGE {
-            command_execution::execute(commands, &self.db, &self.client, room, sender).await
+            let commands = commands.iter().map(String::as_str).collect();
+            let bot_admin = self.config.is_bot_admin(sender);
+            command_execution::execute(
+                commands,
+                &self.db,
+                &self.client,
+                room,
+                sender,
+                in_reply_to,
+                bot_admin,
+            )
+            .await
         } else {
             vec![("".to_owned(), Err(BotError::MessageTooLarge))]
         };
@@ -140,6 +183,53 @@ impl DiceBot {
         results
     }
 
+    /// Settings of the room. Errors are only logged, and the default
+    /// settings used, so that the bot keeps answering commands.
+    async fn room_settings(&self, room: &Joined) -> RoomSettings {
+        self.db
+            .get_room_settings(room.room_id().as_str())
+            .await
+            .unwrap_or_else(|e| {
+                error!("Could not get settings of room {}: {}", room.room_id(), e);
+                RoomSettings::default()
+            })
+    }
+
+    /// What makes a line a command in the room: the prefix of the room
+    /// (or the configured prefix), mentioning the bot, or any line in
+    /// a direct message.
+    async fn command_triggers(&self, room: &Joined, settings: &RoomSettings) -> CommandTriggers {
+        let prefix = settings
+            .command_prefix
+            .clone()
+            .unwrap_or_else(|| self.config.command_prefix());
+
+        let own_id = room.own_user_id();
+        let mut mentions = vec![own_id.to_string(), own_id.localpart().to_owned()];
+        if let Ok(Some(member)) = room.get_member(own_id).await {
+            mentions.extend(member.display_name().map(str::to_owned));
+        }
+
+        CommandTriggers {
+            prefix,
+            mentions,
+            direct: room.is_direct(),
+        }
+    }
+
+    /// Roll the inline rolls of a message without commands, if the
+    /// room has inline rolls turned on.
+    fn execute_inline_rolls(
+        &self,
+        msg_body: &str,
+        settings: &RoomSettings,
+    ) -> Vec<(String, ExecutionResult)> {
+        match inline_rolls::roll_inline(msg_body, settings) {
+            Some(html) => vec![(msg_body.to_owned(), Execution::success(html))],
+            None => vec![],
+        }
+    }
+
     pub async fn handle_results(
         &self,
         room: &Joined,
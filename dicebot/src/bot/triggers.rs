@@ -0,0 +1,180 @@
+use crate::commands::macros;
+
+/// What makes a line of a message a command in a room.
+#[derive(Debug, Clone)]
+pub(super) struct CommandTriggers {
+    /// The command prefix of the room.
+    pub(super) prefix: String,
+
+    /// Names that the bot is mentioned by: its user ID, localpart and
+    /// display name. A line starting with a mention is a command.
+    pub(super) mentions: Vec<String>,
+
+    /// In direct messages with the bot, every line is a command,
+    /// with or without the prefix.
+    pub(super) direct: bool,
+}
+
+/// The rest of the line after a case-insensitive prefix.
+fn strip_prefix_ignore_case<'a>(line: &'a str, prefix: &str) -> Option<&'a str> {
+    match line.get(..prefix.len()) {
+        Some(start) if !prefix.is_empty() && start.eq_ignore_ascii_case(prefix) => {
+            Some(&line[prefix.len()..])
+        }
+        _ => None,
+    }
+}
+
+impl CommandTriggers {
+    /// The command in a line, without its prefix or mention, if the
+    /// line is a command.
+    fn command<'a>(&self, line: &'a str) -> Option<&'a str> {
+        let mentioned = self.mentions.iter().find_map(|name| {
+            strip_prefix_ignore_case(line, name)
+                .and_then(|rest| rest.strip_prefix(&[':', ','][..]))
+                .map(str::trim_start)
+        });
+
+        let command = match (line.strip_prefix(self.prefix.as_str()), mentioned) {
+            (Some(command), _) => Some(command),
+            (None, Some(command)) => Some(
+                command
+                    .strip_prefix(self.prefix.as_str())
+                    .unwrap_or(command),
+            ),
+            (None, None) if self.direct => Some(line.trim_start()),
+            (None, None) => None,
+        };
+
+        command
+            .map(str::trim_end)
+            .filter(|command| !command.is_empty())
+    }
+
+    /// The line as a command with the "!" prefix, which is how
+    /// commands are parsed and stored in macros, whatever the prefix
+    /// of the room is.
+    fn canonical_command(&self, line: &str) -> Option<String> {
+        self.command(line).map(|command| format!("!{}", command))
+    }
+
+    /// The first line of a macro definition with the first command of
+    /// the macro, after the name, also given the "!" prefix.
+    fn canonical_definition(&self, definition: String) -> String {
+        let words: Vec<&str> = definition.splitn(4, ' ').collect();
+        match words.as_slice() {
+            [macro_cmd, add, name, first] => match self.canonical_command(first.trim_start()) {
+                Some(first) => format!("{} {} {} {}", macro_cmd, add, name, first),
+                None => definition,
+            },
+            _ => definition,
+        }
+    }
+}
+
+/// The commands in a message, each with the "!" prefix, up to one more
+/// than max_commands. A macro definition takes the rest of the
+/// message as its commands.
+pub(super) fn split_commands(
+    msg_body: &str,
+    triggers: &CommandTriggers,
+    max_commands: usize,
+) -> Vec<String> {
+    let mut commands = vec![];
+    let mut lines = msg_body.lines();
+
+    while let Some(line) = lines.next() {
+        let command = match triggers.canonical_command(line) {
+            Some(command) => command,
+            None => continue,
+        };
+
+        if macros::is_macro_definition(&command) {
+            let definition: Vec<String> = std::iter::once(triggers.canonical_definition(command))
+                .chain(lines.map(|line| {
+                    triggers
+                        .canonical_command(line)
+                        .unwrap_or_else(|| line.to_owned())
+                }))
+                .collect();
+
+            commands.push(definition.join("\n").trim_end().to_owned());
+            break;
+        }
+
+        commands.push(command);
+        if commands.len() > max_commands {
+            break;
+        }
+    }
+
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triggers(prefix: &str, direct: bool) -> CommandTriggers {
+        CommandTriggers {
+            prefix: prefix.to_owned(),
+            mentions: vec!["@dicebot:example.com".to_owned(), "DiceBot".to_owned()],
+            direct,
+        }
+    }
+
+    #[test]
+    fn splits_command_lines() {
+        let msg = "!roll 1d4\nsome text\r\n!get hp\n";
+        assert_eq!(
+            vec!["!roll 1d4", "!get hp"],
+            split_commands(msg, &triggers("!", false), 50)
+        );
+    }
+
+    #[test]
+    fn macro_definition_takes_rest_of_message() {
+        let msg = "!roll 1d4\n!macro add attack !roll 1d20\n!roll 1d8\n";
+        assert_eq!(
+            vec!["!roll 1d4", "!macro add attack !roll 1d20\n!roll 1d8"],
+            split_commands(msg, &triggers("!", false), 50)
+        );
+    }
+
+    #[test]
+    fn room_prefix_replaces_exclamation_mark() {
+        let msg = ".roll 1d4\n!other bot command\n.macro add a .roll 1d20\n.roll 1d8";
+        assert_eq!(
+            vec!["!roll 1d4", "!macro add a !roll 1d20\n!roll 1d8"],
+            split_commands(msg, &triggers(".", false), 50)
+        );
+    }
+
+    #[test]
+    fn mentions_are_commands() {
+        let triggers = triggers("!", false);
+        assert_eq!(
+            Some("!roll 1d20"),
+            triggers.canonical_command("dicebot: roll 1d20").as_deref()
+        );
+        assert_eq!(
+            Some("!roll 1d20"),
+            triggers
+                .canonical_command("@dicebot:example.com: !roll 1d20")
+                .as_deref()
+        );
+        assert_eq!(None, triggers.canonical_command("dicebots are fun"));
+        assert_eq!(None, triggers.canonical_command("dicebot:"));
+    }
+
+    #[test]
+    fn direct_messages_need_no_prefix() {
+        let msg = "roll 1d4\n\n!get hp";
+        assert_eq!(
+            vec!["!roll 1d4", "!get hp"],
+            split_commands(msg, &triggers("!", true), 50)
+        );
+
+        assert!(split_commands("roll 1d4", &triggers("!", false), 50).is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use crate::context::Context;
+use crate::dice::{roll_exploding_die, DieRoller, RngDieRoller};
 use crate::error::{BotError, DiceRollingError};
 use crate::parser::dice::{Amount, Element, Operator};
 use itertools::Itertools;
@@ -30,7 +31,7 @@ impl fmt::Display for DicePoolQuality {
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct DicePoolModifiers {
-    pub(crate) success_on: i32,
+    pub(crate) success_on: u32,
     pub(crate) exceptional_on: i32,
     pub(crate) quality: DicePoolQuality,
 }
@@ -77,7 +78,7 @@ impl DicePoolModifiers {
 #[derive(Debug, PartialEq)]
 pub struct DicePool {
     pub(crate) amounts: Vec<Amount>,
-    pub(crate) sides: i32,
+    pub(crate) sides: u32,
     pub(crate) modifiers: DicePoolModifiers,
 }
 
@@ -127,7 +128,7 @@ pub struct RolledDicePool {
 }
 
 impl RolledDicePool {
-    fn from(pool: &DicePool, num_dice: i32, rolls: Vec<i32>) -> RolledDicePool {
+    fn from(pool: &DicePool, num_dice: i32, rolls: Vec<u32>) -> RolledDicePool {
         RolledDicePool {
             modifiers: pool.modifiers,
             num_dice: num_dice,
@@ -154,7 +155,7 @@ impl fmt::Display for RolledDicePool {
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct DicePoolRoll {
     modifiers: DicePoolModifiers,
-    rolls: Vec<i32>,
+    rolls: Vec<u32>,
 }
 
 /// Amount of dice to display before cutting off and showing "and X
@@ -186,7 +187,7 @@ fn fmt_for_failure(pool: &DicePoolRoll) -> String {
 }
 
 impl DicePoolRoll {
-    pub fn rolls(&self) -> &[i32] {
+    pub fn rolls(&self) -> &[u32] {
         &self.rolls
     }
 
@@ -227,42 +228,10 @@ impl fmt::Display for DicePoolRoll {
     }
 }
 
-trait DieRoller {
-    fn roll_number(&mut self, sides: i32) -> i32;
-}
-
-///A version of DieRoller that uses a rand::Rng to roll numbers.
-struct RngDieRoller<R: rand::Rng>(R);
-
-impl<R: rand::Rng> DieRoller for RngDieRoller<R> {
-    fn roll_number(&mut self, sides: i32) -> i32 {
-        self.0.gen_range(1..=sides)
-    }
-}
-
-///Roll a die in the pool, that "explodes" on a given number or higher. Dice will keep
-///being rolled until the result is lower than the explode number, which is normally 10.
-///Statistically speaking, usually one result will be returned from this function.
-fn roll_exploding_die<R: DieRoller>(
-    roller: &mut R,
-    sides: i32,
-    explode_on_or_higher: i32,
-) -> Vec<i32> {
-    let mut results = vec![];
-    loop {
-        let roll = roller.roll_number(sides);
-        results.push(roll);
-        if roll < explode_on_or_higher {
-            break;
-        }
-    }
-    results
-}
-
 ///A die with the rote quality is re-rolled once if the roll fails. Otherwise, it obeys
 ///all normal rules (re-roll 10s). Re-rolled dice are appended to the result set, so we
 ///can keep track of the actual dice that were rolled.
-fn roll_rote_die<R: DieRoller>(roller: &mut R, sides: i32, success_on: i32) -> Vec<i32> {
+fn roll_rote_die<R: DieRoller>(roller: &mut R, sides: u32, success_on: u32) -> Vec<u32> {
     let mut rolls = roll_exploding_die(roller, sides, 10);
 
     if rolls.len() == 1 && rolls[0] < success_on {
@@ -277,7 +246,7 @@ fn roll_rote_die<R: DieRoller>(roller: &mut R, sides: i32, success_on: i32) -> V
 ///there are multiple 10s). Nine- and eight-again will explode similarly if the result is
 ///at least that number. Rote quality will re-roll a failure once, while also exploding
 ///on 10. The function returns a Vec of all rolled dice (usually 1).
-fn roll_die<R: DieRoller>(roller: &mut R, pool: &DicePool) -> Vec<i32> {
+fn roll_die<R: DieRoller>(roller: &mut R, pool: &DicePool) -> Vec<u32> {
     let mut results = vec![];
     let sides = pool.sides;
     let success_on = pool.modifiers.success_on;
@@ -295,7 +264,7 @@ fn roll_die<R: DieRoller>(roller: &mut R, pool: &DicePool) -> Vec<i32> {
     results
 }
 
-fn roll_dice<'a, R: DieRoller>(pool: &DicePool, num_dice: i32, roller: &mut R) -> Vec<i32> {
+fn roll_dice<'a, R: DieRoller>(pool: &DicePool, num_dice: i32, roller: &mut R) -> Vec<u32> {
     (0..num_dice)
         .flat_map(|_| roll_die(roller, &pool))
         .collect()
@@ -327,6 +296,7 @@ mod tests {
     use super::*;
     use crate::db::sqlite::Database;
     use crate::db::Variables;
+    use crate::dice::SequentialDieRoller;
     use url::Url;
 
     macro_rules! dummy_room {
@@ -339,30 +309,6 @@ mod tests {
         };
     }
 
-    ///Instead of being random, generate a series of numbers we have complete
-    ///control over.
-    struct SequentialDieRoller {
-        results: Vec<i32>,
-        position: usize,
-    }
-
-    impl SequentialDieRoller {
-        fn new(results: Vec<i32>) -> SequentialDieRoller {
-            SequentialDieRoller {
-                results: results,
-                position: 0,
-            }
-        }
-    }
-
-    impl DieRoller for SequentialDieRoller {
-        fn roll_number(&mut self, _sides: i32) -> i32 {
-            let roll = self.results[self.position];
-            self.position += 1;
-            roll
-        }
-    }
-
     //Sanity checks
     #[test]
     pub fn chance_die_has_success_on_10_test() {
@@ -490,6 +436,8 @@ mod tests {
             active_room: dummy_room!(),
             username: "username",
             message_body: "message",
+            in_reply_to: None,
+            bot_admin: false,
         };
 
         let mut amounts = vec![];
@@ -532,6 +480,8 @@ mod tests {
             active_room: dummy_room!(),
             username: "username",
             message_body: "message",
+            in_reply_to: None,
+            bot_admin: false,
         };
 
         let mut amounts = vec![];
@@ -571,6 +521,8 @@ mod tests {
             active_room: dummy_room!(),
             username: "username",
             message_body: "message",
+            in_reply_to: None,
+            bot_admin: false,
         };
 
         db.set_user_variable(
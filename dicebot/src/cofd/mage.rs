@@ -0,0 +1,145 @@
+//! Mage: The Awakening spellcasting. The spellcasting pool is Gnosis
+//! plus Arcanum, and using more Reach than the spell's free Reach
+//! adds Paradox dice. Both pools are rolled with the regular dice
+//! pool roller.
+
+use super::dice::{roll_pool, DicePool, DicePoolQuality, DicePoolWithContext, RolledDicePool};
+use crate::context::Context;
+use crate::error::BotError;
+use crate::logic::calculate_single_die_amount;
+use crate::parser::dice::{Amount, Element, Operator};
+use std::fmt;
+
+/// Gnosis is read from this user variable.
+pub const GNOSIS_VARIABLE: &str = "gnosis";
+
+/// A spell to cast.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Spell {
+    /// Dots in the Arcanum used to cast the spell.
+    pub arcanum: Amount,
+
+    /// The spell's level (its dots in the Arcanum).
+    pub level: u32,
+
+    /// Total Reach used for the spell.
+    pub reach: u32,
+
+    /// Mana spent to reduce Paradox dice.
+    pub mana: u32,
+
+    /// Extra dice from yantras and other sources (or a penalty).
+    pub bonus: i32,
+}
+
+/// Attach a Context to a spell. Needed for database access.
+pub struct SpellWithContext<'a>(pub &'a Spell, pub &'a Context<'a>);
+
+/// Free Reach: Arcanum dots minus the spell's level, plus one.
+pub fn free_reach(arcanum: i32, level: u32) -> u32 {
+    std::cmp::max(arcanum - level as i32 + 1, 0) as u32
+}
+
+/// Each Reach over free Reach adds Paradox dice based on Gnosis: one
+/// die at Gnosis 1-2, two at 3-4, and so on up to five at 9-10.
+pub fn paradox_per_reach(gnosis: i32) -> u32 {
+    ((std::cmp::max(gnosis, 1) + 1) / 2) as u32
+}
+
+/// Paradox dice for the given Reach over free Reach, reduced by one
+/// for every point of Mana spent.
+pub fn paradox_dice(gnosis: i32, excess_reach: u32, mana: u32) -> u32 {
+    (excess_reach * paradox_per_reach(gnosis)).saturating_sub(mana)
+}
+
+/// The outcome of casting a spell.
+pub struct CastSpell {
+    pub(crate) gnosis: i32,
+    pub(crate) arcanum: i32,
+    pub(crate) free_reach: u32,
+    pub(crate) spellcasting: RolledDicePool,
+
+    /// Only rolled if the spell used more than its free Reach. If Mana
+    /// removes every Paradox die, a chance die is still rolled.
+    pub(crate) paradox: Option<RolledDicePool>,
+}
+
+impl fmt::Display for CastSpell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Gnosis {} + Arcanum {}, {} free Reach",
+            self.gnosis, self.arcanum, self.free_reach
+        )
+    }
+}
+
+async fn roll_simple_pool(num_dice: i32, ctx: &Context<'_>) -> Result<RolledDicePool, BotError> {
+    let pool = DicePool::easy_pool(num_dice, DicePoolQuality::TenAgain);
+    roll_pool(&DicePoolWithContext(&pool, ctx)).await
+}
+
+/// Cast a spell: roll Paradox if the spell reaches beyond its free
+/// Reach, then roll the spellcasting pool.
+pub async fn cast_spell(spell: &SpellWithContext<'_>) -> Result<CastSpell, BotError> {
+    let (spell, ctx) = (spell.0, spell.1);
+    let gnosis_variable = Amount {
+        operator: Operator::Plus,
+        element: Element::Variable(GNOSIS_VARIABLE.to_owned()),
+    };
+
+    let gnosis = calculate_single_die_amount(&gnosis_variable, ctx).await?;
+    let arcanum = calculate_single_die_amount(&spell.arcanum, ctx).await?;
+
+    if arcanum < spell.level as i32 {
+        return Err(BotError::ArcanumTooLow);
+    }
+
+    let free_reach = free_reach(arcanum, spell.level);
+    let excess_reach = spell.reach.saturating_sub(free_reach);
+
+    let paradox = if excess_reach > 0 {
+        let dice = paradox_dice(gnosis, excess_reach, spell.mana);
+        Some(roll_simple_pool(dice as i32, ctx).await?)
+    } else {
+        None
+    };
+
+    let spellcasting = roll_simple_pool(gnosis + arcanum + spell.bonus, ctx).await?;
+
+    Ok(CastSpell {
+        gnosis,
+        arcanum,
+        free_reach,
+        spellcasting,
+        paradox,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn free_reach_test() {
+        assert_eq!(1, free_reach(3, 3));
+        assert_eq!(3, free_reach(4, 2));
+        assert_eq!(0, free_reach(2, 3));
+    }
+
+    #[test]
+    fn paradox_per_reach_by_gnosis_test() {
+        assert_eq!(1, paradox_per_reach(1));
+        assert_eq!(1, paradox_per_reach(2));
+        assert_eq!(2, paradox_per_reach(3));
+        assert_eq!(3, paradox_per_reach(6));
+        assert_eq!(5, paradox_per_reach(10));
+    }
+
+    #[test]
+    fn mana_reduces_paradox_test() {
+        assert_eq!(4, paradox_dice(3, 2, 0));
+        assert_eq!(1, paradox_dice(3, 2, 3));
+        assert_eq!(0, paradox_dice(3, 2, 6));
+    }
+}
@@ -1,2 +1,5 @@
 pub mod dice;
+pub mod mage;
 pub mod parser;
+pub mod resources;
+pub mod werewolf;
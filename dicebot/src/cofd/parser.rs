@@ -1,6 +1,7 @@
 use crate::cofd::dice::{DicePool, DicePoolModifiers, DicePoolQuality};
+use crate::cofd::mage::Spell;
 use crate::error::BotError;
-use crate::parser::dice::{parse_amounts, DiceParsingError};
+use crate::parser::dice::{parse_amounts, parse_single_amount, DiceParsingError};
 use combine::parser::char::{digit, spaces, string};
 use combine::{choice, count, many1, one_of, Parser};
 
@@ -88,9 +89,51 @@ pub fn create_chance_die() -> Result<DicePool, BotError> {
     Ok(DicePool::chance_die())
 }
 
+fn parse_number(input: Option<&str>) -> Result<u32, DiceParsingError> {
+    Ok(input.ok_or(DiceParsingError::InvalidAmount)?.parse()?)
+}
+
+/// Parse a spell: the Arcanum (number or variable) and the spell's
+/// level, followed by any of "reach <num>", "mana <num>" and "bonus
+/// <num>".
+pub fn parse_spell(input: &str) -> Result<Spell, DiceParsingError> {
+    let mut parts = input.split_whitespace();
+    let (arcanum, rest) =
+        parse_single_amount(parts.next().unwrap_or_default())?;
+
+    if !rest.is_empty() {
+        return Err(DiceParsingError::UnconsumedInput);
+    }
+
+    let mut spell = Spell {
+        arcanum,
+        level: parse_number(parts.next())?,
+        reach: 0,
+        mana: 0,
+        bonus: 0,
+    };
+
+    while let Some(part) = parts.next() {
+        match part {
+            "reach" => spell.reach = parse_number(parts.next())?,
+            "mana" => spell.mana = parse_number(parts.next())?,
+            "bonus" => {
+                spell.bonus = parts
+                    .next()
+                    .ok_or(DiceParsingError::InvalidAmount)?
+                    .parse()?
+            }
+            _ => return Err(DiceParsingError::UnconsumedInput),
+        }
+    }
+
+    Ok(spell)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::dice::{Amount, Element, Operator};
 
     #[test]
     fn quality_test() {
@@ -230,4 +273,29 @@ mod tests {
         assert!(result.is_ok());
         assert_eq!(result.unwrap(), expected);
     }
+
+    #[test]
+    fn parse_spell_test() {
+        let spell = parse_spell("forces 2 reach 3 mana 1 bonus -1").expect("was error");
+        assert_eq!(
+            Spell {
+                arcanum: Amount {
+                    operator: Operator::Plus,
+                    element: Element::Variable("forces".to_owned()),
+                },
+                level: 2,
+                reach: 3,
+                mana: 1,
+                bonus: -1,
+            },
+            spell
+        );
+    }
+
+    #[test]
+    fn parse_spell_requires_level_test() {
+        assert!(parse_spell("forces").is_err());
+        assert!(parse_spell("").is_err());
+        assert!(parse_spell("3 2 yantras 2").is_err());
+    }
 }
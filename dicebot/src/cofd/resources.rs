@@ -0,0 +1,253 @@
+//! Spendable resources of the Chronicles of Darkness splats: Mana for
+//! Mage, Glamour for Changeling, Pyros for Promethean, and the five
+//! Pillars for Mummy. Each is limited by a power rating: Gnosis, Wyrd,
+//! Azoth or Sekhem. Deviant: The Renegades has no such resource, so
+//! Deviant rooms only get the plain dice pool.
+
+use crate::context::Context;
+use crate::db::errors::DataError;
+use crate::db::Variables;
+use crate::error::{BotError, DiceRollingError};
+use crate::systems::GameSystem;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Pillar {
+    Ab,
+    Ba,
+    Ka,
+    Ren,
+    Sheut,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Resource {
+    Mana,
+    Glamour,
+    Pyros,
+    Pillar(Pillar),
+}
+
+impl Resource {
+    pub fn parse(input: &str) -> Option<Resource> {
+        let resource = match input.trim().to_lowercase().as_ref() {
+            "mana" => Resource::Mana,
+            "glamour" => Resource::Glamour,
+            "pyros" => Resource::Pyros,
+            "ab" => Resource::Pillar(Pillar::Ab),
+            "ba" => Resource::Pillar(Pillar::Ba),
+            "ka" => Resource::Pillar(Pillar::Ka),
+            "ren" => Resource::Pillar(Pillar::Ren),
+            "sheut" => Resource::Pillar(Pillar::Sheut),
+            _ => return None,
+        };
+
+        Some(resource)
+    }
+
+    /// The game system this resource belongs to.
+    pub(crate) fn game_system(&self) -> GameSystem {
+        match self {
+            Resource::Mana => GameSystem::MageTheAwakening,
+            Resource::Glamour => GameSystem::Changeling,
+            Resource::Pyros => GameSystem::PrometheanTheCreated,
+            Resource::Pillar(_) => GameSystem::MummyTheCurse,
+        }
+    }
+
+    /// Name of the user variable holding the current amount.
+    pub fn variable(&self) -> &'static str {
+        match self {
+            Resource::Mana => "mana",
+            Resource::Glamour => "glamour",
+            Resource::Pyros => "pyros",
+            Resource::Pillar(Pillar::Ab) => "ab",
+            Resource::Pillar(Pillar::Ba) => "ba",
+            Resource::Pillar(Pillar::Ka) => "ka",
+            Resource::Pillar(Pillar::Ren) => "ren",
+            Resource::Pillar(Pillar::Sheut) => "sheut",
+        }
+    }
+
+    /// Name of the user variable holding the power rating that limits
+    /// spending.
+    pub fn rating_variable(&self) -> &'static str {
+        match self {
+            Resource::Mana => "gnosis",
+            Resource::Glamour => "wyrd",
+            Resource::Pyros => "azoth",
+            Resource::Pillar(_) => "sekhem",
+        }
+    }
+
+    /// The most that can be spent at once for the given power rating.
+    /// Gnosis, Wyrd and Azoth allow 1 per dot up to 8, then 10 and
+    /// 15. A mummy cannot spend more Pillar points at once than their
+    /// Sekhem.
+    pub fn spend_limit(&self, rating: i32) -> i32 {
+        match (self, rating) {
+            (Resource::Pillar(_), sekhem) => std::cmp::max(sekhem, 0),
+            (_, rating) if rating <= 8 => std::cmp::max(rating, 1),
+            (_, 9) => 10,
+            _ => 15,
+        }
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Resource::Mana => write!(f, "Mana"),
+            Resource::Glamour => write!(f, "Glamour"),
+            Resource::Pyros => write!(f, "Pyros"),
+            Resource::Pillar(pillar) => write!(f, "{:?}", pillar),
+        }
+    }
+}
+
+async fn get_variable(ctx: &Context<'_>, variable: &str) -> Result<i32, BotError> {
+    let result = ctx
+        .db
+        .get_user_variable(ctx.username, ctx.active_room_id().as_str(), variable)
+        .await;
+
+    match result {
+        Ok(value) => Ok(value),
+        Err(DataError::KeyDoesNotExist(_)) => {
+            Err(DiceRollingError::VariableNotFound(variable.to_owned(), None).into())
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Spend some of a resource, after checking the amount against the
+/// power rating's limit and the amount the user has. Returns the
+/// amount remaining.
+pub async fn spend(ctx: &Context<'_>, resource: Resource, amount: i32) -> Result<i32, BotError> {
+    let rating = get_variable(ctx, resource.rating_variable()).await?;
+    let limit = resource.spend_limit(rating);
+
+    if amount > limit {
+        return Err(BotError::SpendLimitExceeded(limit, resource.to_string()));
+    }
+
+    let result = ctx
+        .db
+        .spend_user_variable(
+            ctx.username,
+            ctx.active_room_id().as_str(),
+            resource.variable(),
+            amount,
+        )
+        .await;
+
+    let remaining = match result {
+        Ok((_, remaining)) => remaining,
+        Err(DataError::KeyDoesNotExist(_)) => {
+            let variable = resource.variable().to_owned();
+            return Err(DiceRollingError::VariableNotFound(variable, None).into());
+        }
+        Err(DataError::BelowMinimum(..)) => {
+            return Err(BotError::NotEnoughResource(resource.to_string()))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(remaining)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::Database;
+    use url::Url;
+
+    macro_rules! dummy_room {
+        () => {
+            crate::context::RoomContext {
+                id: &matrix_sdk::ruma::identifiers::room_id!("!fakeroomid:example.com"),
+                display_name: "displayname".to_owned(),
+                secure: false,
+            }
+        };
+    }
+
+    #[test]
+    fn parses_resources() {
+        assert_eq!(Some(Resource::Mana), Resource::parse("mana"));
+        assert_eq!(Some(Resource::Glamour), Resource::parse("Glamour"));
+        assert_eq!(
+            Some(Resource::Pillar(Pillar::Sheut)),
+            Resource::parse("Sheut")
+        );
+        assert_eq!(None, Resource::parse("vitae"));
+    }
+
+    #[test]
+    fn gnosis_wyrd_and_azoth_limits() {
+        assert_eq!(1, Resource::Mana.spend_limit(1));
+        assert_eq!(5, Resource::Glamour.spend_limit(5));
+        assert_eq!(8, Resource::Pyros.spend_limit(8));
+        assert_eq!(10, Resource::Mana.spend_limit(9));
+        assert_eq!(15, Resource::Pyros.spend_limit(10));
+    }
+
+    #[test]
+    fn sekhem_limits_pillars() {
+        let pillar = Resource::Pillar(Pillar::Ab);
+        assert_eq!(6, pillar.spend_limit(6));
+        assert_eq!(0, pillar.spend_limit(0));
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn spend_deducts_and_checks_limits() {
+        let db_path = tempfile::NamedTempFile::new_in(".").unwrap();
+        crate::db::sqlite::migrator::migrate(db_path.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        let db = Database::new(db_path.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        let homeserver = Url::parse("http://example.com").unwrap();
+        let ctx = Context {
+            account: crate::models::Account::default(),
+            db: db.clone(),
+            matrix_client: matrix_sdk::Client::new(homeserver).unwrap(),
+            origin_room: dummy_room!(),
+            active_room: dummy_room!(),
+            username: "username",
+            message_body: "message",
+            in_reply_to: None,
+            bot_admin: false,
+        };
+
+        let room_id = ctx.active_room_id().as_str().to_owned();
+        db.set_user_variable("username", &room_id, "gnosis", 2)
+            .await
+            .unwrap();
+
+        db.set_user_variable("username", &room_id, "mana", 4)
+            .await
+            .unwrap();
+
+        let remaining = spend(&ctx, Resource::Mana, 2).await;
+        assert!(matches!(remaining, Ok(2)));
+
+        let too_much = spend(&ctx, Resource::Mana, 3).await;
+        assert!(matches!(too_much, Err(BotError::SpendLimitExceeded(2, _))));
+
+        spend(&ctx, Resource::Mana, 2).await.unwrap();
+        let empty = spend(&ctx, Resource::Mana, 1).await;
+        assert!(matches!(empty, Err(BotError::NotEnoughResource(_))));
+
+        let no_rating = spend(&ctx, Resource::Pyros, 1).await;
+        assert!(matches!(
+            no_rating,
+            Err(BotError::DiceRollingError(
+                DiceRollingError::VariableNotFound(..)
+            ))
+        ));
+    }
+}
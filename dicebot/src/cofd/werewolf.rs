@@ -0,0 +1,163 @@
+//! Werewolf: The Forsaken forms. Each form modifies some of the
+//! werewolf's attributes, which are applied to dice pools that use
+//! those attributes as variables.
+
+use crate::context::Context;
+use crate::db::errors::DataError;
+use crate::db::Variables;
+use crate::error::BotError;
+use crate::parser::dice::{Amount, Element};
+use std::convert::TryFrom;
+use strum::{Display, EnumIter, EnumString, IntoEnumIterator};
+
+/// The current form is stored as a regular user variable, holding
+/// the form's number (0 for Hishu through 4 for Urhan).
+pub const FORM_VARIABLE: &str = "form";
+
+#[derive(EnumString, EnumIter, Display, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(ascii_case_insensitive)]
+pub enum Form {
+    Hishu,
+    Dalu,
+    Gauru,
+    Urshul,
+    Urhan,
+}
+
+impl Form {
+    pub fn from_number(number: i32) -> Option<Form> {
+        usize::try_from(number)
+            .ok()
+            .and_then(|number| Form::iter().nth(number))
+    }
+
+    pub fn number(&self) -> i32 {
+        *self as i32
+    }
+
+    /// The bonus or penalty this form gives to an attribute (or Size),
+    /// named as its variable would be.
+    pub fn modifier(&self, attribute: &str) -> i32 {
+        use Form::*;
+        match (self, attribute) {
+            (Dalu, "strength") | (Dalu, "stamina") | (Dalu, "size") => 1,
+            (Dalu, "manipulation") => -1,
+            (Gauru, "strength") => 3,
+            (Gauru, "dexterity") => 1,
+            (Gauru, "stamina") | (Gauru, "size") => 2,
+            (Urshul, "strength") | (Urshul, "dexterity") | (Urshul, "stamina") => 2,
+            (Urshul, "size") => 1,
+            (Urshul, "manipulation") => -1,
+            (Urhan, "dexterity") => 2,
+            (Urhan, "stamina") => 1,
+            (Urhan, "manipulation") | (Urhan, "size") => -1,
+            _ => 0,
+        }
+    }
+
+    /// Total modifier for every attribute variable used in a dice
+    /// pool expression.
+    pub fn pool_bonus(&self, amounts: &[Amount]) -> i32 {
+        amounts
+            .iter()
+            .map(|amount| match &amount.element {
+                Element::Variable(variable) => self.modifier(variable) * amount.operator.mult(),
+                Element::Number(_) => 0,
+            })
+            .sum()
+    }
+}
+
+/// Get the user's current form in the active room, if one is set.
+pub async fn get_form(ctx: &Context<'_>) -> Result<Option<Form>, BotError> {
+    let result = ctx
+        .db
+        .get_user_variable(ctx.username, ctx.active_room_id().as_str(), FORM_VARIABLE)
+        .await;
+
+    match result {
+        Ok(number) => Ok(Form::from_number(number)),
+        Err(DataError::KeyDoesNotExist(_)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Change the user's current form in the active room.
+pub async fn set_form(ctx: &Context<'_>, form: Form) -> Result<(), BotError> {
+    ctx.db
+        .set_user_variable(
+            ctx.username,
+            ctx.active_room_id().as_str(),
+            FORM_VARIABLE,
+            form.number(),
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::dice::Operator;
+
+    fn variable(name: &str, operator: Operator) -> Amount {
+        Amount {
+            operator,
+            element: Element::Variable(name.to_owned()),
+        }
+    }
+
+    #[test]
+    fn form_numbers_round_trip() {
+        for form in Form::iter() {
+            assert_eq!(Some(form), Form::from_number(form.number()));
+        }
+
+        assert_eq!(None, Form::from_number(5));
+        assert_eq!(None, Form::from_number(-1));
+    }
+
+    #[test]
+    fn hishu_has_no_modifiers() {
+        assert_eq!(0, Form::Hishu.modifier("strength"));
+        assert_eq!(0, Form::Hishu.modifier("manipulation"));
+    }
+
+    #[test]
+    fn gauru_modifiers() {
+        assert_eq!(3, Form::Gauru.modifier("strength"));
+        assert_eq!(1, Form::Gauru.modifier("dexterity"));
+        assert_eq!(2, Form::Gauru.modifier("stamina"));
+        assert_eq!(0, Form::Gauru.modifier("wits"));
+    }
+
+    #[test]
+    fn pool_bonus_sums_attribute_variables() {
+        let amounts = vec![
+            variable("strength", Operator::Plus),
+            variable("brawl", Operator::Plus),
+            Amount {
+                operator: Operator::Plus,
+                element: Element::Number(2),
+            },
+        ];
+
+        assert_eq!(3, Form::Gauru.pool_bonus(&amounts));
+        assert_eq!(0, Form::Urhan.pool_bonus(&amounts));
+
+        let amounts = vec![
+            variable("presence", Operator::Plus),
+            variable("manipulation", Operator::Plus),
+        ];
+
+        assert_eq!(-1, Form::Urshul.pool_bonus(&amounts));
+    }
+
+    #[test]
+    fn parses_form_names() {
+        assert_eq!(Ok(Form::Urshul), "urshul".parse());
+        assert_eq!(Ok(Form::Gauru), "Gauru".parse());
+        assert!("wolf".parse::<Form>().is_err());
+    }
+}
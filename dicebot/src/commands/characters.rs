@@ -0,0 +1,152 @@
+use super::{Command, CommandError, Execution, ExecutionResult};
+use crate::context::Context;
+use crate::db::Characters;
+use crate::error::BotError;
+use async_trait::async_trait;
+use std::convert::TryFrom;
+
+/// What to do with the user's characters in the active room.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum CharacterAction {
+    /// Display the active character.
+    Show,
+
+    /// List all of the user's characters.
+    List,
+
+    /// Create a new character and make it active.
+    New(String),
+
+    /// Make an existing character active.
+    Switch(String),
+
+    /// Stop using a character, going back to the user's own variables.
+    Clear,
+
+    /// Delete a character and all of its variables.
+    Delete(String),
+}
+
+pub struct CharacterCommand(pub CharacterAction);
+
+impl TryFrom<String> for CharacterCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let input = input.trim();
+        let (action, name) = match input.split_once(char::is_whitespace) {
+            Some((action, name)) => (action, name.trim().to_owned()),
+            None => (input, String::new()),
+        };
+
+        let action = match (action.to_lowercase().as_ref(), name.is_empty()) {
+            ("", _) => CharacterAction::Show,
+            ("list", true) => CharacterAction::List,
+            ("clear", true) => CharacterAction::Clear,
+            ("new", false) => CharacterAction::New(name),
+            ("switch", false) => CharacterAction::Switch(name),
+            ("delete", false) => CharacterAction::Delete(name),
+            _ => return Err(CommandError::InvalidCommand(input.to_owned()).into()),
+        };
+
+        Ok(CharacterCommand(action))
+    }
+}
+
+#[async_trait]
+impl Command for CharacterCommand {
+    fn name(&self) -> &'static str {
+        "manage characters"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let user = ctx.username;
+        let room_id = ctx.active_room_id().as_str();
+
+        let html = match &self.0 {
+            CharacterAction::Show => {
+                let active = ctx.db.get_active_character(user, room_id).await?;
+                let name = active.unwrap_or_else(|| "none".to_string());
+                format!("<strong>Active character:</strong> {}", name)
+            }
+            CharacterAction::List => {
+                let active = ctx.db.get_active_character(user, room_id).await?;
+                let characters: Vec<String> = ctx
+                    .db
+                    .get_characters(user, room_id)
+                    .await?
+                    .into_iter()
+                    .map(|name| match active {
+                        Some(ref active) if *active == name => format!(" - {} (active)", name),
+                        _ => format!(" - {}", name),
+                    })
+                    .collect();
+
+                format!(
+                    "<strong>Characters:</strong><br/>{}",
+                    characters.join("<br/>")
+                )
+            }
+            CharacterAction::New(name) => {
+                ctx.db.create_character(user, room_id, name).await?;
+                ctx.db
+                    .set_active_character(user, room_id, Some(name))
+                    .await?;
+                format!("<strong>Created character:</strong> {}", name)
+            }
+            CharacterAction::Switch(name) => {
+                ctx.db
+                    .set_active_character(user, room_id, Some(name))
+                    .await?;
+                format!("<strong>Switched to character:</strong> {}", name)
+            }
+            CharacterAction::Clear => {
+                ctx.db.set_active_character(user, room_id, None).await?;
+                "<strong>No active character</strong>".to_string()
+            }
+            CharacterAction::Delete(name) => {
+                ctx.db.delete_character(user, room_id, name).await?;
+                format!("<strong>Deleted character:</strong> {}", name)
+            }
+        };
+
+        Execution::success(html)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_character_actions() {
+        let parse = |input: &str| {
+            CharacterCommand::try_from(input.to_owned())
+                .ok()
+                .map(|cmd| cmd.0)
+        };
+
+        assert_eq!(Some(CharacterAction::Show), parse(""));
+        assert_eq!(Some(CharacterAction::List), parse("list"));
+        assert_eq!(Some(CharacterAction::Clear), parse("clear"));
+        assert_eq!(
+            Some(CharacterAction::New("Anna Black".to_string())),
+            parse("new Anna Black")
+        );
+        assert_eq!(
+            Some(CharacterAction::Switch("Anna".to_string())),
+            parse("SWITCH Anna")
+        );
+        assert_eq!(
+            Some(CharacterAction::Delete("Anna".to_string())),
+            parse("delete Anna")
+        );
+        assert_eq!(None, parse("new"));
+        assert_eq!(None, parse("list Anna"));
+        assert_eq!(None, parse("rename Anna"));
+    }
+}
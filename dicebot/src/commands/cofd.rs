@@ -1,11 +1,36 @@
-use super::{Command, Execution, ExecutionResult};
+use super::{Command, CommandError, Execution, ExecutionResult};
 use crate::cofd::dice::{roll_pool, DicePool, DicePoolWithContext};
-use crate::cofd::parser::{create_chance_die, parse_dice_pool};
+use crate::cofd::mage::{cast_spell, Spell, SpellWithContext};
+use crate::cofd::parser::{create_chance_die, parse_dice_pool, parse_spell};
+use crate::cofd::resources::{self, Resource};
+use crate::cofd::werewolf::{get_form, set_form, Form};
 use crate::context::Context;
+use crate::db::Rooms;
 use crate::error::BotError;
+use crate::parser::dice::{Amount, Element, Operator};
+use crate::systems::GameSystem;
 use async_trait::async_trait;
 use std::convert::TryFrom;
 
+/// Get the game system of the active room, if one has been set.
+async fn room_game_system(ctx: &Context<'_>) -> Result<Option<GameSystem>, BotError> {
+    let system = ctx
+        .db
+        .get_game_system(ctx.active_room_id().as_str())
+        .await?;
+
+    Ok(system)
+}
+
+/// Refuse to execute splat-specific commands in rooms that are not
+/// playing that splat.
+async fn require_game_system(ctx: &Context<'_>, required: GameSystem) -> Result<(), BotError> {
+    match room_game_system(ctx).await? {
+        Some(system) if system == required => Ok(()),
+        _ => Err(BotError::WrongGameSystem(required.name().to_owned())),
+    }
+}
+
 pub struct PoolRollCommand(pub DicePool);
 
 impl PoolRollCommand {
@@ -24,6 +49,22 @@ impl TryFrom<String> for PoolRollCommand {
     }
 }
 
+/// In Werewolf rooms, the user's current form modifies any attribute
+/// variables used in the pool. Returns the form and its total
+/// modifier, if the form changes the pool at all.
+async fn form_bonus(ctx: &Context<'_>, pool: &DicePool) -> Result<Option<(Form, i32)>, BotError> {
+    if room_game_system(ctx).await? != Some(GameSystem::WerewolfTheForsaken) {
+        return Ok(None);
+    }
+
+    let bonus = get_form(ctx)
+        .await?
+        .map(|form| (form, form.pool_bonus(&pool.amounts)))
+        .filter(|&(_, bonus)| bonus != 0);
+
+    Ok(bonus)
+}
+
 #[async_trait]
 impl Command for PoolRollCommand {
     fn name(&self) -> &'static str {
@@ -35,14 +76,201 @@ impl Command for PoolRollCommand {
     }
 
     async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
-        let pool_with_ctx = DicePoolWithContext(&self.0, ctx);
-        let rolled_pool = roll_pool(&pool_with_ctx).await?;
+        let (rolled_pool, form) = match form_bonus(ctx, &self.0).await? {
+            Some((form, bonus)) => {
+                let mut amounts = self.0.amounts.clone();
+                amounts.push(Amount {
+                    operator: Operator::Plus,
+                    element: Element::Number(bonus),
+                });
+
+                let pool = DicePool::new(amounts, self.0.modifiers);
+                let pool_with_ctx = DicePoolWithContext(&pool, ctx);
+                let rolled_pool = roll_pool(&pool_with_ctx).await?;
+                (rolled_pool, format!(" ({} form: {:+})", form, bonus))
+            }
+            None => {
+                let pool_with_ctx = DicePoolWithContext(&self.0, ctx);
+                (roll_pool(&pool_with_ctx).await?, String::new())
+            }
+        };
+
+        let html = format!(
+            "<strong>Pool:</strong> {}{}</p><p><strong>Result</strong>: {}",
+            rolled_pool, form, rolled_pool.roll
+        );
+
+        Execution::success(html)
+    }
+}
+
+pub struct CastCommand(pub Spell);
+
+impl TryFrom<String> for CastCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let spell = parse_spell(&input)?;
+        Ok(CastCommand(spell))
+    }
+}
+
+#[async_trait]
+impl Command for CastCommand {
+    fn name(&self) -> &'static str {
+        "cast mage spell"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        require_game_system(ctx, GameSystem::MageTheAwakening).await?;
+
+        let spell_with_ctx = SpellWithContext(&self.0, ctx);
+        let cast = cast_spell(&spell_with_ctx).await?;
+
+        let mut html = format!(
+            "<strong>Spell:</strong> {}</p><p><strong>Result</strong>: {}",
+            cast, cast.spellcasting.roll
+        );
+
+        if let Some(paradox) = &cast.paradox {
+            html.push_str(&format!(
+                "</p><p><strong>Paradox:</strong> {}",
+                paradox.roll
+            ));
+        }
+
+        Execution::success(html)
+    }
+}
+
+pub struct FormCommand(pub Option<Form>);
+
+impl TryFrom<String> for FormCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(FormCommand(None));
+        }
+
+        let form = input
+            .parse()
+            .map_err(|_| CommandError::InvalidCommand(input.to_owned()))?;
+
+        Ok(FormCommand(Some(form)))
+    }
+}
+
+#[async_trait]
+impl Command for FormCommand {
+    fn name(&self) -> &'static str {
+        "change werewolf form"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        require_game_system(ctx, GameSystem::WerewolfTheForsaken).await?;
+
+        let form = match self.0 {
+            Some(form) => {
+                set_form(ctx, form).await?;
+                form
+            }
+            None => get_form(ctx).await?.unwrap_or(Form::Hishu),
+        };
+
+        Execution::success(format!("<strong>Form:</strong> {}", form))
+    }
+}
+
+pub struct SpendCommand(pub Resource, pub i32);
+
+impl TryFrom<String> for SpendCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let invalid = || CommandError::InvalidCommand(input.clone());
+        let parts: Vec<&str> = input.split_whitespace().collect();
+
+        let (resource, amount) = match parts[..] {
+            [resource] => (resource, 1),
+            [resource, amount] => {
+                let amount = amount
+                    .parse::<i32>()
+                    .ok()
+                    .filter(|&amount| amount > 0)
+                    .ok_or_else(invalid)?;
+                (resource, amount)
+            }
+            _ => return Err(invalid().into()),
+        };
+
+        let resource = Resource::parse(resource).ok_or_else(invalid)?;
+        Ok(SpendCommand(resource, amount))
+    }
+}
+
+#[async_trait]
+impl Command for SpendCommand {
+    fn name(&self) -> &'static str {
+        "spend splat resource"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        require_game_system(ctx, self.0.game_system()).await?;
+        let remaining = resources::spend(ctx, self.0, self.1).await?;
 
         let html = format!(
-            "<strong>Pool:</strong> {}</p><p><strong>Result</strong>: {}",
-            rolled_pool, rolled_pool.roll
+            "<strong>Spent {} {}:</strong> {} remaining",
+            self.1, self.0, remaining
         );
 
         Execution::success(html)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cofd::resources::Pillar;
+
+    #[test]
+    fn parses_spend_command() {
+        let parse = |input: &str| {
+            SpendCommand::try_from(input.to_owned())
+                .ok()
+                .map(|cmd| (cmd.0, cmd.1))
+        };
+
+        assert_eq!(Some((Resource::Mana, 1)), parse("mana"));
+        assert_eq!(Some((Resource::Pillar(Pillar::Ka), 2)), parse("ka 2"));
+        assert_eq!(None, parse("mana 0"));
+        assert_eq!(None, parse("vitae 1"));
+        assert_eq!(None, parse(""));
+    }
+
+    #[test]
+    fn parses_form_command() {
+        let parse = |input: &str| {
+            FormCommand::try_from(input.to_owned())
+                .ok()
+                .map(|cmd| cmd.0)
+        };
+
+        assert_eq!(Some(None), parse(""));
+        assert_eq!(Some(Some(Form::Gauru)), parse("gauru"));
+        assert_eq!(None, parse("wolf"));
+    }
+}
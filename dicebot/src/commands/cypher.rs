@@ -0,0 +1,109 @@
+use super::{Command, Execution, ExecutionResult};
+use crate::context::Context;
+use crate::cypher::dice::{roll_task, CypherTask, Stat};
+use crate::cypher::parser::parse_cypher_task;
+use crate::db::errors::DataError;
+use crate::db::Variables;
+use crate::error::BotError;
+use async_trait::async_trait;
+use std::convert::TryFrom;
+
+pub struct CypherRoll(pub CypherTask);
+
+impl TryFrom<String> for CypherRoll {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let task = parse_cypher_task(&input)?;
+        Ok(CypherRoll(task))
+    }
+}
+
+/// Get a stat variable (pool or Edge) from the active room, if the
+/// user has stored it.
+async fn get_stat_variable(ctx: &Context<'_>, variable: &str) -> Result<Option<i32>, BotError> {
+    let result = ctx
+        .db
+        .get_user_variable(ctx.username, ctx.active_room_id().as_str(), variable)
+        .await;
+
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(DataError::KeyDoesNotExist(_)) => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Pay for the task's effort from the stored pool of its stat. Edge
+/// is read from the stat's Edge variable, and counts as 0 if it is
+/// not stored. If the pool is not stored, nothing is deducted.
+/// Returns the cost, and the remaining pool if it was deducted.
+async fn spend_effort(
+    ctx: &Context<'_>,
+    task: &CypherTask,
+    stat: Stat,
+) -> Result<(u32, Option<i32>), BotError> {
+    let edge = get_stat_variable(ctx, stat.edge_variable()).await?;
+    let edge = edge.map(|edge| edge.max(0) as u32).unwrap_or(0);
+    let cost = task.effort_cost(edge);
+
+    let amount = i32::try_from(cost).map_err(|_| BotError::PoolTooLow(stat.to_string()))?;
+    let result = ctx
+        .db
+        .spend_user_variable(
+            ctx.username,
+            ctx.active_room_id().as_str(),
+            stat.pool_variable(),
+            amount,
+        )
+        .await;
+
+    let remaining = match result {
+        Ok((_, remaining)) => remaining,
+        Err(DataError::KeyDoesNotExist(_)) => return Ok((cost, None)),
+        Err(DataError::BelowMinimum(..)) => return Err(BotError::PoolTooLow(stat.to_string())),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok((cost, Some(remaining)))
+}
+
+#[async_trait]
+impl Command for CypherRoll {
+    fn name(&self) -> &'static str {
+        "roll cypher system task"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let effort = match self.0.stat {
+            Some(stat) if self.0.effort > 0 => {
+                Some((stat, spend_effort(ctx, &self.0, stat).await?))
+            }
+            _ => None,
+        };
+
+        let rolled = roll_task(&self.0);
+        let mut html = format!(
+            "<strong>Roll:</strong> {}</p><p><strong>Result</strong>: {}",
+            self.0, rolled
+        );
+
+        match effort {
+            Some((stat, (cost, Some(remaining)))) => html.push_str(&format!(
+                "</p><p><strong>Effort:</strong> spent {} {} ({} remaining)",
+                cost, stat, remaining
+            )),
+            Some((stat, (cost, None))) => html.push_str(&format!(
+                "</p><p><strong>Effort:</strong> costs {} {}",
+                cost, stat
+            )),
+            None => (),
+        }
+
+        Execution::success(html)
+    }
+}
@@ -0,0 +1,40 @@
+use super::{Command, Execution, ExecutionResult};
+use crate::context::Context;
+use crate::error::BotError;
+use crate::genesys::dice::{roll_pool, NarrativePool};
+use crate::genesys::parser::parse_narrative_pool;
+use async_trait::async_trait;
+use std::convert::TryFrom;
+
+pub struct NarrativeRoll(pub NarrativePool);
+
+impl TryFrom<String> for NarrativeRoll {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let pool = parse_narrative_pool(&input)?;
+        Ok(NarrativeRoll(pool))
+    }
+}
+
+#[async_trait]
+impl Command for NarrativeRoll {
+    fn name(&self) -> &'static str {
+        "roll narrative dice"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, _ctx: &Context<'_>) -> ExecutionResult {
+        let result = roll_pool(&self.0);
+
+        let html = format!(
+            "<strong>Pool:</strong> {}</p><p><strong>Result</strong>: {}",
+            self.0, result
+        );
+
+        Execution::success(html)
+    }
+}
@@ -0,0 +1,49 @@
+use super::{Command, Execution, ExecutionResult};
+use crate::context::Context;
+use crate::error::BotError;
+use crate::gurps::dice::{roll_gurps_check, GurpsCheck, GurpsCheckWithContext, RolledGurpsCheck};
+use crate::gurps::parser::parse_gurps_check;
+use async_trait::async_trait;
+use std::convert::TryFrom;
+
+pub struct GurpsRoll(pub GurpsCheck);
+
+impl TryFrom<String> for GurpsRoll {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let check = parse_gurps_check(&input)?;
+        Ok(GurpsRoll(check))
+    }
+}
+
+#[async_trait]
+impl Command for GurpsRoll {
+    fn name(&self) -> &'static str {
+        "roll gurps check"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let check_with_ctx = GurpsCheckWithContext(&self.0, ctx);
+        let rolled = roll_gurps_check(&check_with_ctx).await?;
+
+        let roll = match &rolled {
+            RolledGurpsCheck::Single(check) => format!("3d6 against skill {}", check.skill),
+            RolledGurpsCheck::Contest(first, second) => format!(
+                "quick contest of skill {} vs skill {}",
+                first.skill, second.skill
+            ),
+        };
+
+        let html = format!(
+            "<strong>Roll:</strong> {}</p><p><strong>Result</strong>: {}",
+            roll, rolled
+        );
+
+        Execution::success(html)
+    }
+}
@@ -0,0 +1,163 @@
+use super::{Attachment, Command, CommandError, Execution, ExecutionResult};
+use crate::context::Context;
+use crate::error::BotError;
+use crate::import_export::{self, ConflictMode, FileFormat, ImportError};
+use crate::matrix;
+use async_trait::async_trait;
+use std::convert::TryFrom;
+
+/// Export the user's variables in the active room as a file.
+pub struct ExportCommand(pub FileFormat);
+
+impl TryFrom<String> for ExportCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        match input.trim() {
+            "" => Ok(ExportCommand(FileFormat::Json)),
+            format => format
+                .parse()
+                .map(ExportCommand)
+                .map_err(|_| CommandError::InvalidCommand(input).into()),
+        }
+    }
+}
+
+#[async_trait]
+impl Command for ExportCommand {
+    fn name(&self) -> &'static str {
+        "export variables"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let room_id = ctx.active_room_id();
+        let variables =
+            import_export::load_variables(&ctx.db, ctx.username, room_id.as_str()).await?;
+
+        let data = import_export::serialize(&variables, self.0)?;
+        let attachment = Attachment {
+            file_name: format!("variables.{}", self.0),
+            content_type: self.0.mime_type(),
+            data: data.into_bytes(),
+        };
+
+        let html = format!(
+            "<strong>Exported:</strong> {} variables from {}",
+            variables.len(),
+            ctx.active_room.display_name
+        );
+
+        Execution::success_with_file(html, attachment)
+    }
+}
+
+/// Import variables from a file into the active room. The command
+/// message must be a reply to the file message.
+pub struct ImportCommand {
+    pub mode: ConflictMode,
+    pub dry_run: bool,
+    pub format: Option<FileFormat>,
+}
+
+impl TryFrom<String> for ImportCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let mut command = ImportCommand {
+            mode: ConflictMode::default(),
+            dry_run: false,
+            format: None,
+        };
+
+        for part in input.split_whitespace() {
+            if let Ok(mode) = part.parse() {
+                command.mode = mode;
+            } else if let Ok(format) = part.parse() {
+                command.format = Some(format);
+            } else if part == "dry-run" || part == "dryrun" {
+                command.dry_run = true;
+            } else {
+                return Err(CommandError::InvalidCommand(input).into());
+            }
+        }
+
+        Ok(command)
+    }
+}
+
+#[async_trait]
+impl Command for ImportCommand {
+    fn name(&self) -> &'static str {
+        "import variables"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let event_id = ctx.in_reply_to.as_ref().ok_or(BotError::NotAFileMessage)?;
+        let (file_name, data) = matrix::download_file(&ctx.matrix_client, ctx.room_id(), event_id)
+            .await?
+            .ok_or(BotError::NotAFileMessage)?;
+
+        let format = self
+            .format
+            .or_else(|| FileFormat::from_file_name(&file_name))
+            .ok_or_else(|| ImportError::UnknownFormat(file_name.clone()))?;
+
+        let data = String::from_utf8(data).map_err(|_| ImportError::NotText)?;
+        let variables = import_export::deserialize(&data, format)?;
+
+        let room_id = ctx.active_room_id();
+        let summary = import_export::import_variables(
+            &ctx.db,
+            ctx.username,
+            room_id.as_str(),
+            &variables,
+            self.mode,
+            self.dry_run,
+        )
+        .await?;
+
+        let title = match self.dry_run {
+            true => "Import (dry run)",
+            false => "Imported",
+        };
+
+        let html = format!("<strong>{}:</strong> {}", title, summary);
+        Execution::success(html)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_import_options() {
+        let cmd = ImportCommand::try_from("overwrite dry-run csv".to_owned()).unwrap();
+        assert_eq!(ConflictMode::Overwrite, cmd.mode);
+        assert_eq!(Some(FileFormat::Csv), cmd.format);
+        assert!(cmd.dry_run);
+
+        let cmd = ImportCommand::try_from("".to_owned()).unwrap();
+        assert_eq!(ConflictMode::Merge, cmd.mode);
+        assert_eq!(None, cmd.format);
+        assert!(!cmd.dry_run);
+
+        assert!(ImportCommand::try_from("everything".to_owned()).is_err());
+    }
+
+    #[test]
+    fn parses_export_format() {
+        let format = |input: &str| ExportCommand::try_from(input.to_owned()).map(|cmd| cmd.0);
+        assert!(matches!(format(""), Ok(FileFormat::Json)));
+        assert!(matches!(format("TOML"), Ok(FileFormat::Toml)));
+        assert!(format("xml").is_err());
+    }
+}
@@ -0,0 +1,47 @@
+use super::{Command, Execution, ExecutionResult};
+use crate::context::Context;
+use crate::error::BotError;
+use crate::l5r::dice::{roll_and_keep, RollAndKeep};
+use crate::l5r::parser::parse_roll_and_keep;
+use async_trait::async_trait;
+use std::convert::TryFrom;
+
+pub struct L5rRoll(pub RollAndKeep);
+
+impl TryFrom<String> for L5rRoll {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let roll = parse_roll_and_keep(&input)?;
+        Ok(L5rRoll(roll))
+    }
+}
+
+#[async_trait]
+impl Command for L5rRoll {
+    fn name(&self) -> &'static str {
+        "roll and keep l5r dice"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, _ctx: &Context<'_>) -> ExecutionResult {
+        let rolled = roll_and_keep(&self.0);
+
+        // Show the converted roll when the ten dice rule changed it.
+        let roll = if rolled.roll != self.0 {
+            format!("{} (as {})", self.0, rolled.roll)
+        } else {
+            self.0.to_string()
+        };
+
+        let html = format!(
+            "<strong>Roll:</strong> {}</p><p><strong>Result</strong>: {}",
+            roll, rolled
+        );
+
+        Execution::success(html)
+    }
+}
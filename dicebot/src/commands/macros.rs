@@ -0,0 +1,326 @@
+use super::parser::{command_name, is_builtin_command};
+use super::{Command, CommandError, Execution, ExecutionResult};
+use crate::context::Context;
+use crate::db::errors::DataError;
+use crate::db::sqlite::Database;
+use crate::db::Macros;
+use crate::error::BotError;
+use crate::models::{Macro, MacroScope};
+use crate::parser::variables::room_variable_name;
+use async_trait::async_trait;
+use std::convert::TryFrom;
+
+/// How deeply macros can run other macros, which stops macros that
+/// (directly or not) run themselves.
+pub const MAX_MACRO_DEPTH: usize = 5;
+
+/// The subcommand of !macro.
+#[derive(Debug, PartialEq, Eq)]
+pub enum MacroAction {
+    Add { name: String, commands: Vec<String> },
+    Delete(String),
+    List,
+}
+
+pub struct MacroCommand(pub MacroAction);
+
+/// Whether a command line defines a macro. A macro definition takes
+/// the rest of the message as the commands of the macro.
+pub fn is_macro_definition(line: &str) -> bool {
+    let mut words = line.split_whitespace();
+    let command = words.next().unwrap_or_default();
+    let action = words.next().unwrap_or_default();
+    command.eq_ignore_ascii_case("!macro") && action.eq_ignore_ascii_case("add")
+}
+
+/// Strip a trailing " # comment" from a command line of a macro.
+fn strip_comment(line: &str) -> &str {
+    line.split(" # ").next().unwrap_or(line).trim()
+}
+
+/// Check a macro name, without its room. prefix. Names are letters
+/// and digits, so that they can be run like any other command, and
+/// must not hide one of the bot's own commands.
+fn validate_name(input: &str, name: &str) -> Result<(), BotError> {
+    let valid = matches!(name.chars().next(), Some(c) if c.is_alphabetic())
+        && name.chars().all(char::is_alphanumeric);
+
+    match valid {
+        true if is_builtin_command(name) => Err(BotError::MacroNameTaken(name.to_lowercase())),
+        true => Ok(()),
+        false => Err(CommandError::InvalidCommand(input.to_owned()).into()),
+    }
+}
+
+/// The scope and name of a macro, from a name with or without the
+/// room. prefix.
+fn scoped_name(name: &str) -> (MacroScope, &str) {
+    match room_variable_name(name) {
+        Some(room_name) => (MacroScope::Room, room_name),
+        None => (MacroScope::User, name),
+    }
+}
+
+impl TryFrom<String> for MacroCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let invalid = || CommandError::InvalidCommand(input.clone());
+        let trimmed = input.trim();
+        let (action, rest) = trimmed
+            .split_once(char::is_whitespace)
+            .unwrap_or((trimmed, ""));
+
+        let action = match action.to_lowercase().as_str() {
+            "list" if rest.is_empty() => MacroAction::List,
+            "del" | "delete" if !rest.contains(char::is_whitespace) => {
+                validate_name(&input, scoped_name(rest).1)?;
+                MacroAction::Delete(rest.to_owned())
+            }
+            "add" => {
+                let rest = rest.trim_start();
+                let (name, commands) = rest.split_once(char::is_whitespace).ok_or_else(invalid)?;
+
+                validate_name(&input, scoped_name(name).1)?;
+
+                let commands: Vec<String> = commands
+                    .lines()
+                    .map(str::trim)
+                    .filter(|line| !line.is_empty())
+                    .map(str::to_owned)
+                    .collect();
+
+                if commands.is_empty() || !commands.iter().all(|cmd| cmd.starts_with('!')) {
+                    return Err(invalid().into());
+                }
+
+                MacroAction::Add {
+                    name: name.to_owned(),
+                    commands,
+                }
+            }
+            _ => return Err(invalid().into()),
+        };
+
+        Ok(MacroCommand(action))
+    }
+}
+
+/// Owner of a macro in the given scope: the user, or the active room.
+fn macro_owner<'a>(ctx: &'a Context<'_>, scope: MacroScope) -> &'a str {
+    match scope {
+        MacroScope::User => ctx.username,
+        MacroScope::Room => ctx.active_room_id().as_str(),
+    }
+}
+
+fn list_macros(macros: &[Macro], scope: MacroScope) -> String {
+    let macros: Vec<String> = macros
+        .iter()
+        .filter(|m| m.scope == scope)
+        .map(|m| format!("!{}: {}", m.name, m.commands.join("; ")))
+        .collect();
+
+    macros.join("<br/>")
+}
+
+#[async_trait]
+impl Command for MacroCommand {
+    fn name(&self) -> &'static str {
+        "macro"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    fn changes_room(&self) -> bool {
+        let name = match &self.0 {
+            MacroAction::Add { name, .. } | MacroAction::Delete(name) => name,
+            MacroAction::List => return false,
+        };
+
+        scoped_name(name).0 == MacroScope::Room
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        match &self.0 {
+            MacroAction::Add { name, commands } => {
+                let (scope, name) = scoped_name(name);
+                let owner = macro_owner(ctx, scope);
+                ctx.db.set_macro(scope, owner, name, commands).await?;
+
+                let html = format!(
+                    "<strong>Macro saved:</strong> !{} ({} commands)",
+                    name.to_lowercase(),
+                    commands.len()
+                );
+
+                Execution::success(html)
+            }
+            MacroAction::Delete(name) => {
+                let (scope, name) = scoped_name(name);
+                let owner = macro_owner(ctx, scope);
+                let value = match ctx.db.delete_macro(scope, owner, name).await {
+                    Ok(()) => format!("!{} deleted", name.to_lowercase()),
+                    Err(DataError::KeyDoesNotExist(_)) => {
+                        format!("!{} does not exist", name.to_lowercase())
+                    }
+                    Err(e) => return Err(e.into()),
+                };
+
+                Execution::success(format!("<strong>Remove Macro:</strong> {}", value))
+            }
+            MacroAction::List => {
+                let room_id = ctx.active_room_id().as_str();
+                let macros = ctx.db.get_macros(ctx.username, room_id).await?;
+
+                let mut html = format!(
+                    "<strong>Macros:</strong><br/>{}",
+                    list_macros(&macros, MacroScope::User)
+                );
+
+                let room_macros = list_macros(&macros, MacroScope::Room);
+                if !room_macros.is_empty() {
+                    html.push_str("<br/><strong>Room Macros:</strong><br/>");
+                    html.push_str(&room_macros);
+                }
+
+                Execution::success(html)
+            }
+        }
+    }
+}
+
+/// Replace every command line that runs a macro with the commands of
+/// the macro, which can themselves run other macros up to
+/// MAX_MACRO_DEPTH deep. The user's macros are used before the macros
+/// of the room. Fails if there would be more than max_commands
+/// commands.
+pub async fn expand_macros(
+    db: &Database,
+    user: &str,
+    room_id: &str,
+    lines: Vec<String>,
+    max_commands: usize,
+) -> Result<Vec<String>, BotError> {
+    let mut expanded = vec![];
+
+    // Lines still to expand, in reverse order, with how many macros
+    // deep they are.
+    let mut pending: Vec<(String, usize)> = lines.into_iter().rev().map(|l| (l, 0)).collect();
+
+    while let Some((line, depth)) = pending.pop() {
+        let name = command_name(&line).filter(|name| !is_builtin_command(name));
+        let found = match &name {
+            Some(name) => db.get_macro(user, room_id, name).await?,
+            None => None,
+        };
+
+        match found {
+            Some(found) if depth >= MAX_MACRO_DEPTH => {
+                return Err(BotError::MacroTooDeep(found.name));
+            }
+            Some(found) => {
+                for command in found.commands.iter().rev() {
+                    pending.push((strip_comment(command).to_owned(), depth + 1));
+                }
+            }
+            None => expanded.push(line),
+        }
+
+        if expanded.len() + pending.len() > max_commands {
+            return Err(BotError::MessageTooLarge);
+        }
+    }
+
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_add_macro() {
+        let cmd = MacroCommand::try_from(
+            "add Attack !roll 1d20+{atk} # attack\n  !roll 1d8+{str}\n".to_owned(),
+        )
+        .expect("was error");
+
+        assert_eq!(
+            MacroAction::Add {
+                name: "Attack".to_owned(),
+                commands: vec![
+                    "!roll 1d20+{atk} # attack".to_owned(),
+                    "!roll 1d8+{str}".to_owned()
+                ],
+            },
+            cmd.0
+        );
+    }
+
+    #[test]
+    fn macro_names_must_not_be_commands() {
+        let result = MacroCommand::try_from("add room.r !roll 1d4".to_owned());
+        assert!(matches!(result, Err(BotError::MacroNameTaken(_))));
+
+        let result = MacroCommand::try_from("add my_attack !roll 1d4".to_owned());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn comments_are_stripped() {
+        assert_eq!(
+            "!roll 1d20+{atk}",
+            strip_comment("!roll 1d20+{atk} # attack")
+        );
+        assert_eq!("!roll 1d20#2", strip_comment("!roll 1d20#2"));
+    }
+
+    async fn with_db<Fut>(f: impl FnOnce(Database) -> Fut)
+    where
+        Fut: std::future::Future<Output = ()>,
+    {
+        let db_path = tempfile::NamedTempFile::new_in(".").unwrap();
+        let db = Database::new(db_path.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        f(db).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn expands_nested_macros() {
+        with_db(|db| async move {
+            let attack = vec!["!roll 1d20 # to hit".to_owned(), "!damage".to_owned()];
+            let damage = vec!["!roll 1d8".to_owned()];
+            db.set_macro(MacroScope::User, "user", "attack", &attack)
+                .await
+                .unwrap();
+            db.set_macro(MacroScope::Room, "room", "damage", &damage)
+                .await
+                .unwrap();
+
+            let lines = vec!["!Attack".to_owned(), "!get hp".to_owned()];
+            let expanded = expand_macros(&db, "user", "room", lines, 50).await.unwrap();
+            assert_eq!(vec!["!roll 1d20", "!roll 1d8", "!get hp"], expanded);
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn recursive_macros_are_too_deep() {
+        with_db(|db| async move {
+            let forever = vec!["!forever".to_owned()];
+            db.set_macro(MacroScope::User, "user", "forever", &forever)
+                .await
+                .unwrap();
+
+            let lines = vec!["!forever".to_owned()];
+            let result = expand_macros(&db, "user", "room", lines, 50).await;
+            assert!(matches!(result, Err(BotError::MacroTooDeep(_))));
+        })
+        .await;
+    }
+}
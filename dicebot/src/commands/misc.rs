@@ -1,21 +1,44 @@
+use super::registry::{find_command, general_help, suggest_command};
 use super::{Command, Execution, ExecutionResult};
 use crate::context::Context;
 use crate::error::BotError;
-use crate::help::{parse_help_topic, HelpTopic};
+use crate::help::parse_help_topic;
 use async_trait::async_trait;
 use std::convert::TryFrom;
 
-pub struct HelpCommand(pub Option<HelpTopic>);
+/// Help about a command or topic, or general help if the topic is
+/// empty.
+pub struct HelpCommand(pub String);
 
 impl TryFrom<String> for HelpCommand {
     type Error = BotError;
 
     fn try_from(input: String) -> Result<Self, Self::Error> {
-        let topic = parse_help_topic(&input);
+        let topic = input.trim().trim_start_matches('!').to_lowercase();
         Ok(HelpCommand(topic))
     }
 }
 
+/// Help text for a command or topic. Commands come before help
+/// topics of the same name.
+fn help_message(topic: &str) -> String {
+    if topic.is_empty() {
+        return general_help();
+    }
+
+    match (find_command(topic), parse_help_topic(topic)) {
+        (Some(spec), _) => spec.help(),
+        (None, Some(topic)) => topic.message().to_owned(),
+        (None, None) => match suggest_command(topic) {
+            Some(name) => format!(
+                "There is no help for this topic. Did you mean !help {}?",
+                name
+            ),
+            None => "There is no help for this topic".to_owned(),
+        },
+    }
+}
+
 #[async_trait]
 impl Command for HelpCommand {
     fn name(&self) -> &'static str {
@@ -27,12 +50,22 @@ impl Command for HelpCommand {
     }
 
     async fn execute(&self, _ctx: &Context<'_>) -> ExecutionResult {
-        let help = match &self.0 {
-            Some(topic) => topic.message(),
-            _ => "There is no help for this topic",
-        };
-
+        let help = help_message(&self.0);
         let html = format!("<strong>Help:</strong> {}", help.replace("\n", "<br/>"));
         Execution::success(html)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn help_for_every_command() {
+        assert!(help_message("").contains("!variables: "));
+        assert!(help_message("link").contains("Syntax: !link <password>"));
+        assert!(help_message("rk").starts_with("!l5r, !rk: "));
+        assert!(help_message("templates").contains("{"));
+        assert!(help_message("cthrol").contains("!help cthroll"));
+    }
+}
@@ -1,17 +1,32 @@
 use crate::context::Context;
 use crate::error::BotError;
+use crate::templating;
 use async_trait::async_trait;
 use log::{error, info};
 use thiserror::Error;
 
 pub mod basic_rolling;
+pub mod characters;
 pub mod cofd;
 pub mod cthulhu;
+pub mod cypher;
+pub mod genesys;
+pub mod gurps;
+pub mod import_export;
+pub mod l5r;
+pub mod macros;
 pub mod management;
 pub mod misc;
 pub mod parser;
+pub mod permissions;
+pub mod registry;
 pub mod rooms;
+pub mod savage_worlds;
+pub mod shadowrun;
+pub mod sheet;
 pub mod variables;
+pub mod wfrp;
+pub mod year_zero;
 
 /// A custom error type specifically related to parsing command text.
 /// Does not wrap an execution failure.
@@ -33,17 +48,43 @@ pub enum CommandError {
 #[derive(Debug)]
 pub struct Execution {
     html: String,
+    attachment: Option<Attachment>,
 }
 
 impl Execution {
     pub fn success(html: String) -> ExecutionResult {
-        Ok(Execution { html })
+        Ok(Execution {
+            html,
+            attachment: None,
+        })
+    }
+
+    /// A successful response that also uploads a file to the room.
+    pub fn success_with_file(html: String, attachment: Attachment) -> ExecutionResult {
+        Ok(Execution {
+            html,
+            attachment: Some(attachment),
+        })
     }
 
     /// Response message in HTML.
     pub fn html(&self) -> String {
         self.html.clone()
     }
+
+    /// File to send along with the response message, if any.
+    pub fn attachment(&self) -> Option<&Attachment> {
+        self.attachment.as_ref()
+    }
+}
+
+/// A file sent to the room as the result of a command (e.g. exported
+/// variables).
+#[derive(Debug, Clone)]
+pub struct Attachment {
+    pub file_name: String,
+    pub content_type: mime::Mime,
+    pub data: Vec<u8>,
 }
 
 /// Wraps either a successful command execution response, or an error
@@ -96,13 +137,20 @@ pub trait Command: Send + Sync {
     async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult;
     fn name(&self) -> &'static str;
     fn is_secure(&self) -> bool;
+
+    /// Whether running the command changes the active room for
+    /// everyone: its settings, room variables or room macros. These
+    /// changes need the room role of the command.
+    fn changes_room(&self) -> bool {
+        false
+    }
 }
 
-/// Determine if we are allowed to execute this command. Currently the
-/// rules are that secure commands must be executed in secure rooms
+/// Determine if we are allowed to execute this command in this kind
+/// of room. Secure commands must be executed in secure rooms
 /// (encrypted + direct), and anything else can be executed where
-/// ever. Later, we can add stuff like admin/regular user power
-/// separation, etc.
+/// ever. Whether the user has a high enough role to run the command
+/// is checked by permissions::check_permission.
 fn execution_allowed(cmd: &(impl Command + ?Sized), ctx: &Context<'_>) -> Result<(), CommandError> {
     match cmd {
         cmd if cmd.is_secure() && ctx.is_secure() => Ok(()),
@@ -115,10 +163,14 @@ fn execution_allowed(cmd: &(impl Command + ?Sized), ctx: &Context<'_>) -> Result
 /// go back to Matrix, if the command was executed, whether or not the
 /// command was successful.
 pub async fn execute_command(ctx: &Context<'_>) -> ExecutionResult {
-    let cmd = parser::parse_command(&ctx.message_body)?;
+    let input = templating::expand_variables(ctx).await?;
+    let (spec, cmd) = parser::parse_command_spec(&input)?;
 
     let result = match execution_allowed(cmd.as_ref(), ctx) {
-        Ok(_) => cmd.execute(ctx).await,
+        Ok(_) => match permissions::check_permission(spec, cmd.as_ref(), ctx).await {
+            Ok(_) => cmd.execute(ctx).await,
+            Err(e) => Err(e),
+        },
         Err(e) => Err(e.into()),
     };
 
@@ -200,6 +252,8 @@ mod tests {
             active_room: secure_room!(),
             username: "myusername",
             message_body: "!notacommand",
+            in_reply_to: None,
+            bot_admin: false,
         };
 
         let cmd = RegisterCommand;
@@ -223,6 +277,8 @@ mod tests {
             active_room: secure_room!(),
             username: "myusername",
             message_body: "!notacommand",
+            in_reply_to: None,
+            bot_admin: false,
         };
 
         let cmd = variables::GetVariableCommand("".to_owned());
@@ -246,6 +302,8 @@ mod tests {
             active_room: dummy_room!(),
             username: "myusername",
             message_body: "!notacommand",
+            in_reply_to: None,
+            bot_admin: false,
         };
 
         let cmd = variables::GetVariableCommand("".to_owned());
@@ -269,6 +327,8 @@ mod tests {
             active_room: dummy_room!(),
             username: "myusername",
             message_body: "!notacommand",
+            in_reply_to: None,
+            bot_admin: false,
         };
 
         let cmd = RegisterCommand;
@@ -292,6 +352,8 @@ mod tests {
             active_room: dummy_room!(),
             username: "myusername",
             message_body: "!notacommand",
+            in_reply_to: None,
+            bot_admin: false,
         };
 
         let result = execute_command(&ctx).await;
@@ -3,28 +3,18 @@
  * governed by the terms of the MIT license, from the original
  * axfive-matrix-dicebot project.
  */
-use crate::commands::{
-    basic_rolling::RollCommand,
-    cofd::PoolRollCommand,
-    cthulhu::{CthAdvanceRoll, CthRoll},
-    management::{CheckCommand, LinkCommand, RegisterCommand, UnlinkCommand, UnregisterCommand},
-    misc::HelpCommand,
-    rooms::{ListRoomsCommand, SetRoomCommand},
-    variables::{
-        DeleteVariableCommand, GetAllVariablesCommand, GetVariableCommand, SetVariableCommand,
-    },
-    Command,
-};
+use crate::commands::registry::{find_command, suggest_command, CommandSpec};
+use crate::commands::Command;
 use crate::error::BotError;
-use combine::parser::char::{char, letter, space};
+use crate::suggestions::did_you_mean;
+use combine::parser::char::{alpha_num, char, space};
 use combine::{any, many1, optional, Parser};
-use std::convert::TryFrom;
 use thiserror::Error;
 
 #[derive(Debug, Clone, PartialEq, Error)]
 pub enum CommandParsingError {
-    #[error("unrecognized command: {0}")]
-    UnrecognizedCommand(String),
+    #[error("unrecognized command: {0}{}", did_you_mean("!", .1))]
+    UnrecognizedCommand(String, Option<&'static str>),
 
     #[error("parser error: {0}")]
     InternalParseError(#[from] combine::error::StringStreamError),
@@ -38,7 +28,7 @@ fn split_command(input: &str) -> Result<(String, String), CommandParsingError> {
     let input = input.trim();
 
     let exclamation = char('!');
-    let word = many1(letter()).map(|value: String| value);
+    let word = many1(alpha_num()).map(|value: String| value);
     let at_least_one_space = many1(space().silent()).map(|value: String| value);
     let cmd_input = optional(at_least_one_space.and(many1(any()).map(|value: String| value)));
 
@@ -61,41 +51,40 @@ fn split_command(input: &str) -> Result<(String, String), CommandParsingError> {
     Ok((command, command_input))
 }
 
-/// Atempt to convert text input to a Boxed command type. Shortens
-/// boilerplate.
-macro_rules! convert_to {
-    ($type:ident, $input: expr) => {
-        $type::try_from($input).map(|cmd| Box::new(cmd) as Box<dyn Command>)
-    };
+/// The lowercase command word of a command line (e.g. "roll" for
+/// "!roll 1d4"), if the line is a command.
+pub fn command_name(input: &str) -> Option<String> {
+    split_command(input)
+        .ok()
+        .map(|(command, _)| command.to_lowercase())
+}
+
+/// Whether the command word is one of the bot's own commands, rather
+/// than e.g. a macro.
+pub fn is_builtin_command(name: &str) -> bool {
+    find_command(name).is_some()
 }
 
 /// Potentially parse a command expression. If we recognize the
 /// command, an error should be raised if the command is misparsed. If
-/// we don't recognize the command, return an error.
+/// we don't recognize the command, return an error, suggesting the
+/// closest command if there is one.
 pub fn parse_command(input: &str) -> Result<Box<dyn Command>, BotError> {
-    match split_command(input) {
-        Ok((cmd, cmd_input)) => match cmd.to_lowercase().as_ref() {
-            "variables" => convert_to!(GetAllVariablesCommand, cmd_input),
-            "get" => convert_to!(GetVariableCommand, cmd_input),
-            "set" => convert_to!(SetVariableCommand, cmd_input),
-            "del" => convert_to!(DeleteVariableCommand, cmd_input),
-            "r" | "roll" => convert_to!(RollCommand, cmd_input),
-            "rp" | "pool" => convert_to!(PoolRollCommand, cmd_input),
-            "chance" => PoolRollCommand::chance_die().map(|cmd| Box::new(cmd) as Box<dyn Command>),
-            "cthroll" => convert_to!(CthRoll, cmd_input),
-            "cthadv" | "ctharoll" => convert_to!(CthAdvanceRoll, cmd_input),
-            "help" => convert_to!(HelpCommand, cmd_input),
-            "register" => convert_to!(RegisterCommand, cmd_input),
-            "link" => convert_to!(LinkCommand, cmd_input),
-            "unlink" => convert_to!(UnlinkCommand, cmd_input),
-            "check" => convert_to!(CheckCommand, cmd_input),
-            "unregister" => convert_to!(UnregisterCommand, cmd_input),
-            "rooms" => convert_to!(ListRoomsCommand, cmd_input),
-            "room" => convert_to!(SetRoomCommand, cmd_input),
-            _ => Err(CommandParsingError::UnrecognizedCommand(cmd).into()),
-        },
-        //All other errors passed up.
-        Err(e) => Err(e.into()),
+    parse_command_spec(input).map(|(_, cmd)| cmd)
+}
+
+/// Parse a command expression like parse_command, along with the
+/// spec of the command, which holds e.g. the role needed to run it.
+pub fn parse_command_spec(
+    input: &str,
+) -> Result<(&'static CommandSpec, Box<dyn Command>), BotError> {
+    let (cmd, cmd_input) = split_command(input)?;
+    match find_command(&cmd) {
+        Some(spec) => Ok((spec, spec.parse(cmd_input)?)),
+        None => {
+            let suggestion = suggest_command(&cmd);
+            Err(CommandParsingError::UnrecognizedCommand(cmd, suggestion).into())
+        }
     }
 }
 
@@ -240,6 +229,170 @@ mod tests {
         parse_command("   !roll 1d4 + 5d6 -3   ").expect("was error");
     }
 
+    #[test]
+    fn savage_worlds_roll_test() {
+        parse_command("!sw d8").expect("was error");
+        parse_command("!sw d8 +1 6").expect("was error");
+        assert!(parse_command("!sw d9").is_err());
+    }
+
+    #[test]
+    fn cofd_splat_commands_test() {
+        parse_command("!cast forces 3 reach 2").expect("was error");
+        parse_command("!form gauru").expect("was error");
+        parse_command("!spend pyros 2").expect("was error");
+        assert!(parse_command("!cast forces").is_err());
+        assert!(parse_command("!spend vitae").is_err());
+    }
+
+    #[test]
+    fn game_system_test() {
+        parse_command("!system").expect("was error");
+        parse_command("!system mage").expect("was error");
+        assert!(parse_command("!system monopoly").is_err());
+    }
+
+    #[test]
+    fn character_test() {
+        parse_command("!char").expect("was error");
+        parse_command("!char new Anna").expect("was error");
+        parse_command("!character switch Anna").expect("was error");
+        assert!(parse_command("!char new").is_err());
+    }
+
+    #[test]
+    fn adjust_variable_test() {
+        parse_command("!inc hp").expect("was error");
+        parse_command("!dec ammo 3").expect("was error");
+        parse_command("!set hp -= 3").expect("was error");
+        parse_command("!bounds hp min 0 max 10 refuse").expect("was error");
+        assert!(parse_command("!dec ammo three").is_err());
+        assert!(parse_command("!bounds hp min").is_err());
+    }
+
+    #[test]
+    fn variables_subcommand_test() {
+        parse_command("!variables").expect("was error");
+        parse_command("!variables copy \"Old Game\" new").expect("was error");
+        parse_command("!variables migrate").expect("was error");
+        parse_command("!variables history hp @player:example.com").expect("was error");
+        parse_command("!undo").expect("was error");
+        assert!(parse_command("!variables copy").is_err());
+        assert!(parse_command("!variables everything").is_err());
+        assert!(parse_command("!variables history").is_err());
+    }
+
+    #[test]
+    fn macro_test() {
+        parse_command("!macro list").expect("was error");
+        parse_command("!macro add attack !roll 1d20+{atk}\n!roll 1d8+{str}").expect("was error");
+        parse_command("!macro del room.attack").expect("was error");
+        assert!(parse_command("!macro add roll !roll 1d20").is_err());
+        assert!(parse_command("!macro add attack roll 1d20").is_err());
+    }
+
+    #[test]
+    fn inline_rolls_test() {
+        parse_command("!inline").expect("was error");
+        parse_command("!inline off").expect("was error");
+        parse_command("!inline markers (( ))").expect("was error");
+        assert!(parse_command("!inline markers ((").is_err());
+        assert!(parse_command("!inline markers <<<<< >>>>>").is_err());
+    }
+
+    #[test]
+    fn prefix_test() {
+        parse_command("!prefix").expect("was error");
+        parse_command("!prefix .").expect("was error");
+        parse_command("!prefix default").expect("was error");
+        assert!(parse_command("!prefix . d").is_err());
+        assert!(parse_command("!prefix ......").is_err());
+    }
+
+    #[test]
+    fn builtin_command_test() {
+        assert_eq!(Some("roll".to_owned()), command_name("!Roll 1d4"));
+        assert_eq!(None, command_name("roll 1d4"));
+        assert!(is_builtin_command("set"));
+        assert!(is_builtin_command("char"));
+        assert!(!is_builtin_command("attack"));
+    }
+
+    #[test]
+    fn unrecognized_command_suggestion_test() {
+        let message = parse_command("!cthrol 50").err().map(|e| e.to_string());
+        assert_eq!(
+            Some("command parsing error: unrecognized command: cthrol (did you mean !cthroll?)"),
+            message.as_deref()
+        );
+    }
+
+    #[test]
+    fn cypher_roll_test() {
+        parse_command("!cypher 4").expect("was error");
+        parse_command("!cypher 5 effort 1 asset 1 skill trained might").expect("was error");
+        assert!(parse_command("!cypher 12").is_err());
+    }
+
+    #[test]
+    fn narrative_roll_test() {
+        parse_command("!narr 2g1y2p1k").expect("was error");
+        parse_command("!genesys 2 ability 1 difficulty").expect("was error");
+        assert!(parse_command("!narr 2x").is_err());
+    }
+
+    #[test]
+    fn gurps_roll_test() {
+        parse_command("!gurps 12").expect("was error");
+        parse_command("!gurps broadsword - 2 vs 14").expect("was error");
+        assert!(parse_command("!gurps 12 14").is_err());
+    }
+
+    #[test]
+    fn l5r_roll_test() {
+        parse_command("!l5r 6k3").expect("was error");
+        parse_command("!rk 7k4 +5 tn 20 raises 1").expect("was error");
+        assert!(parse_command("!l5r 6").is_err());
+    }
+
+    #[test]
+    fn shadowrun_roll_test() {
+        parse_command("!sr 12").expect("was error");
+        parse_command("!sr agility + firearms e").expect("was error");
+        assert!(parse_command("!sr 8 ec").is_err());
+    }
+
+    #[test]
+    fn import_export_test() {
+        parse_command("!export").expect("was error");
+        parse_command("!export csv").expect("was error");
+        parse_command("!import skip dry-run").expect("was error");
+        assert!(parse_command("!export pdf").is_err());
+        assert!(parse_command("!import everything").is_err());
+    }
+
+    #[test]
+    fn sheet_test() {
+        parse_command("!sheet").expect("was error");
+        parse_command("!sheet init").expect("was error");
+        assert!(parse_command("!sheet burn").is_err());
+    }
+
+    #[test]
+    fn wfrp_roll_test() {
+        parse_command("!wfrp 45").expect("was error");
+        parse_command("!wfrp melee + 20 vs 38").expect("was error");
+        assert!(parse_command("!wfrp 45 38").is_err());
+    }
+
+    #[test]
+    fn year_zero_roll_test() {
+        parse_command("!yze 3 2 1").expect("was error");
+        parse_command("!yze 3 stress 2").expect("was error");
+        parse_command("!push").expect("was error");
+        assert!(parse_command("!yze").is_err());
+    }
+
     #[test]
     fn case_insensitive_test() {
         parse_command("!CTHROLL 40").expect("command parsing is not case sensitive.");
@@ -0,0 +1,250 @@
+use super::registry::{find_command, suggest_command, CommandSpec};
+use super::{Command, CommandError, Execution, ExecutionResult};
+use crate::commands::parser::CommandParsingError;
+use crate::context::Context;
+use crate::db::Rooms;
+use crate::error::BotError;
+use crate::matrix;
+use crate::models::Role;
+use async_trait::async_trait;
+use matrix_sdk::ruma::UserId;
+use std::convert::TryFrom;
+
+/// The role of the user in the active room: bot admin if the user is
+/// listed in the config, otherwise from the user's power level.
+pub async fn user_role(ctx: &Context<'_>) -> Result<Role, BotError> {
+    if ctx.bot_admin {
+        return Ok(Role::BotAdmin);
+    }
+
+    let user = UserId::try_from(ctx.username)?;
+    let power_level =
+        matrix::get_power_level(&ctx.matrix_client, ctx.active_room_id(), &user).await?;
+
+    Ok(Role::from_power_level(power_level))
+}
+
+/// The role needed to run a command in the active room: the role set
+/// by the room admins, or the default role of the command. Commands
+/// that change the room need the room role by default.
+async fn required_role(
+    spec: &CommandSpec,
+    cmd: &(impl Command + ?Sized),
+    ctx: &Context<'_>,
+) -> Result<Role, BotError> {
+    let room_id = ctx.active_room_id().as_str();
+    let role = ctx.db.get_command_role(room_id, spec.name).await?;
+    Ok(role.unwrap_or_else(|| default_role(spec, cmd.changes_room())))
+}
+
+fn default_role(spec: &CommandSpec, changes_room: bool) -> Role {
+    match changes_room {
+        true => std::cmp::max(spec.role, spec.room_role),
+        false => spec.role,
+    }
+}
+
+/// Whether the user has the role needed to run the command in the
+/// active room.
+pub(super) async fn check_permission(
+    spec: &CommandSpec,
+    cmd: &(impl Command + ?Sized),
+    ctx: &Context<'_>,
+) -> Result<(), BotError> {
+    let required = required_role(spec, cmd, ctx).await?;
+    if required == Role::Player {
+        return Ok(());
+    }
+
+    match user_role(ctx).await? {
+        role if role >= required => Ok(()),
+        _ => Err(BotError::RoleTooLow(spec.name.to_owned(), required)),
+    }
+}
+
+/// Describe the roles needed to run a command in the active room.
+async fn describe_roles(spec: &CommandSpec, ctx: &Context<'_>) -> Result<String, BotError> {
+    let room_id = ctx.active_room_id().as_str();
+    let roles = match ctx.db.get_command_role(room_id, spec.name).await? {
+        Some(role) => format!("the {} role", role),
+        None if default_role(spec, true) != spec.role => format!(
+            "the {} role, and the {} role to change the room",
+            spec.role, spec.room_role
+        ),
+        None => format!("the {} role", spec.role),
+    };
+
+    Ok(format!("!{} needs {}", spec.name, roles))
+}
+
+/// Roles that room admins can give to commands. Room admins cannot
+/// lock themselves out of a command.
+fn parse_role(input: &str) -> Option<Role> {
+    match input.to_lowercase().as_str() {
+        "player" | "players" => Some(Role::Player),
+        "gm" | "gms" => Some(Role::GameMaster),
+        "admin" | "admins" => Some(Role::RoomAdmin),
+        _ => None,
+    }
+}
+
+/// Whether room admins can change the role needed for the command.
+/// The role of !permission itself and of commands for bot admins
+/// cannot be changed.
+fn is_changeable(spec: &CommandSpec) -> bool {
+    spec.name != "permission" && default_role(spec, true) <= Role::RoomAdmin
+}
+
+fn find_spec(name: &str) -> Result<&'static CommandSpec, BotError> {
+    let name = name.trim_start_matches('!');
+    find_command(name).ok_or_else(|| {
+        CommandParsingError::UnrecognizedCommand(name.to_owned(), suggest_command(name)).into()
+    })
+}
+
+/// Show or change the roles needed to run commands in the room. A
+/// role of None goes back to the default role of the command.
+pub enum PermissionSetting {
+    List,
+    Show(&'static CommandSpec),
+    Role(&'static CommandSpec, Option<Role>),
+}
+
+pub struct PermissionCommand(pub PermissionSetting);
+
+impl TryFrom<String> for PermissionCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let setting = match words.as_slice() {
+            [] => PermissionSetting::List,
+            [command] => PermissionSetting::Show(find_spec(command)?),
+            [command, reset] if reset.eq_ignore_ascii_case("default") => {
+                PermissionSetting::Role(find_spec(command)?, None)
+            }
+            [command, role] => match parse_role(role) {
+                Some(role) => PermissionSetting::Role(find_spec(command)?, Some(role)),
+                None => return Err(CommandError::InvalidCommand(input).into()),
+            },
+            _ => return Err(CommandError::InvalidCommand(input).into()),
+        };
+
+        if let PermissionSetting::Role(spec, _) = setting {
+            if !is_changeable(spec) {
+                return Err(BotError::RoleNotChangeable(spec.name.to_owned()));
+            }
+        }
+
+        Ok(PermissionCommand(setting))
+    }
+}
+
+#[async_trait]
+impl Command for PermissionCommand {
+    fn name(&self) -> &'static str {
+        "command permissions"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    fn changes_room(&self) -> bool {
+        matches!(self.0, PermissionSetting::Role(..))
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let room_id = ctx.active_room_id().as_str();
+        let html = match &self.0 {
+            PermissionSetting::List => {
+                let changed: Vec<String> = ctx
+                    .db
+                    .get_command_roles(room_id)
+                    .await?
+                    .into_iter()
+                    .map(|(command, role)| format!("!{}: {}", command, role))
+                    .collect();
+
+                let changed = match changed.is_empty() {
+                    true => "none".to_owned(),
+                    false => changed.join("<br/>"),
+                };
+
+                format!(
+                    "<strong>Your role:</strong> {}<br/>\
+                     <strong>Changed command roles:</strong><br/>{}",
+                    user_role(ctx).await?,
+                    changed
+                )
+            }
+            PermissionSetting::Show(spec) => format!(
+                "<strong>Permission:</strong> {}",
+                describe_roles(spec, ctx).await?
+            ),
+            PermissionSetting::Role(spec, role) => {
+                ctx.db.set_command_role(room_id, spec.name, *role).await?;
+                format!(
+                    "<strong>Permission:</strong> {}",
+                    describe_roles(spec, ctx).await?
+                )
+            }
+        };
+
+        Execution::success(html)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::parser::parse_command_spec;
+
+    #[test]
+    fn parse_permission_commands() {
+        let cmd = PermissionCommand::try_from("!R gm".to_owned()).expect("was error");
+        assert!(matches!(
+            cmd.0,
+            PermissionSetting::Role(spec, Some(Role::GameMaster)) if spec.name == "roll"
+        ));
+
+        let cmd = PermissionCommand::try_from("macro default".to_owned()).expect("was error");
+        assert!(matches!(cmd.0, PermissionSetting::Role(spec, None) if spec.name == "macro"));
+
+        assert!(PermissionCommand::try_from("roll botadmin".to_owned()).is_err());
+        assert!(PermissionCommand::try_from("attack gm".to_owned()).is_err());
+    }
+
+    #[test]
+    fn permission_role_cannot_be_changed() {
+        let result = PermissionCommand::try_from("permission player".to_owned());
+        assert!(matches!(result, Err(BotError::RoleNotChangeable(_))));
+    }
+
+    #[test]
+    fn room_changes_need_the_room_role() {
+        let role = |input: &str| {
+            let (spec, cmd) = parse_command_spec(input).expect("was error");
+            default_role(spec, cmd.changes_room())
+        };
+
+        assert_eq!(Role::Player, role("!system"));
+        assert_eq!(Role::GameMaster, role("!system mage"));
+        assert_eq!(Role::Player, role("!set hp 5"));
+        assert_eq!(Role::GameMaster, role("!set room.difficulty 5"));
+        assert_eq!(Role::GameMaster, role("!dec room.momentum"));
+        assert_eq!(Role::Player, role("!macro add attack !roll 1d20"));
+        assert_eq!(Role::GameMaster, role("!macro del room.attack"));
+        assert_eq!(Role::Player, role("!inline"));
+        assert_eq!(Role::GameMaster, role("!prefix ."));
+        assert_eq!(Role::RoomAdmin, role("!permission roll gm"));
+    }
+
+    #[test]
+    fn roles_from_power_levels() {
+        assert_eq!(Role::Player, Role::from_power_level(0));
+        assert_eq!(Role::GameMaster, Role::from_power_level(50));
+        assert_eq!(Role::RoomAdmin, Role::from_power_level(100));
+        assert!(Role::BotAdmin > Role::RoomAdmin);
+    }
+}
@@ -0,0 +1,812 @@
+use crate::commands::{
+    basic_rolling::RollCommand,
+    characters::CharacterCommand,
+    cofd::{CastCommand, FormCommand, PoolRollCommand, SpendCommand},
+    cthulhu::{CthAdvanceRoll, CthRoll},
+    cypher::CypherRoll,
+    genesys::NarrativeRoll,
+    gurps::GurpsRoll,
+    import_export::{ExportCommand, ImportCommand},
+    l5r::L5rRoll,
+    macros::MacroCommand,
+    management::{CheckCommand, LinkCommand, RegisterCommand, UnlinkCommand, UnregisterCommand},
+    misc::HelpCommand,
+    permissions::PermissionCommand,
+    rooms::{
+        CommandPrefixCommand, GameSystemCommand, InlineRollsCommand, ListRoomsCommand,
+        SetRoomCommand,
+    },
+    savage_worlds::{BennyCommand, SwRoll},
+    shadowrun::SrRoll,
+    sheet::SheetCommand,
+    variables::{
+        parse_variables_command, AdjustVariableCommand, BoundsCommand, DeleteVariableCommand,
+        GetVariableCommand, SetVariableCommand, UndoCommand,
+    },
+    wfrp::WfrpRoll,
+    year_zero::{PushCommand, YzeRollCommand},
+    Command,
+};
+use crate::error::BotError;
+use crate::help::HelpTopic;
+use crate::models::Role;
+use crate::suggestions::closest_match;
+use std::convert::TryFrom;
+
+type CommandParser = fn(String) -> Result<Box<dyn Command>, BotError>;
+
+/// Everything the bot knows about a command: how to parse it, and how
+/// to explain it in !help.
+pub struct CommandSpec {
+    /// Main name of the command, without the prefix.
+    pub name: &'static str,
+
+    /// Other names that run the same command.
+    pub aliases: &'static [&'static str],
+
+    /// What comes after the name of the command.
+    pub syntax: &'static str,
+
+    /// What the command does, in a few words.
+    pub description: &'static str,
+
+    /// Complete example commands. Every example must parse.
+    pub examples: &'static [&'static str],
+
+    /// Whether {variable} references in the command are replaced by
+    /// their values before it is parsed. Never set for commands that
+    /// store text or take passwords, so they get exactly what was
+    /// typed.
+    pub expands_variables: bool,
+
+    /// Whether the command can only be run in encrypted direct
+    /// messages with the bot.
+    pub secure: bool,
+
+    /// Role needed to run the command, unless the room admins changed
+    /// it for the room.
+    pub role: Role,
+
+    /// Role needed when the command changes the room for everyone
+    /// (see Command::changes_room), unless the room admins changed
+    /// the role of the command for the room.
+    pub room_role: Role,
+
+    /// Longer help about the command, if it has a help topic.
+    pub help_topic: Option<HelpTopic>,
+
+    parse: CommandParser,
+}
+
+/// Shortens boilerplate for commands that are parsed from the rest of
+/// the command line with TryFrom.
+fn convert<T>(input: String) -> Result<Box<dyn Command>, BotError>
+where
+    T: Command + TryFrom<String, Error = BotError> + 'static,
+{
+    T::try_from(input).map(|cmd| Box::new(cmd) as Box<dyn Command>)
+}
+
+/// Every command of the bot.
+pub static COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "roll",
+        aliases: &["r"],
+        syntax: "<dice-expression>",
+        description: "roll dice",
+        examples: &["!roll 1d20+5", "!r 2d20k1"],
+        expands_variables: true,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::RollingDice),
+        parse: convert::<RollCommand>,
+    },
+    CommandSpec {
+        name: "pool",
+        aliases: &["rp"],
+        syntax: "<expression> [modifiers]",
+        description: "roll a Chronicles of Darkness dice pool",
+        examples: &["!pool 8", "!pool myskill - 5 n"],
+        expands_variables: true,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::DicePool),
+        parse: convert::<PoolRollCommand>,
+    },
+    CommandSpec {
+        name: "chance",
+        aliases: &[],
+        syntax: "",
+        description: "roll a Chronicles of Darkness chance die",
+        examples: &["!chance"],
+        expands_variables: true,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::ChroniclesOfDarkness),
+        parse: |_| PoolRollCommand::chance_die().map(|cmd| Box::new(cmd) as Box<dyn Command>),
+    },
+    CommandSpec {
+        name: "cast",
+        aliases: &[],
+        syntax: "<arcanum> <level> [reach <num>] [mana <num>] [bonus <num>]",
+        description: "cast a Mage spell",
+        examples: &["!cast forces 2", "!cast life 3 reach 2 mana 1"],
+        expands_variables: true,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::ChroniclesOfDarkness),
+        parse: convert::<CastCommand>,
+    },
+    CommandSpec {
+        name: "form",
+        aliases: &[],
+        syntax: "<hishu|dalu|gauru|urshul|urhan>",
+        description: "change Werewolf form",
+        examples: &["!form gauru"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::ChroniclesOfDarkness),
+        parse: convert::<FormCommand>,
+    },
+    CommandSpec {
+        name: "spend",
+        aliases: &[],
+        syntax: "<resource> [amount]",
+        description: "spend Mana, Glamour, Pillar points or Pyros",
+        examples: &["!spend mana 2"],
+        expands_variables: true,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::ChroniclesOfDarkness),
+        parse: convert::<SpendCommand>,
+    },
+    CommandSpec {
+        name: "cthroll",
+        aliases: &[],
+        syntax: "<num|variable> [b|bb|p|pp]",
+        description: "roll percentile dice for Call of Cthulhu",
+        examples: &["!cthroll 60", "!cthroll 30 bb"],
+        expands_variables: true,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::Cthulhu),
+        parse: convert::<CthRoll>,
+    },
+    CommandSpec {
+        name: "cthadv",
+        aliases: &["ctharoll"],
+        syntax: "<num|variable>",
+        description: "roll Call of Cthulhu skill advancement",
+        examples: &["!cthadv spothidden"],
+        expands_variables: true,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::Cthulhu),
+        parse: convert::<CthAdvanceRoll>,
+    },
+    CommandSpec {
+        name: "cypher",
+        aliases: &[],
+        syntax: "<difficulty> [effort <num>] [asset <num>] [skill trained|specialized] [stat]",
+        description: "roll a Cypher System task",
+        examples: &["!cypher 4", "!cypher 6 effort 2 speed"],
+        expands_variables: true,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::Cypher),
+        parse: convert::<CypherRoll>,
+    },
+    CommandSpec {
+        name: "sw",
+        aliases: &["swroll"],
+        syntax: "d<sides> [+/-modifier] [target number]",
+        description: "roll a Savage Worlds trait",
+        examples: &["!sw d8", "!sw d10 -1 6"],
+        expands_variables: true,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::SavageWorlds),
+        parse: convert::<SwRoll>,
+    },
+    CommandSpec {
+        name: "benny",
+        aliases: &["bennies"],
+        syntax: "[spend | gain <num> | set <num>]",
+        description: "track Savage Worlds bennies",
+        examples: &["!benny", "!benny set 3"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::SavageWorlds),
+        parse: convert::<BennyCommand>,
+    },
+    CommandSpec {
+        name: "narr",
+        aliases: &["genesys"],
+        syntax: "<dice>",
+        description: "roll Genesys and Star Wars narrative dice",
+        examples: &["!narr 2g1y2p1k", "!narr 3 ability 2 difficulty"],
+        expands_variables: true,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::Genesys),
+        parse: convert::<NarrativeRoll>,
+    },
+    CommandSpec {
+        name: "gurps",
+        aliases: &[],
+        syntax: "<skill> [vs <opposing skill>]",
+        description: "roll a GURPS success roll or quick contest",
+        examples: &["!gurps 12", "!gurps stealth vs 13"],
+        expands_variables: true,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::Gurps),
+        parse: convert::<GurpsRoll>,
+    },
+    CommandSpec {
+        name: "l5r",
+        aliases: &["rk"],
+        syntax: "<X>k<Y> [+/-modifier] [tn <target>] [raises <num>]",
+        description: "roll and keep Legend of the Five Rings dice",
+        examples: &["!l5r 6k3", "!l5r 7k4 tn 15 raises 2"],
+        expands_variables: true,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::L5r),
+        parse: convert::<L5rRoll>,
+    },
+    CommandSpec {
+        name: "sr",
+        aliases: &["srroll"],
+        syntax: "<expression> [edge]",
+        description: "roll a Shadowrun dice pool",
+        examples: &["!sr 12", "!sr agility + firearms + edge e"],
+        expands_variables: true,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::Shadowrun),
+        parse: convert::<SrRoll>,
+    },
+    CommandSpec {
+        name: "wfrp",
+        aliases: &[],
+        syntax: "<target> [vs <opposing target>]",
+        description: "roll a Warhammer Fantasy Roleplay test",
+        examples: &["!wfrp 45", "!wfrp melee vs 38"],
+        expands_variables: true,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::Wfrp),
+        parse: convert::<WfrpRoll>,
+    },
+    CommandSpec {
+        name: "yze",
+        aliases: &["yz"],
+        syntax: "<base> [skill] [gear] [stress <num>]",
+        description: "roll Year Zero Engine dice",
+        examples: &["!yze 3 2 1", "!yze 4 2 stress 1"],
+        expands_variables: true,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::YearZero),
+        parse: convert::<YzeRollCommand>,
+    },
+    CommandSpec {
+        name: "push",
+        aliases: &[],
+        syntax: "",
+        description: "push your last Year Zero Engine roll",
+        examples: &["!push"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::YearZero),
+        parse: convert::<PushCommand>,
+    },
+    CommandSpec {
+        name: "variables",
+        aliases: &[],
+        syntax: "[copy <from room> [to room] | migrate | history <name> [user]]",
+        description: "list, copy and migrate your variables",
+        examples: &["!variables", "!variables history hp", "!variables migrate"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::GameMaster,
+        help_topic: None,
+        parse: parse_variables_command,
+    },
+    CommandSpec {
+        name: "get",
+        aliases: &[],
+        syntax: "<name>",
+        description: "show a variable",
+        examples: &["!get myvar", "!get room.difficulty"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: None,
+        parse: convert::<GetVariableCommand>,
+    },
+    CommandSpec {
+        name: "set",
+        aliases: &[],
+        syntax: "<name> <number | = formula | \"text\" | += amount | -= amount>",
+        description: "set a variable",
+        examples: &[
+            "!set myvar 5",
+            "!set defense = min(wits, dex) + athletics",
+            "!set weapon \"Longsword\"",
+            "!set hp -= 3",
+        ],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::GameMaster,
+        help_topic: None,
+        parse: convert::<SetVariableCommand>,
+    },
+    CommandSpec {
+        name: "del",
+        aliases: &[],
+        syntax: "<name>",
+        description: "delete a variable",
+        examples: &["!del myvar"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::GameMaster,
+        help_topic: None,
+        parse: convert::<DeleteVariableCommand>,
+    },
+    CommandSpec {
+        name: "inc",
+        aliases: &[],
+        syntax: "<name> [amount]",
+        description: "add to a variable",
+        examples: &["!inc ammo", "!inc hp 2"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::GameMaster,
+        help_topic: None,
+        parse: |input| {
+            AdjustVariableCommand::increment(input).map(|cmd| Box::new(cmd) as Box<dyn Command>)
+        },
+    },
+    CommandSpec {
+        name: "dec",
+        aliases: &[],
+        syntax: "<name> [amount]",
+        description: "subtract from a variable",
+        examples: &["!dec hp 3"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::GameMaster,
+        help_topic: None,
+        parse: |input| {
+            AdjustVariableCommand::decrement(input).map(|cmd| Box::new(cmd) as Box<dyn Command>)
+        },
+    },
+    CommandSpec {
+        name: "bounds",
+        aliases: &[],
+        syntax: "<name> [min <num>] [max <num>] [refuse] | <name> none",
+        description: "limit the values of a variable",
+        examples: &["!bounds hp min 0 max 10", "!bounds hp"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: None,
+        parse: convert::<BoundsCommand>,
+    },
+    CommandSpec {
+        name: "undo",
+        aliases: &[],
+        syntax: "",
+        description: "undo your last change to your variables",
+        examples: &["!undo"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::History),
+        parse: convert::<UndoCommand>,
+    },
+    CommandSpec {
+        name: "export",
+        aliases: &[],
+        syntax: "[json|toml|csv]",
+        description: "upload your variables as a file",
+        examples: &["!export", "!export csv"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::ImportExport),
+        parse: convert::<ExportCommand>,
+    },
+    CommandSpec {
+        name: "import",
+        aliases: &[],
+        syntax: "[merge|skip|overwrite] [dry-run] [json|toml|csv]",
+        description: "import variables from an uploaded file",
+        examples: &["!import", "!import skip dry-run"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::ImportExport),
+        parse: convert::<ImportCommand>,
+    },
+    CommandSpec {
+        name: "char",
+        aliases: &["character"],
+        syntax: "[list | clear | new <name> | switch <name> | delete <name>]",
+        description: "manage your characters",
+        examples: &["!char new Anna", "!char list"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::Characters),
+        parse: convert::<CharacterCommand>,
+    },
+    CommandSpec {
+        name: "sheet",
+        aliases: &[],
+        syntax: "[init]",
+        description: "show or create your character sheet",
+        examples: &["!sheet init", "!sheet"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: Some(HelpTopic::Sheet),
+        parse: convert::<SheetCommand>,
+    },
+    CommandSpec {
+        name: "macro",
+        aliases: &[],
+        syntax: "add <name> <commands> | del <name> | list",
+        description: "save commands to run together",
+        examples: &["!macro add attack !roll 1d20+{atk}", "!macro list"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::GameMaster,
+        help_topic: Some(HelpTopic::Macros),
+        parse: convert::<MacroCommand>,
+    },
+    CommandSpec {
+        name: "system",
+        aliases: &[],
+        syntax: "[name]",
+        description: "show or set the room's game system",
+        examples: &["!system", "!system mage"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::GameMaster,
+        help_topic: Some(HelpTopic::ChroniclesOfDarkness),
+        parse: convert::<GameSystemCommand>,
+    },
+    CommandSpec {
+        name: "inline",
+        aliases: &[],
+        syntax: "[on|off] | markers <open> <close>",
+        description: "inline roll settings of the room",
+        examples: &["!inline", "!inline on", "!inline markers (( ))"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::GameMaster,
+        help_topic: Some(HelpTopic::InlineRolls),
+        parse: convert::<InlineRollsCommand>,
+    },
+    CommandSpec {
+        name: "prefix",
+        aliases: &[],
+        syntax: "[<prefix>|default]",
+        description: "command prefix of the room",
+        examples: &["!prefix", "!prefix ."],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::GameMaster,
+        help_topic: Some(HelpTopic::Prefix),
+        parse: convert::<CommandPrefixCommand>,
+    },
+    CommandSpec {
+        name: "permission",
+        aliases: &["permissions"],
+        syntax: "[<command> [player|gm|admin|default]]",
+        description: "show or change the role needed to run a command in the room",
+        examples: &["!permission", "!permission roll", "!permission system gm"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::RoomAdmin,
+        help_topic: Some(HelpTopic::Permissions),
+        parse: convert::<PermissionCommand>,
+    },
+    CommandSpec {
+        name: "help",
+        aliases: &[],
+        syntax: "[command or topic]",
+        description: "show help",
+        examples: &["!help", "!help roll"],
+        expands_variables: false,
+        secure: false,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: None,
+        parse: convert::<HelpCommand>,
+    },
+    CommandSpec {
+        name: "register",
+        aliases: &[],
+        syntax: "",
+        description: "register an account with the bot",
+        examples: &["!register"],
+        expands_variables: false,
+        secure: true,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: None,
+        parse: convert::<RegisterCommand>,
+    },
+    CommandSpec {
+        name: "unregister",
+        aliases: &[],
+        syntax: "",
+        description: "delete your account",
+        examples: &["!unregister"],
+        expands_variables: false,
+        secure: true,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: None,
+        parse: convert::<UnregisterCommand>,
+    },
+    CommandSpec {
+        name: "check",
+        aliases: &[],
+        syntax: "",
+        description: "check the status of your account",
+        examples: &["!check"],
+        expands_variables: false,
+        secure: true,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: None,
+        parse: convert::<CheckCommand>,
+    },
+    CommandSpec {
+        name: "link",
+        aliases: &[],
+        syntax: "<password>",
+        description: "set a password to use your account in external applications",
+        examples: &["!link hunter2"],
+        expands_variables: false,
+        secure: true,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: None,
+        parse: convert::<LinkCommand>,
+    },
+    CommandSpec {
+        name: "unlink",
+        aliases: &[],
+        syntax: "",
+        description: "remove the password for external applications",
+        examples: &["!unlink"],
+        expands_variables: false,
+        secure: true,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: None,
+        parse: convert::<UnlinkCommand>,
+    },
+    CommandSpec {
+        name: "rooms",
+        aliases: &[],
+        syntax: "",
+        description: "list the rooms you share with the bot",
+        examples: &["!rooms"],
+        expands_variables: false,
+        secure: true,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: None,
+        parse: convert::<ListRoomsCommand>,
+    },
+    CommandSpec {
+        name: "room",
+        aliases: &[],
+        syntax: "<room ID or name>",
+        description: "set your active room for commands in direct messages",
+        examples: &["!room My Campaign"],
+        expands_variables: false,
+        secure: true,
+        role: Role::Player,
+        room_role: Role::Player,
+        help_topic: None,
+        parse: convert::<SetRoomCommand>,
+    },
+];
+
+impl CommandSpec {
+    /// Parse the rest of the command line into the command.
+    pub fn parse(&self, input: String) -> Result<Box<dyn Command>, BotError> {
+        (self.parse)(input)
+    }
+
+    fn names(&self) -> impl Iterator<Item = &'static str> {
+        std::iter::once(self.name).chain(self.aliases.iter().copied())
+    }
+
+    /// The command and its aliases, e.g. "!roll, !r".
+    fn title(&self) -> String {
+        let names: Vec<String> = self.names().map(|name| format!("!{}", name)).collect();
+        names.join(", ")
+    }
+
+    /// Help for the command: its names and what it does, followed by
+    /// the help topic of the command, or by its syntax and examples if
+    /// it has no help topic.
+    pub fn help(&self) -> String {
+        let mut help = format!("{}: {}\n", self.title(), self.description);
+        if self.secure {
+            help.push_str("Only in encrypted direct messages with the bot.\n");
+        }
+
+        if self.role != Role::Player {
+            help.push_str(&format!("Needs the {} role in the room.\n", self.role));
+        }
+
+        if self.room_role > self.role {
+            let role = self.room_role;
+            help.push_str(&format!("Changing the room needs the {} role.\n", role));
+        }
+
+        if self.expands_variables {
+            help.push_str("Can use {variables} (see !help templates).\n");
+        }
+
+        match &self.help_topic {
+            Some(topic) => {
+                help.push('\n');
+                help.push_str(topic.message());
+            }
+            None => {
+                help.push_str(&format!("\nSyntax: !{} {}\n", self.name, self.syntax));
+                help.push_str("\nExamples:\n");
+                for example in self.examples {
+                    help.push_str(&format!(" {}\n", example));
+                }
+            }
+        }
+
+        help
+    }
+}
+
+/// Find a command by its name or one of its aliases, ignoring case.
+pub fn find_command(name: &str) -> Option<&'static CommandSpec> {
+    let name = name.to_lowercase();
+    COMMANDS
+        .iter()
+        .find(|spec| spec.names().any(|known| known == name))
+}
+
+/// The name or alias of the command that the given name is closest
+/// to, if any is close enough.
+pub fn suggest_command(name: &str) -> Option<&'static str> {
+    let names: Vec<&'static str> = COMMANDS.iter().flat_map(CommandSpec::names).collect();
+    closest_match(name, &names)
+}
+
+/// Help listing every command, and other help topics.
+pub fn general_help() -> String {
+    let commands: Vec<String> = COMMANDS
+        .iter()
+        .map(|spec| format!(" {}: {}", spec.title(), spec.description))
+        .collect();
+
+    format!(
+        "Commands:\n{}\n\nUse !help <command> for more about a command.\n\
+         Other topics: {}\n",
+        commands.join("\n"),
+        crate::help::OTHER_TOPICS.join(", ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_unique() {
+        let mut names: Vec<&str> = COMMANDS.iter().flat_map(CommandSpec::names).collect();
+        let count = names.len();
+        names.sort_unstable();
+        names.dedup();
+        assert_eq!(count, names.len());
+    }
+
+    #[test]
+    fn examples_parse() {
+        for spec in COMMANDS {
+            assert!(!spec.examples.is_empty(), "!{} has no examples", spec.name);
+            for example in spec.examples {
+                let cmd = crate::commands::parser::parse_command(example)
+                    .unwrap_or_else(|e| panic!("{} did not parse: {}", example, e));
+
+                assert_eq!(
+                    spec.secure,
+                    cmd.is_secure(),
+                    "{} does not match the secure flag of !{}",
+                    example,
+                    spec.name
+                );
+
+                assert!(
+                    !(cmd.is_secure() && spec.expands_variables),
+                    "{} is secure, so it must not expand variables",
+                    example
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn help_mentions_secure_commands_and_room_roles() {
+        let register = find_command("register").unwrap().help();
+        assert!(register.contains("Only in encrypted direct messages"));
+
+        let system = find_command("system").unwrap().help();
+        assert!(!system.contains("Only in encrypted direct messages"));
+        assert!(system.contains("Changing the room needs the GM role."));
+    }
+
+    #[test]
+    fn finds_aliases_ignoring_case() {
+        assert_eq!(Some("roll"), find_command("R").map(|spec| spec.name));
+        assert_eq!(
+            Some("char"),
+            find_command("character").map(|spec| spec.name)
+        );
+        assert!(find_command("attack").is_none());
+    }
+
+    #[test]
+    fn suggests_close_commands() {
+        assert_eq!(Some("cthroll"), suggest_command("cthrol"));
+        assert_eq!(Some("variables"), suggest_command("variabels"));
+        assert_eq!(None, suggest_command("qqqqqq"));
+    }
+}
@@ -1,8 +1,11 @@
-use super::{Command, Execution, ExecutionResult};
+use super::{Command, CommandError, Execution, ExecutionResult};
+use crate::config::is_valid_command_prefix;
 use crate::context::Context;
-use crate::db::Users;
+use crate::db::{Rooms, Users};
 use crate::error::BotError;
+use crate::inline_rolls::escape_html;
 use crate::matrix;
+use crate::systems::GameSystem;
 use async_trait::async_trait;
 use fuse_rust::{Fuse, FuseProperty, Fuseable};
 use futures::stream::{self, StreamExt, TryStreamExt};
@@ -12,9 +15,9 @@ use std::convert::TryFrom;
 /// Holds matrix room ID and display name as strings, for use with
 /// searching. See search_for_room.
 #[derive(Clone, Debug, Eq, PartialEq)]
-struct RoomNameAndId {
-    id: String,
-    name: String,
+pub(super) struct RoomNameAndId {
+    pub(super) id: String,
+    pub(super) name: String,
 }
 
 /// Allows searching for a room name and ID struct, instead of just
@@ -39,7 +42,7 @@ impl Fuseable for RoomNameAndId {
 /// string. It prefers the exact room ID first, and then falls back to
 /// fuzzy searching based on room display name. The best match is
 /// returned, or None if no matches were found.
-fn search_for_room<'a>(
+pub(super) fn search_for_room<'a>(
     rooms_for_user: &'a [RoomNameAndId],
     search_for: &str,
 ) -> Option<&'a RoomNameAndId> {
@@ -58,7 +61,7 @@ fn search_for_room<'a>(
         .or_else(best_fuzzy_match)
 }
 
-async fn get_rooms_for_user(
+pub(super) async fn get_rooms_for_user(
     client: &Client,
     user_id: &str,
 ) -> Result<Vec<RoomNameAndId>, BotError> {
@@ -162,6 +165,199 @@ impl Command for SetRoomCommand {
     }
 }
 
+pub struct GameSystemCommand(Option<GameSystem>);
+
+impl TryFrom<String> for GameSystemCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Ok(GameSystemCommand(None));
+        }
+
+        GameSystem::parse(input)
+            .map(|system| GameSystemCommand(Some(system)))
+            .ok_or_else(|| BotError::UnknownGameSystem(input.to_owned()))
+    }
+}
+
+#[async_trait]
+impl Command for GameSystemCommand {
+    fn name(&self) -> &'static str {
+        "room game system"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    fn changes_room(&self) -> bool {
+        self.0.is_some()
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let room_id = ctx.active_room_id().as_str();
+
+        let system = match self.0 {
+            Some(system) => {
+                ctx.db.set_game_system(room_id, system).await?;
+                Some(system)
+            }
+            None => ctx.db.get_game_system(room_id).await?,
+        };
+
+        let name = system.map(|system| system.name()).unwrap_or("not set");
+        Execution::success(format!("<strong>Game system:</strong> {}", name))
+    }
+}
+
+/// Longest inline roll marker.
+const MAX_MARKER_LENGTH: usize = 4;
+
+/// Change to the inline roll settings of a room.
+#[derive(Debug, PartialEq, Eq)]
+pub enum InlineRollsSetting {
+    Show,
+    Enabled(bool),
+    Markers(String, String),
+}
+
+pub struct InlineRollsCommand(pub InlineRollsSetting);
+
+impl TryFrom<String> for InlineRollsCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let valid_marker = |marker: &str| marker.chars().count() <= MAX_MARKER_LENGTH;
+
+        let setting = match words.as_slice() {
+            [] => InlineRollsSetting::Show,
+            [on] if on.eq_ignore_ascii_case("on") => InlineRollsSetting::Enabled(true),
+            [off] if off.eq_ignore_ascii_case("off") => InlineRollsSetting::Enabled(false),
+            [markers, open, close]
+                if markers.eq_ignore_ascii_case("markers")
+                    && valid_marker(open)
+                    && valid_marker(close) =>
+            {
+                InlineRollsSetting::Markers(open.to_string(), close.to_string())
+            }
+            _ => return Err(CommandError::InvalidCommand(input).into()),
+        };
+
+        Ok(InlineRollsCommand(setting))
+    }
+}
+
+#[async_trait]
+impl Command for InlineRollsCommand {
+    fn name(&self) -> &'static str {
+        "inline roll settings"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    fn changes_room(&self) -> bool {
+        self.0 != InlineRollsSetting::Show
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let room_id = ctx.active_room_id().as_str();
+        let mut settings = ctx.db.get_room_settings(room_id).await?;
+
+        match &self.0 {
+            InlineRollsSetting::Show => (),
+            InlineRollsSetting::Enabled(enabled) => settings.inline_rolls = *enabled,
+            InlineRollsSetting::Markers(open, close) => {
+                settings.inline_roll_open = open.clone();
+                settings.inline_roll_close = close.clone();
+            }
+        }
+
+        if self.0 != InlineRollsSetting::Show {
+            ctx.db.set_room_settings(room_id, &settings).await?;
+        }
+
+        let html = format!(
+            "<strong>Inline rolls:</strong> {}, between {} and {}",
+            if settings.inline_rolls { "on" } else { "off" },
+            escape_html(&settings.inline_roll_open),
+            escape_html(&settings.inline_roll_close)
+        );
+
+        Execution::success(html)
+    }
+}
+
+/// Longest command prefix of a room.
+const MAX_PREFIX_LENGTH: usize = 4;
+
+/// Show or change the command prefix of the room. None resets it to
+/// the prefix in the bot's config.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PrefixSetting {
+    Show,
+    Prefix(Option<String>),
+}
+
+pub struct CommandPrefixCommand(pub PrefixSetting);
+
+impl TryFrom<String> for CommandPrefixCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let words: Vec<&str> = input.split_whitespace().collect();
+        let setting = match words.as_slice() {
+            [] => PrefixSetting::Show,
+            [reset] if reset.eq_ignore_ascii_case("default") => PrefixSetting::Prefix(None),
+            [prefix]
+                if prefix.chars().count() <= MAX_PREFIX_LENGTH
+                    && is_valid_command_prefix(prefix) =>
+            {
+                PrefixSetting::Prefix(Some(prefix.to_string()))
+            }
+            _ => return Err(CommandError::InvalidCommand(input).into()),
+        };
+
+        Ok(CommandPrefixCommand(setting))
+    }
+}
+
+#[async_trait]
+impl Command for CommandPrefixCommand {
+    fn name(&self) -> &'static str {
+        "room command prefix"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    fn changes_room(&self) -> bool {
+        matches!(self.0, PrefixSetting::Prefix(_))
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let room_id = ctx.active_room_id().as_str();
+        let mut settings = ctx.db.get_room_settings(room_id).await?;
+
+        if let PrefixSetting::Prefix(prefix) = &self.0 {
+            settings.command_prefix = prefix.clone();
+            ctx.db.set_room_settings(room_id, &settings).await?;
+        }
+
+        let prefix = match &settings.command_prefix {
+            Some(prefix) => escape_html(prefix),
+            None => "the bot's default".to_owned(),
+        };
+
+        Execution::success(format!("<strong>Command prefix:</strong> {}", prefix))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -184,4 +380,22 @@ mod tests {
         assert!(found_room.is_some());
         assert_eq!(found_room.unwrap(), &rooms[0]);
     }
+
+    #[test]
+    fn prefix_must_not_be_alphanumeric() {
+        let parse = |input: &str| {
+            CommandPrefixCommand::try_from(input.to_owned())
+                .ok()
+                .map(|cmd| cmd.0)
+        };
+
+        assert_eq!(
+            Some(PrefixSetting::Prefix(Some(".".to_owned()))),
+            parse(".")
+        );
+        assert_eq!(Some(PrefixSetting::Prefix(None)), parse("default"));
+        assert_eq!(None, parse("x"));
+        assert_eq!(None, parse("!1"));
+        assert_eq!(None, parse("!!!!!"));
+    }
 }
@@ -0,0 +1,218 @@
+use super::{Command, CommandError, Execution, ExecutionResult};
+use crate::context::Context;
+use crate::db::errors::DataError;
+use crate::db::Variables;
+use crate::error::BotError;
+use crate::savage_worlds::dice::{roll_trait, TraitRoll};
+use crate::savage_worlds::parser::parse_trait_roll;
+use async_trait::async_trait;
+use std::convert::TryFrom;
+
+/// Bennies are stored as a regular user variable, so they can also be
+/// inspected and changed with !get and !set.
+const BENNIES_VARIABLE: &str = "bennies";
+
+pub struct SwRoll(pub TraitRoll);
+
+impl TryFrom<String> for SwRoll {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let roll = parse_trait_roll(&input)?;
+        Ok(SwRoll(roll))
+    }
+}
+
+#[async_trait]
+impl Command for SwRoll {
+    fn name(&self) -> &'static str {
+        "roll savage worlds trait"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, _ctx: &Context<'_>) -> ExecutionResult {
+        let rolled = roll_trait(&self.0);
+
+        let html = format!(
+            "<strong>Roll:</strong> {}</p><p><strong>Result</strong>: {}",
+            self.0, rolled
+        );
+
+        Execution::success(html)
+    }
+}
+
+/// What to do with the user's bennies.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BennyAction {
+    /// Display the current amount of bennies.
+    Show,
+
+    /// Spend a single benny, if the user has one.
+    Spend,
+
+    /// Gain the given amount of bennies.
+    Gain(i32),
+
+    /// Set bennies to an exact amount (e.g. at the start of a session).
+    Set(i32),
+}
+
+pub struct BennyCommand(pub BennyAction);
+
+impl TryFrom<String> for BennyCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let parts: Vec<&str> = input.split_whitespace().collect();
+        let parse_amount = |amount: &str| {
+            amount
+                .parse::<i32>()
+                .ok()
+                .filter(|&amount| amount >= 0)
+                .ok_or_else(|| CommandError::InvalidCommand(input.clone()))
+        };
+
+        let action = match parts[..] {
+            [] => BennyAction::Show,
+            ["spend"] => BennyAction::Spend,
+            ["gain"] | ["add"] => BennyAction::Gain(1),
+            ["gain", amount] | ["add", amount] => BennyAction::Gain(parse_amount(amount)?),
+            ["set", amount] => BennyAction::Set(parse_amount(amount)?),
+            _ => return Err(CommandError::InvalidCommand(input).into()),
+        };
+
+        Ok(BennyCommand(action))
+    }
+}
+
+/// Get the amount of bennies the user has in the active room. Having
+/// no stored amount is the same as having zero bennies.
+async fn get_bennies(ctx: &Context<'_>) -> Result<i32, BotError> {
+    let result = ctx
+        .db
+        .get_user_variable(
+            ctx.username,
+            ctx.active_room_id().as_str(),
+            BENNIES_VARIABLE,
+        )
+        .await;
+
+    match result {
+        Ok(bennies) => Ok(bennies),
+        Err(DataError::KeyDoesNotExist(_)) => Ok(0),
+        Err(e) => Err(e.into()),
+    }
+}
+
+async fn set_bennies(ctx: &Context<'_>, bennies: i32) -> Result<i32, BotError> {
+    ctx.db
+        .set_user_variable(
+            ctx.username,
+            ctx.active_room_id().as_str(),
+            BENNIES_VARIABLE,
+            bennies,
+        )
+        .await?;
+
+    Ok(bennies)
+}
+
+/// Gain bennies in one atomic update, starting from 0 if the user has
+/// none yet.
+async fn gain_bennies(ctx: &Context<'_>, amount: i32) -> Result<i32, BotError> {
+    let result = ctx
+        .db
+        .adjust_user_variable(
+            ctx.username,
+            ctx.active_room_id().as_str(),
+            BENNIES_VARIABLE,
+            amount,
+        )
+        .await;
+
+    match result {
+        Ok((_, bennies)) => Ok(bennies),
+        Err(DataError::KeyDoesNotExist(_)) => set_bennies(ctx, amount).await,
+        Err(DataError::I32SchemaViolation) => Err(BotError::TooManyBennies),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Spend a single benny in one atomic update, so two spends at once
+/// cannot both take the last benny.
+async fn spend_benny(ctx: &Context<'_>) -> Result<i32, BotError> {
+    let result = ctx
+        .db
+        .spend_user_variable(
+            ctx.username,
+            ctx.active_room_id().as_str(),
+            BENNIES_VARIABLE,
+            1,
+        )
+        .await;
+
+    match result {
+        Ok((_, bennies)) => Ok(bennies),
+        Err(DataError::KeyDoesNotExist(_)) | Err(DataError::BelowMinimum(..)) => {
+            Err(BotError::NoBenniesLeft)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[async_trait]
+impl Command for BennyCommand {
+    fn name(&self) -> &'static str {
+        "track savage worlds bennies"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let (bennies, message) = match self.0 {
+            BennyAction::Show => (get_bennies(ctx).await?, "Bennies"),
+            BennyAction::Spend => (spend_benny(ctx).await?, "Spent a benny"),
+            BennyAction::Gain(amount) => (gain_bennies(ctx, amount).await?, "Gained bennies"),
+            BennyAction::Set(amount) => (set_bennies(ctx, amount).await?, "Set bennies"),
+        };
+
+        let html = format!("<strong>{}:</strong> {} remaining", message, bennies);
+        Execution::success(html)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_benny_actions() {
+        let parse = |input: &str| {
+            BennyCommand::try_from(input.to_owned())
+                .ok()
+                .map(|cmd| cmd.0)
+        };
+
+        assert!(matches!(parse(""), Some(BennyAction::Show)));
+        assert!(matches!(parse("spend"), Some(BennyAction::Spend)));
+        assert!(matches!(parse("gain"), Some(BennyAction::Gain(1))));
+        assert!(matches!(parse("add 2"), Some(BennyAction::Gain(2))));
+        assert!(matches!(parse("set 3"), Some(BennyAction::Set(3))));
+    }
+
+    #[test]
+    fn rejects_bad_benny_actions() {
+        let parse = |input: &str| BennyCommand::try_from(input.to_owned()).is_err();
+
+        assert!(parse("steal"));
+        assert!(parse("set"));
+        assert!(parse("set -1"));
+        assert!(parse("gain lots"));
+    }
+}
@@ -0,0 +1,42 @@
+use super::{Command, Execution, ExecutionResult};
+use crate::context::Context;
+use crate::error::BotError;
+use crate::shadowrun::dice::{roll_pool, ShadowrunPool, ShadowrunPoolWithContext, ShadowrunResult};
+use crate::shadowrun::parser::parse_shadowrun_pool;
+use async_trait::async_trait;
+use std::convert::TryFrom;
+
+pub struct SrRoll(pub ShadowrunPool);
+
+impl TryFrom<String> for SrRoll {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let pool = parse_shadowrun_pool(&input)?;
+        Ok(SrRoll(pool))
+    }
+}
+
+#[async_trait]
+impl Command for SrRoll {
+    fn name(&self) -> &'static str {
+        "roll shadowrun pool"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let pool_with_ctx = ShadowrunPoolWithContext(&self.0, ctx);
+        let rolled_pool = roll_pool(&pool_with_ctx).await?;
+
+        let html = format!(
+            "<strong>Pool:</strong> {}</p><p><strong>Result</strong>: {}",
+            rolled_pool,
+            ShadowrunResult(&rolled_pool)
+        );
+
+        Execution::success(html)
+    }
+}
@@ -0,0 +1,99 @@
+use super::{Command, CommandError, Execution, ExecutionResult};
+use crate::context::Context;
+use crate::db::{Rooms, Variables};
+use crate::error::BotError;
+use crate::systems::sheet::SheetTemplate;
+use async_trait::async_trait;
+use std::convert::TryFrom;
+
+/// What to do with the user's character sheet.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SheetAction {
+    /// Render the sheet, with derived values.
+    Show,
+
+    /// Create every field of the sheet that is not already set.
+    Init,
+}
+
+pub struct SheetCommand(pub SheetAction);
+
+impl TryFrom<String> for SheetCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let action = match input.trim().to_lowercase().as_ref() {
+            "" => SheetAction::Show,
+            "init" => SheetAction::Init,
+            _ => return Err(CommandError::InvalidCommand(input).into()),
+        };
+
+        Ok(SheetCommand(action))
+    }
+}
+
+/// Find the sheet template of the active room's game system.
+async fn room_sheet_template(ctx: &Context<'_>) -> Result<&'static SheetTemplate, BotError> {
+    let system = ctx
+        .db
+        .get_game_system(ctx.active_room_id().as_str())
+        .await?;
+
+    let name = system.map(|system| system.name()).unwrap_or("not set");
+    system
+        .and_then(|system| system.sheet_template())
+        .ok_or_else(|| BotError::NoSheetTemplate(name.to_owned()))
+}
+
+#[async_trait]
+impl Command for SheetCommand {
+    fn name(&self) -> &'static str {
+        "character sheet"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let template = room_sheet_template(ctx).await?;
+        let room_id = ctx.active_room_id().as_str();
+        let variables = ctx.db.get_user_variables(ctx.username, room_id).await?;
+
+        let html = match self.0 {
+            SheetAction::Show => template.render(&variables),
+            SheetAction::Init => {
+                let mut created = 0;
+                for field in template.fields() {
+                    if !variables.contains_key(field.name) {
+                        ctx.db
+                            .set_user_variable(ctx.username, room_id, field.name, field.default)
+                            .await?;
+                        created += 1;
+                    }
+                }
+
+                format!("<strong>Sheet created:</strong> {} new fields", created)
+            }
+        };
+
+        Execution::success(html)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_sheet_actions() {
+        let parse = |input: &str| {
+            SheetCommand::try_from(input.to_owned())
+                .ok()
+                .map(|cmd| cmd.0)
+        };
+        assert_eq!(Some(SheetAction::Show), parse(""));
+        assert_eq!(Some(SheetAction::Init), parse(" INIT "));
+        assert_eq!(None, parse("reset"));
+    }
+}
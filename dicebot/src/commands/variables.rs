@@ -1,10 +1,44 @@
-use super::{Command, Execution, ExecutionResult};
+use super::permissions::user_role;
+use super::rooms::{get_rooms_for_user, search_for_room};
+use super::{Command, CommandError, Execution, ExecutionResult};
 use crate::context::Context;
 use crate::db::errors::DataError;
-use crate::db::Variables;
-use crate::error::BotError;
+use crate::db::{RoomVariables, VariableHistory, Variables};
+use crate::error::{BotError, DiceRollingError};
+use crate::logic::VariableResolver;
+use crate::matrix;
+use crate::models::{Role, StoredValue, VariableBounds};
+use crate::parser::variables::{room_variable_name, VariableValue, ROOM_PREFIX};
 use async_trait::async_trait;
+use matrix_sdk::ruma::UserId;
+use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Sorted list of numbers, formulas and strings for display.
+fn list_variables(
+    numbers: HashMap<String, i32>,
+    formulas: HashMap<String, String>,
+    strings: HashMap<String, String>,
+) -> String {
+    let mut variable_list: Vec<String> = numbers
+        .into_iter()
+        .map(|(name, value)| format!(" - {} = {}", name, value))
+        .chain(
+            formulas
+                .into_iter()
+                .map(|(name, formula)| format!(" - {} = {}", name, formula)),
+        )
+        .chain(
+            strings
+                .into_iter()
+                .map(|(name, text)| format!(" - {} = \"{}\"", name, text)),
+        )
+        .collect();
+
+    variable_list.sort();
+    variable_list.join("<br/>")
+}
 
 pub struct GetAllVariablesCommand;
 
@@ -27,24 +61,38 @@ impl Command for GetAllVariablesCommand {
     }
 
     async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let room_id = ctx.active_room_id();
         let variables = ctx
             .db
-            .get_user_variables(&ctx.username, ctx.active_room_id().as_str())
+            .get_user_variables(ctx.username, room_id.as_str())
             .await?;
 
-        let mut variable_list: Vec<String> = variables
-            .into_iter()
-            .map(|(name, value)| format!(" - {} = {}", name, value))
-            .collect();
+        let formulas = ctx
+            .db
+            .get_user_formulas(ctx.username, room_id.as_str())
+            .await?;
 
-        variable_list.sort();
+        let strings = ctx
+            .db
+            .get_user_strings(ctx.username, room_id.as_str())
+            .await?;
 
-        let value = variable_list.join("\n");
-        let html = format!(
+        let mut html = format!(
             "<strong>Variables:</strong><br/>{}",
-            value.replace("\n", "<br/>")
+            list_variables(variables, formulas, strings)
         );
 
+        let room_variables = list_variables(
+            ctx.db.get_room_variables(room_id.as_str()).await?,
+            ctx.db.get_room_formulas(room_id.as_str()).await?,
+            ctx.db.get_room_strings(room_id.as_str()).await?,
+        );
+
+        if !room_variables.is_empty() {
+            html.push_str("<br/><strong>Room Variables:</strong><br/>");
+            html.push_str(&room_variables);
+        }
+
         Execution::success(html)
     }
 }
@@ -70,16 +118,23 @@ impl Command for GetVariableCommand {
     }
 
     async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        //Includes formulas and derived values from the room's
+        //character sheet.
         let name = &self.0;
-        let result = ctx
-            .db
-            .get_user_variable(&ctx.username, ctx.active_room_id().as_str(), name)
-            .await;
-
-        let value = match result {
-            Ok(num) => format!("{} = {}", name, num),
-            Err(DataError::KeyDoesNotExist(_)) => format!("{} is not set", name),
-            Err(e) => return Err(e.into()),
+        let variables = VariableResolver::load(ctx).await?;
+
+        let value = match (variables.resolve(name), variables.formula(name)) {
+            (Ok(num), Some(formula)) => format!("{} = {} ({})", name, num, formula),
+            (Ok(num), None) => format!("{} = {}", name, num),
+            (Err(DiceRollingError::VariableNotNumeric(_)), _) => {
+                format!(
+                    "{} = \"{}\"",
+                    name,
+                    variables.text(name).unwrap_or_default()
+                )
+            }
+            (Err(DiceRollingError::VariableNotFound(..)), _) => format!("{} is not set", name),
+            (Err(e), _) => return Err(e.into()),
         };
 
         let html = format!("<strong>Variable:</strong> {}", value);
@@ -87,7 +142,7 @@ impl Command for GetVariableCommand {
     }
 }
 
-pub struct SetVariableCommand(pub String, pub i32);
+pub struct SetVariableCommand(pub String, pub VariableValue);
 
 impl TryFrom<String> for SetVariableCommand {
     type Error = BotError;
@@ -108,20 +163,247 @@ impl Command for SetVariableCommand {
         false
     }
 
+    fn changes_room(&self) -> bool {
+        room_variable_name(&self.0).is_some()
+    }
+
     async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
         let name = &self.0;
-        let value = self.1;
-
-        ctx.db
-            .set_user_variable(&ctx.username, ctx.active_room_id().as_str(), name, value)
-            .await?;
+        let room_id = ctx.active_room_id();
+
+        if let Some(room_name) = room_variable_name(name) {
+            let content = set_room_variable(ctx, room_name, &self.1).await?;
+            let html = format!("<strong>Set Room Variable:</strong> {}", content);
+            return Execution::success(html);
+        }
+
+        let content = match &self.1 {
+            VariableValue::Number(value) => {
+                ctx.db
+                    .set_user_variable(ctx.username, room_id.as_str(), name, *value)
+                    .await?;
+
+                format!("{} = {}", name, value)
+            }
+            VariableValue::Formula(formula) => {
+                ctx.db
+                    .set_user_formula(ctx.username, room_id.as_str(), name, formula)
+                    .await?;
+
+                format!("{} = {}", name, formula)
+            }
+            VariableValue::Text(text) => {
+                ctx.db
+                    .set_user_string(ctx.username, room_id.as_str(), name, text)
+                    .await?;
+
+                format!("{} = \"{}\"", name, text)
+            }
+            VariableValue::Adjust(amount) => adjust_variable(ctx, name, *amount).await?,
+        };
 
-        let content = format!("{} = {}", name, value);
         let html = format!("<strong>Set Variable:</strong> {}", content);
         Execution::success(html)
     }
 }
 
+async fn set_room_variable(
+    ctx: &Context<'_>,
+    name: &str,
+    value: &VariableValue,
+) -> Result<String, BotError> {
+    let room_id = ctx.active_room_id().as_str();
+
+    let content = match value {
+        VariableValue::Number(value) => {
+            ctx.db.set_room_variable(room_id, name, *value).await?;
+            format!("{} = {}", name, value)
+        }
+        VariableValue::Formula(formula) => {
+            ctx.db.set_room_formula(room_id, name, formula).await?;
+            format!("{} = {}", name, formula)
+        }
+        VariableValue::Text(text) => {
+            ctx.db.set_room_string(room_id, name, text).await?;
+            format!("{} = \"{}\"", name, text)
+        }
+        VariableValue::Adjust(amount) => {
+            let prefixed = format!("{}{}", ROOM_PREFIX, name);
+            return adjust_variable(ctx, &prefixed, *amount).await;
+        }
+    };
+
+    Ok(content)
+}
+
+/// Atomically add an amount to a variable, and describe the change
+/// from the old to the new value.
+async fn adjust_variable(ctx: &Context<'_>, name: &str, amount: i32) -> Result<String, BotError> {
+    let room_id = ctx.active_room_id();
+    let result = match room_variable_name(name) {
+        Some(room_name) => {
+            ctx.db
+                .adjust_room_variable(room_id.as_str(), room_name, amount)
+                .await
+        }
+        None => {
+            ctx.db
+                .adjust_user_variable(ctx.username, room_id.as_str(), name, amount)
+                .await
+        }
+    };
+
+    let (old, new) = match result {
+        Ok(values) => values,
+        Err(DataError::KeyDoesNotExist(_)) => return Ok(format!("{} is not set", name)),
+        Err(e) => return Err(e.into()),
+    };
+
+    let clamped = if old + amount != new {
+        " (clamped)"
+    } else {
+        ""
+    };
+    Ok(format!("{}: {} → {}{}", name, old, new, clamped))
+}
+
+/// Increment or decrement a variable: !inc hp, !dec ammo 3.
+pub struct AdjustVariableCommand(pub String, pub i32);
+
+impl AdjustVariableCommand {
+    pub fn increment(input: String) -> Result<AdjustVariableCommand, BotError> {
+        AdjustVariableCommand::parse(input, 1)
+    }
+
+    pub fn decrement(input: String) -> Result<AdjustVariableCommand, BotError> {
+        AdjustVariableCommand::parse(input, -1)
+    }
+
+    fn parse(input: String, sign: i32) -> Result<AdjustVariableCommand, BotError> {
+        let parts: Vec<&str> = input.split_whitespace().collect();
+        let amount = match parts[..] {
+            [_] => Some(1),
+            [_, amount] => amount.parse::<i32>().ok().filter(|&amount| amount >= 0),
+            _ => None,
+        };
+
+        match amount {
+            Some(amount) => Ok(AdjustVariableCommand(parts[0].to_owned(), sign * amount)),
+            None => Err(CommandError::InvalidCommand(input).into()),
+        }
+    }
+}
+
+#[async_trait]
+impl Command for AdjustVariableCommand {
+    fn name(&self) -> &'static str {
+        "adjust variable value"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    fn changes_room(&self) -> bool {
+        room_variable_name(&self.0).is_some()
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let content = adjust_variable(ctx, &self.0, self.1).await?;
+        let html = format!("<strong>Variable:</strong> {}", content);
+        Execution::success(html)
+    }
+}
+
+/// Show or change the bounds of a variable.
+pub struct BoundsCommand(pub String, pub Option<VariableBounds>);
+
+impl TryFrom<String> for BoundsCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let invalid = || CommandError::InvalidCommand(input.clone());
+        let mut parts = input.split_whitespace();
+        let name = parts.next().ok_or_else(invalid)?.to_owned();
+        let rest: Vec<&str> = parts.collect();
+
+        //Room variables have no bounds.
+        if room_variable_name(&name).is_some() {
+            return Err(invalid().into());
+        }
+
+        if rest.is_empty() {
+            return Ok(BoundsCommand(name, None));
+        } else if rest == ["none"] {
+            return Ok(BoundsCommand(name, Some(VariableBounds::default())));
+        }
+
+        let mut bounds = VariableBounds::default();
+        let mut rest = rest.into_iter();
+        while let Some(part) = rest.next() {
+            let mut number = || rest.next().and_then(|num| num.parse::<i32>().ok());
+            match part {
+                "min" => bounds.min = Some(number().ok_or_else(invalid)?),
+                "max" => bounds.max = Some(number().ok_or_else(invalid)?),
+                "refuse" => bounds.refuse = true,
+                "clamp" => bounds.refuse = false,
+                _ => return Err(invalid().into()),
+            }
+        }
+
+        Ok(BoundsCommand(name, Some(bounds)))
+    }
+}
+
+fn describe_bounds(bounds: &VariableBounds) -> String {
+    let bound = |value: Option<i32>| value.map_or("none".to_string(), |v| v.to_string());
+    let mode = if bounds.refuse { "refuse" } else { "clamp" };
+    format!(
+        "min {}, max {}, {} when out of bounds",
+        bound(bounds.min),
+        bound(bounds.max),
+        mode
+    )
+}
+
+#[async_trait]
+impl Command for BoundsCommand {
+    fn name(&self) -> &'static str {
+        "variable bounds"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let name = &self.0;
+        let room_id = ctx.active_room_id();
+
+        let result = match &self.1 {
+            Some(bounds) => ctx
+                .db
+                .set_variable_bounds(ctx.username, room_id.as_str(), name, bounds)
+                .await
+                .map(|_| *bounds),
+            None => {
+                ctx.db
+                    .get_variable_bounds(ctx.username, room_id.as_str(), name)
+                    .await
+            }
+        };
+
+        let value = match result {
+            Ok(bounds) => format!("{}: {}", name, describe_bounds(&bounds)),
+            Err(DataError::KeyDoesNotExist(_)) => format!("{} is not set", name),
+            Err(e) => return Err(e.into()),
+        };
+
+        let html = format!("<strong>Bounds:</strong> {}", value);
+        Execution::success(html)
+    }
+}
+
 pub struct DeleteVariableCommand(pub String);
 
 impl TryFrom<String> for DeleteVariableCommand {
@@ -142,12 +424,21 @@ impl Command for DeleteVariableCommand {
         false
     }
 
+    fn changes_room(&self) -> bool {
+        room_variable_name(&self.0).is_some()
+    }
+
     async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
         let name = &self.0;
-        let result = ctx
-            .db
-            .delete_user_variable(&ctx.username, ctx.active_room_id().as_str(), name)
-            .await;
+        let room_id = ctx.active_room_id().as_str();
+        let result = match room_variable_name(name) {
+            Some(room_name) => ctx.db.delete_room_variable(room_id, room_name).await,
+            None => {
+                ctx.db
+                    .delete_user_variable(ctx.username, room_id, name)
+                    .await
+            }
+        };
 
         let value = match result {
             Ok(()) => format!("{} now unset", name),
@@ -159,3 +450,385 @@ impl Command for DeleteVariableCommand {
         Execution::success(html)
     }
 }
+
+/// Parse the !variables command and its subcommands. Without a
+/// subcommand, it lists the variables in the active room.
+pub fn parse_variables_command(input: String) -> Result<Box<dyn Command>, BotError> {
+    let input = input.trim();
+    let (action, rest) = match input.split_once(char::is_whitespace) {
+        Some((action, rest)) => (action, rest.trim()),
+        None => (input, ""),
+    };
+
+    match (action.to_lowercase().as_ref(), rest.is_empty()) {
+        ("", _) => Ok(Box::new(GetAllVariablesCommand)),
+        ("copy", false) => Ok(Box::new(CopyVariablesCommand::try_from(rest.to_owned())?)),
+        ("migrate", true) => Ok(Box::new(MigrateVariablesCommand)),
+        ("history", false) => Ok(Box::new(VariableHistoryCommand::try_from(rest.to_owned())?)),
+        _ => Err(CommandError::InvalidCommand(input.to_owned()).into()),
+    }
+}
+
+/// Split room arguments on whitespace. Room names containing spaces
+/// can be surrounded with double quotes.
+fn split_room_arguments(input: &str) -> Vec<String> {
+    let mut arguments = vec![];
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    arguments.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+
+    if !current.is_empty() {
+        arguments.push(current);
+    }
+
+    arguments
+}
+
+/// Copy the user's variables from one room to another. The
+/// destination defaults to the active room. Rooms are found by ID
+/// or by fuzzy searching their names.
+pub struct CopyVariablesCommand {
+    pub from: String,
+    pub to: Option<String>,
+}
+
+impl TryFrom<String> for CopyVariablesCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let mut arguments = split_room_arguments(&input).into_iter();
+        match (arguments.next(), arguments.next(), arguments.next()) {
+            (Some(from), to, None) => Ok(CopyVariablesCommand { from, to }),
+            _ => Err(CommandError::InvalidCommand(input).into()),
+        }
+    }
+}
+
+#[async_trait]
+impl Command for CopyVariablesCommand {
+    fn name(&self) -> &'static str {
+        "copy variables"
+    }
+
+    fn is_secure(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let rooms_for_user = get_rooms_for_user(&ctx.matrix_client, ctx.username).await?;
+        let from =
+            search_for_room(&rooms_for_user, &self.from).ok_or(BotError::RoomDoesNotExist)?;
+
+        let (to_id, to_name) = match &self.to {
+            Some(to) => search_for_room(&rooms_for_user, to)
+                .map(|room| (room.id.as_str(), room.name.as_str()))
+                .ok_or(BotError::RoomDoesNotExist)?,
+            None => (
+                ctx.active_room_id().as_str(),
+                ctx.active_room.display_name.as_str(),
+            ),
+        };
+
+        if from.id == to_id {
+            return Err(BotError::CopyToSameRoom);
+        }
+
+        let copied = ctx
+            .db
+            .copy_user_variables(ctx.username, &from.id, to_id)
+            .await?;
+
+        let html = format!(
+            r#"<strong>Copied:</strong> {} variables from "{}" to "{}""#,
+            copied, from.name, to_name
+        );
+
+        Execution::success(html)
+    }
+}
+
+/// Copy all variables, characters and room settings from the room
+/// that the active room replaced when it was upgraded.
+pub struct MigrateVariablesCommand;
+
+#[async_trait]
+impl Command for MigrateVariablesCommand {
+    fn name(&self) -> &'static str {
+        "migrate variables"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    fn changes_room(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let room_id = ctx.active_room_id();
+        let old_room = matrix::get_room_predecessor(&ctx.matrix_client, room_id)
+            .ok_or(BotError::NoPredecessorRoom)?;
+
+        let copied = ctx
+            .db
+            .copy_all_variables(old_room.as_str(), room_id.as_str())
+            .await?;
+
+        let html = format!(
+            "<strong>Migrated:</strong> {} variables from {}",
+            copied, old_room
+        );
+
+        Execution::success(html)
+    }
+}
+
+/// Number of changes shown by !variables history.
+const HISTORY_LENGTH: i64 = 10;
+
+/// A value in the history, or "unset" if the variable did not exist.
+fn describe_value(value: Option<&StoredValue>) -> String {
+    value
+        .map(|value| value.to_string())
+        .unwrap_or_else(|| "unset".to_string())
+}
+
+/// How long ago a UNIX timestamp was, roughly.
+fn time_ago(timestamp: i64) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|now| now.as_secs() as i64)
+        .unwrap_or(timestamp);
+
+    match now.saturating_sub(timestamp) {
+        seconds if seconds < 60 => "just now".to_string(),
+        seconds if seconds < 60 * 60 => format!("{} min ago", seconds / 60),
+        seconds if seconds < 60 * 60 * 24 => format!("{} h ago", seconds / (60 * 60)),
+        seconds => format!("{} days ago", seconds / (60 * 60 * 24)),
+    }
+}
+
+/// Show the recent changes to a variable. Moderators can also see
+/// the changes to the variables of other users in the room.
+pub struct VariableHistoryCommand {
+    pub name: String,
+    pub user: Option<String>,
+}
+
+impl TryFrom<String> for VariableHistoryCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let parts: Vec<&str> = input.split_whitespace().collect();
+        let (name, user) = match parts[..] {
+            [name] => (name, None),
+            [name, user] => (name, Some(UserId::try_from(user)?.to_string())),
+            _ => return Err(CommandError::InvalidCommand(input).into()),
+        };
+
+        Ok(VariableHistoryCommand {
+            name: name.to_owned(),
+            user,
+        })
+    }
+}
+
+#[async_trait]
+impl Command for VariableHistoryCommand {
+    fn name(&self) -> &'static str {
+        "variable history"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let room_id = ctx.active_room_id().as_str();
+        let user = self.user.as_deref().unwrap_or(ctx.username);
+
+        //Only GMs can see the history of other players.
+        if user != ctx.username && user_role(ctx).await? < Role::GameMaster {
+            return Err(BotError::RoleTooLow(
+                "variables history".to_owned(),
+                Role::GameMaster,
+            ));
+        }
+
+        let history = match room_variable_name(&self.name) {
+            Some(room_name) => {
+                ctx.db
+                    .get_variable_history(None, room_id, room_name, HISTORY_LENGTH)
+                    .await?
+            }
+            None => {
+                ctx.db
+                    .get_variable_history(Some(user), room_id, &self.name, HISTORY_LENGTH)
+                    .await?
+            }
+        };
+
+        let changes: Vec<String> = history
+            .iter()
+            .map(|change| {
+                format!(
+                    " - {} → {} by {} via {}, {}",
+                    describe_value(change.old_value.as_ref()),
+                    describe_value(change.new_value.as_ref()),
+                    change.changed_by,
+                    change.via,
+                    time_ago(change.changed_at)
+                )
+            })
+            .collect();
+
+        let html = match changes.is_empty() {
+            true => format!("<strong>History:</strong> {} has no changes", self.name),
+            false => format!(
+                "<strong>History of {}:</strong><br/>{}",
+                self.name,
+                changes.join("<br/>")
+            ),
+        };
+
+        Execution::success(html)
+    }
+}
+
+/// Undo the user's last change to their variables in the active
+/// room. Repeating it undoes earlier changes.
+pub struct UndoCommand;
+
+impl TryFrom<String> for UndoCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        match input.trim() {
+            "" => Ok(UndoCommand),
+            _ => Err(CommandError::InvalidCommand(input).into()),
+        }
+    }
+}
+
+#[async_trait]
+impl Command for UndoCommand {
+    fn name(&self) -> &'static str {
+        "undo variable change"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let room_id = ctx.active_room_id().as_str();
+        let change = ctx.db.undo_last_change(ctx.username, room_id).await?;
+
+        let html = match change {
+            Some(change) => format!(
+                "<strong>Undo:</strong> {}: {} → {} (undid {})",
+                change.key,
+                describe_value(change.new_value.as_ref()),
+                describe_value(change.old_value.as_ref()),
+                change.via
+            ),
+            None => "<strong>Undo:</strong> nothing to undo".to_string(),
+        };
+
+        Execution::success(html)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_increments_and_decrements() {
+        let inc = AdjustVariableCommand::increment("hp".to_owned()).unwrap();
+        assert_eq!(("hp", 1), (inc.0.as_str(), inc.1));
+
+        let dec = AdjustVariableCommand::decrement("ammo 3".to_owned()).unwrap();
+        assert_eq!(("ammo", -3), (dec.0.as_str(), dec.1));
+
+        assert!(AdjustVariableCommand::decrement("ammo -3".to_owned()).is_err());
+        assert!(AdjustVariableCommand::increment("".to_owned()).is_err());
+    }
+
+    #[test]
+    fn parses_bounds() {
+        let parse = |input: &str| {
+            BoundsCommand::try_from(input.to_owned())
+                .ok()
+                .map(|cmd| cmd.1)
+        };
+
+        assert_eq!(Some(None), parse("hp"));
+        assert_eq!(Some(Some(VariableBounds::default())), parse("hp none"));
+        assert_eq!(
+            Some(Some(VariableBounds {
+                min: Some(0),
+                max: Some(10),
+                refuse: true,
+            })),
+            parse("hp min 0 max 10 refuse")
+        );
+
+        assert_eq!(None, parse("hp max ten"));
+        assert_eq!(None, parse("room.difficulty min 0"));
+        assert_eq!(None, parse(""));
+    }
+
+    #[test]
+    fn parses_copy_rooms() {
+        let parse = |input: &str| {
+            CopyVariablesCommand::try_from(input.to_owned())
+                .ok()
+                .map(|cmd| (cmd.from, cmd.to))
+        };
+
+        assert_eq!(Some(("old".to_owned(), None)), parse("old"));
+        assert_eq!(
+            Some(("Old Game".to_owned(), Some("new".to_owned()))),
+            parse(r#""Old Game" new"#)
+        );
+
+        assert_eq!(None, parse(""));
+        assert_eq!(None, parse("one two three"));
+    }
+
+    #[test]
+    fn parses_history_user() {
+        let cmd = VariableHistoryCommand::try_from("hp @player:example.com".to_owned()).unwrap();
+        assert_eq!("hp", cmd.name);
+        assert_eq!(Some("@player:example.com".to_owned()), cmd.user);
+
+        assert!(VariableHistoryCommand::try_from("hp player".to_owned()).is_err());
+        assert!(VariableHistoryCommand::try_from("".to_owned()).is_err());
+    }
+
+    #[test]
+    fn describes_history_values() {
+        assert_eq!("unset", describe_value(None));
+        assert_eq!(
+            "= dex + 1",
+            describe_value(Some(&StoredValue::Formula("dex + 1".to_owned())))
+        );
+        assert_eq!(
+            "\"Longsword\"",
+            describe_value(Some(&StoredValue::Text("Longsword".to_owned())))
+        );
+    }
+}
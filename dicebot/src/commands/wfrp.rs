@@ -0,0 +1,48 @@
+use super::{Command, Execution, ExecutionResult};
+use crate::context::Context;
+use crate::error::BotError;
+use crate::wfrp::dice::{roll_wfrp_test, RolledWfrpTest, WfrpTest, WfrpTestWithContext};
+use crate::wfrp::parser::parse_wfrp_test;
+use async_trait::async_trait;
+use std::convert::TryFrom;
+
+pub struct WfrpRoll(pub WfrpTest);
+
+impl TryFrom<String> for WfrpRoll {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let test = parse_wfrp_test(&input)?;
+        Ok(WfrpRoll(test))
+    }
+}
+
+#[async_trait]
+impl Command for WfrpRoll {
+    fn name(&self) -> &'static str {
+        "roll wfrp test"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let test_with_ctx = WfrpTestWithContext(&self.0, ctx);
+        let rolled = roll_wfrp_test(&test_with_ctx).await?;
+
+        let roll = match &rolled {
+            RolledWfrpTest::Single(test) => format!("test against {}", test.target),
+            RolledWfrpTest::Opposed(first, second) => {
+                format!("opposed test of {} vs {}", first.target, second.target)
+            }
+        };
+
+        let html = format!(
+            "<strong>Roll:</strong> {}</p><p><strong>Result</strong>: {}",
+            roll, rolled
+        );
+
+        Execution::success(html)
+    }
+}
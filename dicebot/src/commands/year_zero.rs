@@ -0,0 +1,116 @@
+use super::{Command, CommandError, Execution, ExecutionResult};
+use crate::context::Context;
+use crate::db::YearZeroRolls;
+use crate::error::BotError;
+use crate::year_zero::dice::{panic_check, push_yze, roll_yze, RolledYze, YzeRoll};
+use crate::year_zero::parser::parse_yze_roll;
+use async_trait::async_trait;
+use std::convert::TryFrom;
+
+pub struct YzeRollCommand(pub YzeRoll);
+
+impl TryFrom<String> for YzeRollCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        let roll = parse_yze_roll(&input)?;
+        Ok(YzeRollCommand(roll))
+    }
+}
+
+/// Format a roll result, making a panic check if a stress die came up
+/// as a 1.
+fn fmt_result(rolled: &RolledYze) -> String {
+    let mut result = rolled.to_string();
+    if let Some(check) = panic_check(rolled) {
+        result.push_str(&format!("</p><p><strong>Panic check</strong>: {}", check));
+    }
+
+    result
+}
+
+#[async_trait]
+impl Command for YzeRollCommand {
+    fn name(&self) -> &'static str {
+        "roll year zero engine dice"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let rolled = roll_yze(&self.0);
+
+        ctx.db
+            .set_last_yze_roll(ctx.username, ctx.active_room_id().as_str(), &rolled)
+            .await?;
+
+        let html = format!(
+            "<strong>Roll:</strong> {}</p><p><strong>Result</strong>: {}",
+            self.0,
+            fmt_result(&rolled)
+        );
+
+        Execution::success(html)
+    }
+}
+
+pub struct PushCommand;
+
+impl TryFrom<String> for PushCommand {
+    type Error = BotError;
+
+    fn try_from(input: String) -> Result<Self, Self::Error> {
+        if input.trim().is_empty() {
+            Ok(PushCommand)
+        } else {
+            Err(CommandError::InvalidCommand(input).into())
+        }
+    }
+}
+
+#[async_trait]
+impl Command for PushCommand {
+    fn name(&self) -> &'static str {
+        "push year zero engine roll"
+    }
+
+    fn is_secure(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &Context<'_>) -> ExecutionResult {
+        let room_id = ctx.active_room_id().as_str();
+        let rolled = ctx
+            .db
+            .get_last_yze_roll(ctx.username, room_id)
+            .await?
+            .ok_or(BotError::NoRollToPush)?;
+
+        if rolled.pushed {
+            return Err(BotError::RollAlreadyPushed);
+        }
+
+        let pushed = push_yze(&rolled);
+        ctx.db
+            .set_last_yze_roll(ctx.username, room_id, &pushed)
+            .await?;
+
+        let html = format!("<strong>Pushed roll</strong>: {}", fmt_result(&pushed));
+
+        Execution::success(html)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn push_takes_no_arguments() {
+        assert!(PushCommand::try_from("".to_owned()).is_ok());
+        assert!(PushCommand::try_from("  ".to_owned()).is_ok());
+        assert!(PushCommand::try_from("again".to_owned()).is_err());
+    }
+}
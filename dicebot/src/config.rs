@@ -11,6 +11,19 @@ pub enum ConfigError {
 
     #[error("toml parsing error: {0}")]
     TomlParsingError(#[from] toml::de::Error),
+
+    #[error("invalid command prefix (no letters, digits or spaces): {0:?}")]
+    InvalidCommandPrefix(String),
+}
+
+/// Whether the text can start commands. A prefix with letters, digits
+/// or spaces would make normal words into commands, and an empty one
+/// would make every message a command.
+pub fn is_valid_command_prefix(prefix: &str) -> bool {
+    !prefix.is_empty()
+        && !prefix
+            .chars()
+            .any(|c| c.is_alphanumeric() || c.is_whitespace())
 }
 
 pub fn read_config<P: Into<PathBuf>>(config_path: P) -> Result<Config, ConfigError> {
@@ -24,8 +37,18 @@ pub fn read_config<P: Into<PathBuf>>(config_path: P) -> Result<Config, ConfigErr
 }
 
 fn deserialize_config(contents: &str) -> Result<Config, ConfigError> {
-    let config = toml::from_str(&contents)?;
-    Ok(config)
+    let config: Config = toml::from_str(&contents)?;
+    let prefix = config
+        .bot
+        .as_ref()
+        .and_then(|bc| bc.command_prefix.as_ref());
+
+    match prefix {
+        Some(prefix) if !is_valid_command_prefix(prefix) => {
+            Err(ConfigError::InvalidCommandPrefix(prefix.clone()))
+        }
+        _ => Ok(config),
+    }
 }
 
 /// The "matrix" section of the config, which gives home server, login information, and etc.
@@ -43,6 +66,8 @@ struct MatrixConfig {
 
 const DEFAULT_OLDEST_MESSAGE_AGE: u64 = 15 * 60;
 
+const DEFAULT_COMMAND_PREFIX: &str = "!";
+
 fn db_path_from_env() -> String {
     env::var("DATABASE_PATH")
         .expect("could not find database path in config or environment variable")
@@ -62,6 +87,13 @@ struct BotConfig {
     /// want to connect to it. The RPC server will reject any clients
     /// that don't present the shared key.
     rpc_key: Option<String>,
+
+    /// What commands start with, unless a room has its own prefix.
+    command_prefix: Option<String>,
+
+    /// Matrix user IDs of the bot admins, who can run any command in
+    /// any room.
+    bot_admins: Option<Vec<String>>,
 }
 
 /// The "database" section of the config file.
@@ -101,6 +133,16 @@ impl BotConfig {
     fn rpc_key(&self) -> Option<String> {
         self.rpc_key.clone()
     }
+
+    /// The command prefix, or DEFAULT_COMMAND_PREFIX ("!") if it is
+    /// not defined.
+    #[inline]
+    #[must_use]
+    fn command_prefix(&self) -> String {
+        self.command_prefix
+            .clone()
+            .unwrap_or_else(|| DEFAULT_COMMAND_PREFIX.to_owned())
+    }
 }
 
 /// Represents the toml config file for the dicebot. The sections of
@@ -170,6 +212,29 @@ impl Config {
     pub fn rpc_key(&self) -> Option<String> {
         self.bot.as_ref().and_then(|bc| bc.rpc_key())
     }
+
+    /// What commands start with in rooms that do not have their own
+    /// prefix. Defaults to "!".
+    #[inline]
+    #[must_use]
+    pub fn command_prefix(&self) -> String {
+        self.bot
+            .as_ref()
+            .map(|bc| bc.command_prefix())
+            .unwrap_or_else(|| DEFAULT_COMMAND_PREFIX.to_owned())
+    }
+
+    /// Whether the user is one of the bot admins. There are no bot
+    /// admins unless they are listed in the config.
+    #[inline]
+    #[must_use]
+    pub fn is_bot_admin(&self, user_id: &str) -> bool {
+        self.bot
+            .as_ref()
+            .and_then(|bc| bc.bot_admins.as_ref())
+            .map(|admins| admins.iter().any(|admin| admin == user_id))
+            .unwrap_or(false)
+    }
 }
 
 #[cfg(test)]
@@ -213,6 +278,84 @@ mod tests {
         assert_eq!(15 * 60, cfg.oldest_message_age());
     }
 
+    #[test]
+    fn command_prefix_default_test() {
+        let cfg = Config {
+            matrix: MatrixConfig {
+                home_server: "".to_owned(),
+                username: "".to_owned(),
+                password: "".to_owned(),
+            },
+            database: None,
+            bot: Some(BotConfig::default()),
+        };
+
+        assert_eq!("!", cfg.command_prefix());
+    }
+
+    #[test]
+    fn command_prefix_uses_setting_test() {
+        let cfg = Config {
+            matrix: MatrixConfig {
+                home_server: "".to_owned(),
+                username: "".to_owned(),
+                password: "".to_owned(),
+            },
+            database: None,
+            bot: Some(BotConfig {
+                command_prefix: Some(".".to_owned()),
+                ..Default::default()
+            }),
+        };
+
+        assert_eq!(".", cfg.command_prefix());
+    }
+
+    #[test]
+    fn command_prefix_validation_test() {
+        assert!(is_valid_command_prefix("!"));
+        assert!(is_valid_command_prefix(".."));
+        assert!(!is_valid_command_prefix(""));
+        assert!(!is_valid_command_prefix("a"));
+        assert!(!is_valid_command_prefix("!1"));
+        assert!(!is_valid_command_prefix("! "));
+    }
+
+    #[test]
+    fn invalid_command_prefix_is_rejected_test() {
+        let contents = indoc::indoc! {r#"
+            [matrix]
+            home_server = "example.com"
+            username = "dicebot"
+            password = "hunter2"
+
+            [bot]
+            command_prefix = ""
+        "#};
+
+        let result = deserialize_config(contents);
+        assert!(matches!(result, Err(ConfigError::InvalidCommandPrefix(_))));
+    }
+
+    #[test]
+    fn bot_admins_test() {
+        let cfg = Config {
+            matrix: MatrixConfig {
+                home_server: "".to_owned(),
+                username: "".to_owned(),
+                password: "".to_owned(),
+            },
+            database: None,
+            bot: Some(BotConfig {
+                bot_admins: Some(vec!["@admin:example.com".to_owned()]),
+                ..Default::default()
+            }),
+        };
+
+        assert!(cfg.is_bot_admin("@admin:example.com"));
+        assert!(!cfg.is_bot_admin("@player:example.com"));
+    }
+
     #[test]
     fn db_path_uses_setting_first_test() {
         let cfg = Config {
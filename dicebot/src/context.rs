@@ -2,7 +2,7 @@ use crate::db::sqlite::Database;
 use crate::error::BotError;
 use crate::models::Account;
 use matrix_sdk::room::Joined;
-use matrix_sdk::ruma::identifiers::{RoomId, UserId};
+use matrix_sdk::ruma::identifiers::{EventId, RoomId, UserId};
 use matrix_sdk::Client;
 use std::convert::TryFrom;
 
@@ -17,6 +17,13 @@ pub struct Context<'a> {
     pub username: &'a str,
     pub message_body: &'a str,
     pub account: Account,
+
+    /// The message that the command message replies to, if any (e.g.
+    /// a file to import).
+    pub in_reply_to: Option<EventId>,
+
+    /// Whether the user is one of the bot admins in the config.
+    pub bot_admin: bool,
 }
 
 impl Context<'_> {
@@ -256,8 +256,8 @@ impl fmt::Display for RolledAdvancement {
 }
 
 /// This is a trait so we can inject controlled dice rolls in unit
-/// tests.
-trait DieRoller {
+/// tests. Rolls a single d10, from 0 to 9.
+pub(crate) trait DieRoller {
     fn roll(&mut self) -> u32;
 }
 
@@ -276,7 +276,7 @@ macro_rules! is_variable {
 
 /// A die roller than can have an RNG implementation injected, but
 /// must be thread-safe. Required for the async dice rolling code.
-struct RngDieRoller<R: Rng + ?Sized + Send>(R);
+pub(crate) struct RngDieRoller<R: Rng + ?Sized + Send>(pub R);
 
 impl<R: Rng + ?Sized + Send> DieRoller for RngDieRoller<R> {
     fn roll(&mut self) -> u32 {
@@ -289,7 +289,7 @@ impl<R: Rng + ?Sized + Send> DieRoller for RngDieRoller<R> {
 /// with bonus/penalty dice. The unit roll (ones place) is added to
 /// the tens roll, unless both results are 0, in which case the result
 /// is 100.
-fn roll_percentile_dice<R: DieRoller>(roller: &mut R, unit_roll: u32) -> u32 {
+pub(crate) fn roll_percentile_dice<R: DieRoller>(roller: &mut R, unit_roll: u32) -> u32 {
     let tens_roll = roller.roll() * 10;
 
     if tens_roll == 0 && unit_roll == 0 {
@@ -299,6 +299,42 @@ fn roll_percentile_dice<R: DieRoller>(roller: &mut R, unit_roll: u32) -> u32 {
     }
 }
 
+/// Roll a plain d100 (1 to 100): the unit die first, then the tens
+/// die.
+pub(crate) fn roll_d100<R: DieRoller>(roller: &mut R) -> u32 {
+    let unit_roll = roller.roll();
+    roll_percentile_dice(roller, unit_roll)
+}
+
+/// Generate a series of numbers manually for testing. For this die
+/// system, the first roll in the Vec should be the unit roll, and any
+/// subsequent rolls should be the tens place roll. The results rolled
+/// must come from a d10 (0 to 9).
+#[cfg(test)]
+pub(crate) struct SequentialDieRoller {
+    results: Vec<u32>,
+    position: usize,
+}
+
+#[cfg(test)]
+impl SequentialDieRoller {
+    pub fn new(results: Vec<u32>) -> SequentialDieRoller {
+        SequentialDieRoller {
+            results,
+            position: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+impl DieRoller for SequentialDieRoller {
+    fn roll(&mut self) -> u32 {
+        let roll = self.results[self.position];
+        self.position += 1;
+        roll
+    }
+}
+
 fn roll_regular_dice<R: DieRoller>(
     modifier: &DiceRollModifier,
     target: u32,
@@ -332,8 +368,7 @@ fn roll_regular_dice<R: DieRoller>(
 }
 
 fn roll_advancement_dice<R: DieRoller>(target: u32, roller: &mut R) -> RolledAdvancement {
-    let unit_roll = roller.roll();
-    let percentile_roll = roll_percentile_dice(roller, unit_roll);
+    let percentile_roll = roll_d100(roller);
 
     if percentile_roll > target || percentile_roll > 95 {
         RolledAdvancement {
@@ -438,32 +473,6 @@ mod tests {
         };
     }
 
-    /// Generate a series of numbers manually for testing. For this
-    /// die system, the first roll in the Vec should be the unit roll,
-    /// and any subsequent rolls should be the tens place roll. The
-    /// results rolled must come from a d10 (0 to 9).
-    struct SequentialDieRoller {
-        results: Vec<u32>,
-        position: usize,
-    }
-
-    impl SequentialDieRoller {
-        fn new(results: Vec<u32>) -> SequentialDieRoller {
-            SequentialDieRoller {
-                results,
-                position: 0,
-            }
-        }
-    }
-
-    impl DieRoller for SequentialDieRoller {
-        fn roll(&mut self) -> u32 {
-            let roll = self.results[self.position];
-            self.position += 1;
-            roll
-        }
-    }
-
     #[test]
     fn extract_variable_gets_variable_name() {
         let amount = Amount {
@@ -516,6 +525,8 @@ mod tests {
             active_room: dummy_room!(),
             username: "username",
             message_body: "message",
+            in_reply_to: None,
+            bot_admin: false,
         };
 
         let roll_with_ctx = DiceRollWithContext(&roll, &ctx);
@@ -554,6 +565,8 @@ mod tests {
             active_room: dummy_room!(),
             username: "username",
             message_body: "message",
+            in_reply_to: None,
+            bot_admin: false,
         };
 
         let roll_with_ctx = AdvancementRollWithContext(&roll, &ctx);
@@ -592,6 +605,8 @@ mod tests {
             active_room: dummy_room!(),
             username: "username",
             message_body: "message",
+            in_reply_to: None,
+            bot_admin: false,
         };
 
         let roll_with_ctx = AdvancementRollWithContext(&roll, &ctx);
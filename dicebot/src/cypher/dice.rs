@@ -0,0 +1,340 @@
+use crate::dice::{DieRoller, RngDieRoller};
+use std::fmt;
+
+/// The target number is the difficulty times three.
+const TARGET_MULTIPLIER: u32 = 3;
+
+/// Applying the first level of effort costs 3 points from a pool.
+const FIRST_EFFORT_COST: u32 = 3;
+
+/// Every level of effort after the first costs 2 more points.
+const ADDITIONAL_EFFORT_COST: u32 = 2;
+
+/// Tasks are rated from 0 (routine) to 10 (impossible).
+pub const MAX_DIFFICULTY: u32 = 10;
+
+/// No more than two assets can ease a task.
+pub const MAX_ASSETS: u32 = 2;
+
+/// One of the three stat pools that effort is paid from.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Stat {
+    Might,
+    Speed,
+    Intellect,
+}
+
+impl Stat {
+    /// Name of the user variable holding the current pool.
+    pub fn pool_variable(&self) -> &'static str {
+        match self {
+            Self::Might => "might",
+            Self::Speed => "speed",
+            Self::Intellect => "intellect",
+        }
+    }
+
+    /// Name of the user variable holding the Edge for this stat.
+    pub fn edge_variable(&self) -> &'static str {
+        match self {
+            Self::Might => "mightedge",
+            Self::Speed => "speededge",
+            Self::Intellect => "intellectedge",
+        }
+    }
+}
+
+impl fmt::Display for Stat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Self::Might => "Might",
+            Self::Speed => "Speed",
+            Self::Intellect => "Intellect",
+        };
+
+        write!(f, "{}", message)
+    }
+}
+
+/// How good the character is at the skill used for the task.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SkillLevel {
+    /// Eases the task by one step.
+    Trained,
+
+    /// Eases the task by two steps.
+    Specialized,
+}
+
+impl SkillLevel {
+    pub fn steps(&self) -> u32 {
+        match self {
+            Self::Trained => 1,
+            Self::Specialized => 2,
+        }
+    }
+}
+
+/// A task to attempt: its difficulty, and everything easing it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CypherTask {
+    pub difficulty: u32,
+    pub effort: u32,
+    pub assets: u32,
+    pub skill: Option<SkillLevel>,
+
+    /// The pool effort is paid from, if any.
+    pub stat: Option<Stat>,
+}
+
+impl CypherTask {
+    /// Each level of effort, each asset and the skill level ease the
+    /// task by one step (two for specialized).
+    pub fn easing_steps(&self) -> u32 {
+        let skill = self.skill.map(|skill| skill.steps()).unwrap_or(0);
+        self.effort + self.assets + skill
+    }
+
+    /// Difficulty after easing, which can never go below 0.
+    pub fn eased_difficulty(&self) -> u32 {
+        self.difficulty.saturating_sub(self.easing_steps())
+    }
+
+    /// The number the d20 must meet or beat.
+    pub fn target_number(&self) -> u32 {
+        self.eased_difficulty() * TARGET_MULTIPLIER
+    }
+
+    /// Points of the pool that the effort costs, after subtracting
+    /// the stat's Edge.
+    pub fn effort_cost(&self, edge: u32) -> u32 {
+        if self.effort == 0 {
+            return 0;
+        }
+
+        let cost = FIRST_EFFORT_COST + (self.effort - 1) * ADDITIONAL_EFFORT_COST;
+        cost.saturating_sub(edge)
+    }
+}
+
+impl fmt::Display for CypherTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "difficulty {}", self.difficulty)?;
+
+        let steps = self.easing_steps();
+        if steps > 0 {
+            write!(f, " eased by {} to {}", steps, self.eased_difficulty())?;
+        }
+
+        write!(f, " (target {})", self.target_number())
+    }
+}
+
+/// Special results for high and low d20 rolls. They apply whether the
+/// task succeeded or not.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Special {
+    /// A natural 1: the GM intrudes, at no XP reward to the player.
+    GmIntrusion,
+
+    /// A 17 or 18: extra damage if the task was an attack.
+    BonusDamage(u32),
+
+    /// A 19: a minor effect (or +3 damage).
+    MinorEffect,
+
+    /// A 20: a major effect (or +4 damage).
+    MajorEffect,
+}
+
+impl fmt::Display for Special {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GmIntrusion => write!(f, "GM intrusion!"),
+            Self::BonusDamage(damage) => write!(f, "+{} damage if attacking!", damage),
+            Self::MinorEffect => write!(f, "minor effect!"),
+            Self::MajorEffect => write!(f, "major effect!"),
+        }
+    }
+}
+
+/// A completed task. If the task was eased to difficulty 0, it
+/// succeeds automatically and there is no roll.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RolledTask {
+    pub(crate) task: CypherTask,
+    pub(crate) roll: Option<u32>,
+}
+
+impl RolledTask {
+    pub fn is_success(&self) -> bool {
+        match self.roll {
+            Some(roll) => roll >= self.task.target_number(),
+            None => true,
+        }
+    }
+
+    pub fn special(&self) -> Option<Special> {
+        match self.roll {
+            Some(1) => Some(Special::GmIntrusion),
+            Some(17) => Some(Special::BonusDamage(1)),
+            Some(18) => Some(Special::BonusDamage(2)),
+            Some(19) => Some(Special::MinorEffect),
+            Some(20) => Some(Special::MajorEffect),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for RolledTask {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let roll = match self.roll {
+            Some(roll) => roll,
+            None => return write!(f, "routine task, automatic success!"),
+        };
+
+        let result = if self.is_success() {
+            "success!"
+        } else {
+            "failure!"
+        };
+
+        write!(
+            f,
+            "{} against {}: {}",
+            roll,
+            self.task.target_number(),
+            result
+        )?;
+
+        if let Some(special) = self.special() {
+            write!(f, " {}", special)?;
+        }
+
+        Ok(())
+    }
+}
+
+fn roll_task_die<R: DieRoller>(task: &CypherTask, roller: &mut R) -> RolledTask {
+    let roll = if task.eased_difficulty() > 0 {
+        Some(roller.roll_number(20))
+    } else {
+        None
+    };
+
+    RolledTask {
+        task: task.clone(),
+        roll,
+    }
+}
+
+/// Attempt a task by rolling a d20 against its eased target number.
+pub fn roll_task(task: &CypherTask) -> RolledTask {
+    let mut roller = RngDieRoller(rand::thread_rng());
+    roll_task_die(task, &mut roller)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::SequentialDieRoller;
+
+    fn task(difficulty: u32) -> CypherTask {
+        CypherTask {
+            difficulty,
+            effort: 0,
+            assets: 0,
+            skill: None,
+            stat: None,
+        }
+    }
+
+    #[test]
+    fn target_is_difficulty_times_three() {
+        assert_eq!(12, task(4).target_number());
+        assert_eq!(30, task(10).target_number());
+    }
+
+    #[test]
+    fn easing_steps_lower_difficulty() {
+        let eased = CypherTask {
+            effort: 1,
+            assets: 1,
+            skill: Some(SkillLevel::Specialized),
+            ..task(6)
+        };
+
+        assert_eq!(4, eased.easing_steps());
+        assert_eq!(2, eased.eased_difficulty());
+        assert_eq!(6, eased.target_number());
+    }
+
+    #[test]
+    fn difficulty_cannot_go_below_zero() {
+        let eased = CypherTask {
+            assets: 2,
+            skill: Some(SkillLevel::Trained),
+            ..task(2)
+        };
+
+        assert_eq!(0, eased.eased_difficulty());
+    }
+
+    #[test]
+    fn routine_task_is_not_rolled() {
+        let mut roller = SequentialDieRoller::new(vec![]);
+        let rolled = roll_task_die(&task(0), &mut roller);
+        assert_eq!(None, rolled.roll);
+        assert!(rolled.is_success());
+    }
+
+    #[test]
+    fn roll_meets_or_beats_target() {
+        let mut roller = SequentialDieRoller::new(vec![12, 11]);
+        assert!(roll_task_die(&task(4), &mut roller).is_success());
+        assert!(!roll_task_die(&task(4), &mut roller).is_success());
+    }
+
+    #[test]
+    fn effort_cost_with_edge() {
+        let effort = |effort| CypherTask { effort, ..task(5) };
+        assert_eq!(0, effort(0).effort_cost(1));
+        assert_eq!(3, effort(1).effort_cost(0));
+        assert_eq!(7, effort(3).effort_cost(0));
+        assert_eq!(5, effort(2).effort_cost(0));
+        assert_eq!(4, effort(2).effort_cost(1));
+        assert_eq!(0, effort(1).effort_cost(4));
+    }
+
+    #[test]
+    fn special_results() {
+        let rolled = |roll| RolledTask {
+            task: task(3),
+            roll: Some(roll),
+        };
+
+        assert_eq!(Some(Special::GmIntrusion), rolled(1).special());
+        assert_eq!(None, rolled(16).special());
+        assert_eq!(Some(Special::BonusDamage(1)), rolled(17).special());
+        assert_eq!(Some(Special::BonusDamage(2)), rolled(18).special());
+        assert_eq!(Some(Special::MinorEffect), rolled(19).special());
+        assert_eq!(Some(Special::MajorEffect), rolled(20).special());
+    }
+
+    #[test]
+    fn formats_task_and_result() {
+        let eased = CypherTask {
+            effort: 1,
+            ..task(4)
+        };
+
+        assert_eq!("difficulty 4 eased by 1 to 3 (target 9)", eased.to_string());
+
+        let rolled = RolledTask {
+            task: eased,
+            roll: Some(19),
+        };
+
+        assert_eq!("19 against 9: success! minor effect!", rolled.to_string());
+    }
+}
@@ -0,0 +1,97 @@
+use super::dice::{CypherTask, SkillLevel, Stat, MAX_ASSETS, MAX_DIFFICULTY};
+use crate::parser::dice::DiceParsingError;
+
+fn parse_number(input: Option<&str>, max: u32) -> Result<u32, DiceParsingError> {
+    let number: u32 = input.ok_or(DiceParsingError::InvalidAmount)?.parse()?;
+    if number > max {
+        return Err(DiceParsingError::InvalidAmount);
+    }
+
+    Ok(number)
+}
+
+fn parse_skill(input: Option<&str>) -> Result<SkillLevel, DiceParsingError> {
+    match input {
+        Some("trained") => Ok(SkillLevel::Trained),
+        Some("specialized") | Some("specialised") => Ok(SkillLevel::Specialized),
+        _ => Err(DiceParsingError::InvalidModifiers),
+    }
+}
+
+/// Parse a Cypher System task: the difficulty, followed by any of
+/// "effort <num>", "asset <num>", "skill trained|specialized", and the
+/// stat (might, speed or intellect) that effort is paid from.
+pub fn parse_cypher_task(input: &str) -> Result<CypherTask, DiceParsingError> {
+    let mut parts = input.split_whitespace();
+    let mut task = CypherTask {
+        difficulty: parse_number(parts.next(), MAX_DIFFICULTY)?,
+        effort: 0,
+        assets: 0,
+        skill: None,
+        stat: None,
+    };
+
+    while let Some(part) = parts.next() {
+        match part {
+            "effort" => task.effort = parse_number(parts.next(), MAX_DIFFICULTY)?,
+            "asset" | "assets" => task.assets = parse_number(parts.next(), MAX_ASSETS)?,
+            "skill" => task.skill = Some(parse_skill(parts.next())?),
+            "might" => task.stat = Some(Stat::Might),
+            "speed" => task.stat = Some(Stat::Speed),
+            "intellect" => task.stat = Some(Stat::Intellect),
+            _ => return Err(DiceParsingError::UnconsumedInput),
+        }
+    }
+
+    Ok(task)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn difficulty_only_test() {
+        let task = parse_cypher_task("4").expect("was error");
+        assert_eq!(4, task.difficulty);
+        assert_eq!(0, task.easing_steps());
+    }
+
+    #[test]
+    fn full_task_test() {
+        let task =
+            parse_cypher_task("6 effort 2 asset 1 skill specialized speed").expect("was error");
+
+        assert_eq!(
+            CypherTask {
+                difficulty: 6,
+                effort: 2,
+                assets: 1,
+                skill: Some(SkillLevel::Specialized),
+                stat: Some(Stat::Speed),
+            },
+            task
+        );
+    }
+
+    #[test]
+    fn rejects_out_of_range_test() {
+        assert!(matches!(
+            parse_cypher_task("11"),
+            Err(DiceParsingError::InvalidAmount)
+        ));
+
+        assert!(matches!(
+            parse_cypher_task("4 asset 3"),
+            Err(DiceParsingError::InvalidAmount)
+        ));
+    }
+
+    #[test]
+    fn rejects_bad_input_test() {
+        assert!(parse_cypher_task("").is_err());
+        assert!(parse_cypher_task("4 effort").is_err());
+        assert!(parse_cypher_task("4 skill great").is_err());
+        assert!(parse_cypher_task("4 luck 2").is_err());
+    }
+}
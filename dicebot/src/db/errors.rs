@@ -9,6 +9,18 @@ pub enum DataError {
     #[error("too many entries")]
     TooManyEntries,
 
+    #[error("{0} cannot go below {1}")]
+    BelowMinimum(String, i32),
+
+    #[error("{0} cannot go above {1}")]
+    AboveMaximum(String, i32),
+
+    #[error("character does not exist: {0}")]
+    CharacterDoesNotExist(String),
+
+    #[error("character already exists: {0}")]
+    CharacterAlreadyExists(String),
+
     #[error("expected i32, but i32 schema was violated")]
     I32SchemaViolation,
 
@@ -21,12 +33,26 @@ pub enum DataError {
     #[error("expected string, but utf8 schema was violated: {0}")]
     Utf8SchemaViolation(#[from] std::string::FromUtf8Error),
 
+    //The migration and sqlx errors are boxed, so that results that
+    //can fail with a database error stay small.
     #[error("data migration error: {0}")]
-    MigrationError(#[from] crate::db::sqlite::migrator::MigrationError),
+    MigrationError(Box<crate::db::sqlite::migrator::MigrationError>),
 
     #[error("internal database error: {0}")]
-    SqlxError(#[from] sqlx::Error),
+    SqlxError(Box<sqlx::Error>),
 
     #[error("numeric conversion error")]
     NumericConversionError(#[from] TryFromIntError),
 }
+
+impl From<crate::db::sqlite::migrator::MigrationError> for DataError {
+    fn from(error: crate::db::sqlite::migrator::MigrationError) -> Self {
+        DataError::MigrationError(Box::new(error))
+    }
+}
+
+impl From<sqlx::Error> for DataError {
+    fn from(error: sqlx::Error) -> Self {
+        DataError::SqlxError(Box::new(error))
+    }
+}
@@ -1,5 +1,9 @@
 use crate::error::BotError;
-use crate::models::User;
+use crate::models::{
+    Macro, MacroScope, Role, RoomSettings, User, VariableBounds, VariableChange, VariableSet,
+};
+use crate::systems::GameSystem;
+use crate::year_zero::dice::RolledYze;
 use async_trait::async_trait;
 use errors::DataError;
 use std::collections::HashMap;
@@ -32,10 +36,131 @@ pub(crate) trait Users {
 #[async_trait]
 pub(crate) trait Rooms {
     async fn should_process(&self, room_id: &str, event_id: &str) -> Result<bool, DataError>;
+
+    async fn get_game_system(&self, room_id: &str) -> Result<Option<GameSystem>, DataError>;
+
+    async fn set_game_system(&self, room_id: &str, system: GameSystem) -> Result<(), DataError>;
+
+    /// Settings of the room, or the default settings if they were
+    /// never changed.
+    async fn get_room_settings(&self, room_id: &str) -> Result<RoomSettings, DataError>;
+
+    async fn set_room_settings(
+        &self,
+        room_id: &str,
+        settings: &RoomSettings,
+    ) -> Result<(), DataError>;
+
+    /// Commands whose role was changed in the room, with the role that
+    /// they need instead of their default role.
+    async fn get_command_roles(&self, room_id: &str) -> Result<Vec<(String, Role)>, DataError>;
+
+    async fn get_command_role(
+        &self,
+        room_id: &str,
+        command: &str,
+    ) -> Result<Option<Role>, DataError>;
+
+    /// Change the role needed to run a command in the room, or go back
+    /// to the default role of the command if the role is None.
+    async fn set_command_role(
+        &self,
+        room_id: &str,
+        command: &str,
+        role: Option<Role>,
+    ) -> Result<(), DataError>;
+}
+
+#[async_trait]
+pub(crate) trait Characters {
+    async fn create_character(
+        &self,
+        user: &str,
+        room_id: &str,
+        name: &str,
+    ) -> Result<(), DataError>;
+
+    async fn get_characters(&self, user: &str, room_id: &str) -> Result<Vec<String>, DataError>;
+
+    async fn get_active_character(
+        &self,
+        user: &str,
+        room_id: &str,
+    ) -> Result<Option<String>, DataError>;
+
+    /// Switch to the named character, or back to the user's own
+    /// variables if the name is None.
+    async fn set_active_character(
+        &self,
+        user: &str,
+        room_id: &str,
+        name: Option<&str>,
+    ) -> Result<(), DataError>;
+
+    /// Delete a character and all of its variables.
+    async fn delete_character(
+        &self,
+        user: &str,
+        room_id: &str,
+        name: &str,
+    ) -> Result<(), DataError>;
+}
+
+#[async_trait]
+pub(crate) trait YearZeroRolls {
+    async fn get_last_yze_roll(
+        &self,
+        user: &str,
+        room_id: &str,
+    ) -> Result<Option<RolledYze>, DataError>;
+
+    async fn set_last_yze_roll(
+        &self,
+        user: &str,
+        room_id: &str,
+        roll: &RolledYze,
+    ) -> Result<(), DataError>;
+}
+
+/// Macros of a user, or of a room. Macro names are case-insensitive,
+/// and returned in lower case.
+#[async_trait]
+pub(crate) trait Macros {
+    /// The macro of the given name: the user's macro comes first,
+    /// then the room's.
+    async fn get_macro(
+        &self,
+        user: &str,
+        room_id: &str,
+        name: &str,
+    ) -> Result<Option<Macro>, DataError>;
+
+    /// All macros of the user and the room, sorted by scope and name.
+    async fn get_macros(&self, user: &str, room_id: &str) -> Result<Vec<Macro>, DataError>;
+
+    /// Store a macro for the owner (a user ID or a room ID, depending
+    /// on the scope), replacing any macro of the same name.
+    async fn set_macro(
+        &self,
+        scope: MacroScope,
+        owner_id: &str,
+        name: &str,
+        commands: &[String],
+    ) -> Result<(), DataError>;
+
+    async fn delete_macro(
+        &self,
+        scope: MacroScope,
+        owner_id: &str,
+        name: &str,
+    ) -> Result<(), DataError>;
 }
 
 // TODO move this up to the top once we delete sled. Traits will be the
 // main API, then we can have different impls for different DBs.
+/// Variables belong to the user's active character in the room, or to
+/// the user directly if no character is active. Variable names are
+/// case-insensitive, and returned in lower case.
 #[async_trait]
 pub trait Variables {
     async fn get_user_variables(
@@ -61,6 +186,98 @@ pub trait Variables {
         value: i32,
     ) -> Result<(), DataError>;
 
+    /// Formula variables, by name, with their unevaluated expressions.
+    async fn get_user_formulas(
+        &self,
+        user: &str,
+        room_id: &str,
+    ) -> Result<HashMap<String, String>, DataError>;
+
+    /// Store a formula variable, replacing any variable of the same
+    /// name.
+    async fn set_user_formula(
+        &self,
+        user: &str,
+        room_id: &str,
+        variable_name: &str,
+        formula: &str,
+    ) -> Result<(), DataError>;
+
+    /// String variables, by name.
+    async fn get_user_strings(
+        &self,
+        user: &str,
+        room_id: &str,
+    ) -> Result<HashMap<String, String>, DataError>;
+
+    /// Store a string variable, replacing any variable of the same
+    /// name.
+    async fn set_user_string(
+        &self,
+        user: &str,
+        room_id: &str,
+        variable_name: &str,
+        value: &str,
+    ) -> Result<(), DataError>;
+
+    /// Store all of the given variables, replacing variables of the
+    /// same names, and delete the named variables, in a single
+    /// transaction.
+    async fn import_user_variables(
+        &self,
+        user: &str,
+        room_id: &str,
+        variables: &VariableSet,
+        delete: &[String],
+    ) -> Result<(), DataError>;
+
+    /// Copy all of the user's variables and characters from one room
+    /// to another, in a single transaction. Variables of the same
+    /// name in the destination room are replaced. Returns the number
+    /// of variables copied.
+    async fn copy_user_variables(
+        &self,
+        user: &str,
+        from_room: &str,
+        to_room: &str,
+    ) -> Result<u64, DataError>;
+
+    /// Atomically add an amount (negative to subtract) to a variable,
+    /// respecting its bounds. Returns the old and new values.
+    async fn adjust_user_variable(
+        &self,
+        user: &str,
+        room_id: &str,
+        variable_name: &str,
+        amount: i32,
+    ) -> Result<(i32, i32), DataError>;
+
+    /// Atomically subtract an amount from a variable, like
+    /// adjust_user_variable, but refuse to go below zero even if the
+    /// variable has no minimum. Returns the old and new values.
+    async fn spend_user_variable(
+        &self,
+        user: &str,
+        room_id: &str,
+        variable_name: &str,
+        amount: i32,
+    ) -> Result<(i32, i32), DataError>;
+
+    async fn get_variable_bounds(
+        &self,
+        user: &str,
+        room_id: &str,
+        variable_name: &str,
+    ) -> Result<VariableBounds, DataError>;
+
+    async fn set_variable_bounds(
+        &self,
+        user: &str,
+        room_id: &str,
+        variable_name: &str,
+        bounds: &VariableBounds,
+    ) -> Result<(), DataError>;
+
     async fn delete_user_variable(
         &self,
         user: &str,
@@ -68,3 +285,82 @@ pub trait Variables {
         variable_name: &str,
     ) -> Result<(), DataError>;
 }
+
+/// History of changes to user and room variables.
+#[async_trait]
+pub trait VariableHistory {
+    /// The most recent changes to a variable of the user's active
+    /// character (or the user), newest first. Without a user, the
+    /// history of the room variable is returned.
+    async fn get_variable_history(
+        &self,
+        user: Option<&str>,
+        room_id: &str,
+        variable_name: &str,
+        limit: i64,
+    ) -> Result<Vec<VariableChange>, DataError>;
+
+    /// Undo the user's newest change to their own variables (of any
+    /// character) in the room that has not already been undone.
+    /// Changes to characters that have since been deleted are
+    /// skipped. Returns the change that was undone, if there was one.
+    async fn undo_last_change(
+        &self,
+        user: &str,
+        room_id: &str,
+    ) -> Result<Option<VariableChange>, DataError>;
+}
+
+/// Variables shared by everyone in a room. Like user variables, names
+/// are case-insensitive, and returned in lower case.
+#[async_trait]
+pub trait RoomVariables {
+    async fn get_room_variables(&self, room_id: &str) -> Result<HashMap<String, i32>, DataError>;
+
+    async fn set_room_variable(
+        &self,
+        room_id: &str,
+        variable_name: &str,
+        value: i32,
+    ) -> Result<(), DataError>;
+
+    async fn get_room_formulas(&self, room_id: &str) -> Result<HashMap<String, String>, DataError>;
+
+    async fn set_room_formula(
+        &self,
+        room_id: &str,
+        variable_name: &str,
+        formula: &str,
+    ) -> Result<(), DataError>;
+
+    async fn get_room_strings(&self, room_id: &str) -> Result<HashMap<String, String>, DataError>;
+
+    async fn set_room_string(
+        &self,
+        room_id: &str,
+        variable_name: &str,
+        value: &str,
+    ) -> Result<(), DataError>;
+
+    /// Atomically add an amount (negative to subtract) to a room
+    /// variable. Returns the old and new values.
+    async fn adjust_room_variable(
+        &self,
+        room_id: &str,
+        variable_name: &str,
+        amount: i32,
+    ) -> Result<(i32, i32), DataError>;
+
+    async fn delete_room_variable(
+        &self,
+        room_id: &str,
+        variable_name: &str,
+    ) -> Result<(), DataError>;
+
+    /// Copy everything variable-related from one room to another in a
+    /// single transaction, e.g. when a room is upgraded: the variables
+    /// and characters of all users, room variables, the game system,
+    /// room settings and command permissions. Returns the number of
+    /// user variables copied.
+    async fn copy_all_variables(&self, from_room: &str, to_room: &str) -> Result<u64, DataError>;
+}
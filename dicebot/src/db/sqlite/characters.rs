@@ -0,0 +1,327 @@
+use super::history::{record_change, stored_value, VariableKey};
+use super::Database;
+use crate::db::{errors::DataError, Characters};
+use async_trait::async_trait;
+
+async fn get_character_id(
+    db: &Database,
+    user: &str,
+    room_id: &str,
+    name: &str,
+) -> Result<Option<i64>, DataError> {
+    let row = sqlx::query!(
+        r#"SELECT id FROM characters
+           WHERE user_id = ? AND room_id = ? AND name = ?"#,
+        user,
+        room_id,
+        name
+    )
+    .fetch_optional(&db.conn)
+    .await?;
+
+    Ok(row.and_then(|row| row.id))
+}
+
+#[async_trait]
+impl Characters for Database {
+    async fn create_character(
+        &self,
+        user: &str,
+        room_id: &str,
+        name: &str,
+    ) -> Result<(), DataError> {
+        if get_character_id(self, user, room_id, name).await?.is_some() {
+            return Err(DataError::CharacterAlreadyExists(name.to_string()));
+        }
+
+        sqlx::query("INSERT INTO characters (user_id, room_id, name) VALUES (?, ?, ?)")
+            .bind(user)
+            .bind(room_id)
+            .bind(name)
+            .execute(&self.conn)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn get_characters(&self, user: &str, room_id: &str) -> Result<Vec<String>, DataError> {
+        let rows = sqlx::query!(
+            r#"SELECT name FROM characters
+               WHERE user_id = ? AND room_id = ?
+               ORDER BY name"#,
+            user,
+            room_id
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| row.name).collect())
+    }
+
+    async fn get_active_character(
+        &self,
+        user: &str,
+        room_id: &str,
+    ) -> Result<Option<String>, DataError> {
+        let row = sqlx::query!(
+            r#"SELECT characters.name FROM active_characters
+               INNER JOIN characters ON characters.id = active_characters.character_id
+               WHERE active_characters.user_id = ? AND active_characters.room_id = ?"#,
+            user,
+            room_id
+        )
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(row.map(|row| row.name))
+    }
+
+    async fn set_active_character(
+        &self,
+        user: &str,
+        room_id: &str,
+        name: Option<&str>,
+    ) -> Result<(), DataError> {
+        let name = match name {
+            Some(name) => name,
+            None => {
+                sqlx::query("DELETE FROM active_characters WHERE user_id = ? AND room_id = ?")
+                    .bind(user)
+                    .bind(room_id)
+                    .execute(&self.conn)
+                    .await?;
+
+                return Ok(());
+            }
+        };
+
+        let character_id = get_character_id(self, user, room_id, name)
+            .await?
+            .ok_or_else(|| DataError::CharacterDoesNotExist(name.to_string()))?;
+
+        sqlx::query(
+            "INSERT INTO active_characters (user_id, room_id, character_id)
+             VALUES (?, ?, ?)
+             ON CONFLICT(user_id, room_id) DO UPDATE SET character_id = excluded.character_id",
+        )
+        .bind(user)
+        .bind(room_id)
+        .bind(character_id)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_character(
+        &self,
+        user: &str,
+        room_id: &str,
+        name: &str,
+    ) -> Result<(), DataError> {
+        let character_id = get_character_id(self, user, room_id, name)
+            .await?
+            .ok_or_else(|| DataError::CharacterDoesNotExist(name.to_string()))?;
+
+        let mut tx = self.conn.begin().await?;
+
+        //Record the deletion of each of the character's variables.
+        let variables = sqlx::query!(
+            r#"SELECT key, value as "value: i32", formula, string_value FROM user_variables
+               WHERE character_id = ?"#,
+            character_id
+        )
+        .fetch_all(&mut tx)
+        .await?;
+
+        for variable in variables {
+            let key = VariableKey {
+                user: Some(user),
+                room_id,
+                character_id,
+                name: &variable.key,
+            };
+
+            let old = stored_value(variable.value, variable.formula, variable.string_value);
+            record_change(&mut tx, &self.source, key, Some(&old), None, None).await?;
+        }
+
+        sqlx::query("DELETE FROM user_variables WHERE character_id = ?")
+            .bind(character_id)
+            .execute(&mut tx)
+            .await?;
+
+        sqlx::query("DELETE FROM active_characters WHERE character_id = ?")
+            .bind(character_id)
+            .execute(&mut tx)
+            .await?;
+
+        sqlx::query("DELETE FROM characters WHERE id = ?")
+            .bind(character_id)
+            .execute(&mut tx)
+            .await?;
+
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Variables;
+    use std::future::Future;
+
+    async fn with_db<Fut>(f: impl FnOnce(Database) -> Fut)
+    where
+        Fut: Future<Output = ()>,
+    {
+        let db_path = tempfile::NamedTempFile::new_in(".").unwrap();
+        crate::db::sqlite::migrator::migrate(db_path.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        let db = Database::new(db_path.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        f(db).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn create_and_list_characters() {
+        with_db(|db| async move {
+            db.create_character("myuser", "myroom", "Zed")
+                .await
+                .expect("could not create character");
+
+            db.create_character("myuser", "myroom", "Anna")
+                .await
+                .expect("could not create character");
+
+            db.create_character("otheruser", "myroom", "Bob")
+                .await
+                .expect("could not create character");
+
+            let characters = db
+                .get_characters("myuser", "myroom")
+                .await
+                .expect("could not get characters");
+
+            assert_eq!(vec!["Anna".to_string(), "Zed".to_string()], characters);
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn cannot_create_duplicate_character() {
+        with_db(|db| async move {
+            db.create_character("myuser", "myroom", "Anna")
+                .await
+                .expect("could not create character");
+
+            let result = db.create_character("myuser", "myroom", "Anna").await;
+            assert!(matches!(result, Err(DataError::CharacterAlreadyExists(_))));
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn switch_active_character() {
+        with_db(|db| async move {
+            db.create_character("myuser", "myroom", "Anna")
+                .await
+                .expect("could not create character");
+
+            let active = db.get_active_character("myuser", "myroom").await.unwrap();
+            assert_eq!(None, active);
+
+            db.set_active_character("myuser", "myroom", Some("Anna"))
+                .await
+                .expect("could not switch character");
+
+            let active = db.get_active_character("myuser", "myroom").await.unwrap();
+            assert_eq!(Some("Anna".to_string()), active);
+
+            db.set_active_character("myuser", "myroom", None)
+                .await
+                .expect("could not clear character");
+
+            let active = db.get_active_character("myuser", "myroom").await.unwrap();
+            assert_eq!(None, active);
+
+            let result = db
+                .set_active_character("myuser", "myroom", Some("Nobody"))
+                .await;
+
+            assert!(matches!(result, Err(DataError::CharacterDoesNotExist(_))));
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn variables_are_scoped_to_active_character() {
+        with_db(|db| async move {
+            db.set_user_variable("myuser", "myroom", "strength", 1)
+                .await
+                .expect("could not set variable");
+
+            db.create_character("myuser", "myroom", "Anna")
+                .await
+                .expect("could not create character");
+
+            db.set_active_character("myuser", "myroom", Some("Anna"))
+                .await
+                .expect("could not switch character");
+
+            let result = db.get_user_variable("myuser", "myroom", "strength").await;
+            assert!(matches!(result, Err(DataError::KeyDoesNotExist(_))));
+
+            db.set_user_variable("myuser", "myroom", "strength", 4)
+                .await
+                .expect("could not set variable");
+
+            let value = db.get_user_variable("myuser", "myroom", "strength").await;
+            assert_eq!(4, value.unwrap());
+
+            db.set_active_character("myuser", "myroom", None)
+                .await
+                .expect("could not clear character");
+
+            let value = db.get_user_variable("myuser", "myroom", "strength").await;
+            assert_eq!(1, value.unwrap());
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn delete_character_removes_variables() {
+        with_db(|db| async move {
+            db.create_character("myuser", "myroom", "Anna")
+                .await
+                .expect("could not create character");
+
+            db.set_active_character("myuser", "myroom", Some("Anna"))
+                .await
+                .expect("could not switch character");
+
+            db.set_user_variable("myuser", "myroom", "strength", 4)
+                .await
+                .expect("could not set variable");
+
+            db.delete_character("myuser", "myroom", "Anna")
+                .await
+                .expect("could not delete character");
+
+            let active = db.get_active_character("myuser", "myroom").await.unwrap();
+            assert_eq!(None, active);
+
+            let count = db.get_variable_count("myuser", "myroom").await.unwrap();
+            assert_eq!(0, count);
+
+            let result = db.delete_character("myuser", "myroom", "Anna").await;
+            assert!(matches!(result, Err(DataError::CharacterDoesNotExist(_))));
+        })
+        .await;
+    }
+}
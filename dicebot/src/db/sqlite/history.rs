@@ -0,0 +1,565 @@
+use super::room_variables::store_room_variable;
+use super::variables::store_user_variable;
+use super::Database;
+use crate::db::{errors::DataError, VariableHistory};
+use crate::models::{ChangeSource, StoredValue, VariableChange};
+use async_trait::async_trait;
+use sqlx::{Sqlite, Transaction};
+use std::convert::TryFrom;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A variable whose changes are recorded: a user variable of one of
+/// the user's characters (0 for the user's own variables), or a room
+/// variable if there is no user.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct VariableKey<'a> {
+    pub user: Option<&'a str>,
+    pub room_id: &'a str,
+    pub character_id: i64,
+    pub name: &'a str,
+}
+
+/// Type and text of a value, as stored in the history table.
+fn encode(value: Option<&StoredValue>) -> (Option<&'static str>, Option<String>) {
+    match value {
+        Some(StoredValue::Number(number)) => (Some("number"), Some(number.to_string())),
+        Some(StoredValue::Formula(formula)) => (Some("formula"), Some(formula.clone())),
+        Some(StoredValue::Text(text)) => (Some("string"), Some(text.clone())),
+        None => (None, None),
+    }
+}
+
+fn decode(value_type: Option<String>, value: Option<String>) -> Option<StoredValue> {
+    match (value_type.as_deref(), value) {
+        (Some("number"), Some(value)) => value.parse().ok().map(StoredValue::Number),
+        (Some("formula"), Some(value)) => Some(StoredValue::Formula(value)),
+        (Some("string"), Some(value)) => Some(StoredValue::Text(value)),
+        _ => None,
+    }
+}
+
+/// Interpret a row of the user or room variables tables.
+pub(super) fn stored_value(
+    value: i32,
+    formula: Option<String>,
+    text: Option<String>,
+) -> StoredValue {
+    match (formula, text) {
+        (Some(formula), _) => StoredValue::Formula(formula),
+        (_, Some(text)) => StoredValue::Text(text),
+        _ => StoredValue::Number(value),
+    }
+}
+
+/// The current value of a variable, if it is set.
+pub(super) async fn current_value(
+    tx: &mut Transaction<'_, Sqlite>,
+    key: VariableKey<'_>,
+) -> Result<Option<StoredValue>, DataError> {
+    let name = key.name.to_lowercase();
+    let value = match key.user {
+        Some(user) => sqlx::query!(
+            r#"SELECT value as "value: i32", formula, string_value FROM user_variables
+               WHERE user_id = ? AND room_id = ? AND character_id = ? AND key = ?"#,
+            user,
+            key.room_id,
+            key.character_id,
+            name
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .map(|row| stored_value(row.value, row.formula, row.string_value)),
+        None => sqlx::query!(
+            r#"SELECT value as "value: i32", formula, string_value FROM room_variables
+               WHERE room_id = ? AND key = ?"#,
+            key.room_id,
+            name
+        )
+        .fetch_optional(&mut *tx)
+        .await?
+        .map(|row| stored_value(row.value, row.formula, row.string_value)),
+    };
+
+    Ok(value)
+}
+
+/// Add a change to the variable history. Undo records refer to the
+/// change that they undid.
+pub(super) async fn record_change(
+    tx: &mut Transaction<'_, Sqlite>,
+    source: &ChangeSource,
+    key: VariableKey<'_>,
+    old_value: Option<&StoredValue>,
+    new_value: Option<&StoredValue>,
+    undo_of: Option<i64>,
+) -> Result<(), DataError> {
+    let now: i64 = i64::try_from(
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("Clock has gone backwards")
+            .as_secs(),
+    )?;
+
+    let changed_by = source
+        .changed_by
+        .as_deref()
+        .or(key.user)
+        .unwrap_or("unknown");
+
+    let (old_type, old_value) = encode(old_value);
+    let (new_type, new_value) = encode(new_value);
+
+    sqlx::query(
+        "INSERT INTO variable_history
+                (room_id, user_id, character_id, key, old_type, old_value, new_type, new_value,
+                 changed_by, via, changed_at, undo_of)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(key.room_id)
+    .bind(key.user)
+    .bind(key.character_id)
+    .bind(key.name.to_lowercase())
+    .bind(old_type)
+    .bind(old_value)
+    .bind(new_type)
+    .bind(new_value)
+    .bind(changed_by)
+    .bind(&source.via)
+    .bind(now)
+    .bind(undo_of)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(())
+}
+
+/// A variable that is copied over a variable in another room, along
+/// with the value it replaces, if there is one. Room variables have
+/// no user.
+#[derive(sqlx::FromRow)]
+pub(super) struct CopiedVariable {
+    pub user_id: Option<String>,
+    pub character_id: i64,
+    pub key: String,
+    pub value: i32,
+    pub formula: Option<String>,
+    pub string_value: Option<String>,
+    pub old_key: Option<String>,
+    pub old_value: Option<i32>,
+    pub old_formula: Option<String>,
+    pub old_string_value: Option<String>,
+}
+
+/// Record the changes made by copying variables into a room, for
+/// every copied variable whose value changed.
+pub(super) async fn record_copies(
+    tx: &mut Transaction<'_, Sqlite>,
+    source: &ChangeSource,
+    to_room: &str,
+    copies: Vec<CopiedVariable>,
+) -> Result<(), DataError> {
+    for copy in copies {
+        let old = copy.old_key.as_ref().map(|_| {
+            let value = copy.old_value.unwrap_or(0);
+            stored_value(
+                value,
+                copy.old_formula.clone(),
+                copy.old_string_value.clone(),
+            )
+        });
+
+        let new = stored_value(copy.value, copy.formula.clone(), copy.string_value.clone());
+        if old.as_ref() != Some(&new) {
+            let key = VariableKey {
+                user: copy.user_id.as_deref(),
+                room_id: to_room,
+                character_id: copy.character_id,
+                name: &copy.key,
+            };
+
+            record_change(tx, source, key, old.as_ref(), Some(&new), None).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Store a new value for a variable (or delete it, if there is no
+/// value), and record the change in the history if the value
+/// changed. Returns the old value.
+pub(super) async fn write_variable(
+    tx: &mut Transaction<'_, Sqlite>,
+    source: &ChangeSource,
+    key: VariableKey<'_>,
+    value: Option<&StoredValue>,
+) -> Result<Option<StoredValue>, DataError> {
+    let old_value = current_value(tx, key).await?;
+
+    match key.user {
+        Some(user) => {
+            store_user_variable(tx, user, key.room_id, key.character_id, key.name, value).await?
+        }
+        None => store_room_variable(tx, key.room_id, key.name, value).await?,
+    }
+
+    if old_value.as_ref() != value {
+        record_change(tx, source, key, old_value.as_ref(), value, None).await?;
+    }
+
+    Ok(old_value)
+}
+
+#[async_trait]
+impl VariableHistory for Database {
+    async fn get_variable_history(
+        &self,
+        user: Option<&str>,
+        room_id: &str,
+        variable_name: &str,
+        limit: i64,
+    ) -> Result<Vec<VariableChange>, DataError> {
+        let variable_name = &variable_name.to_lowercase();
+        let rows = sqlx::query!(
+            r#"SELECT key, old_type, old_value, new_type, new_value, changed_by, via,
+                      changed_at as "changed_at: i64"
+               FROM variable_history
+               WHERE room_id = ? AND key = ? AND user_id IS ?
+               AND character_id = ifnull(
+                 (SELECT character_id FROM active_characters
+                  WHERE user_id = ? AND room_id = ?), 0)
+               ORDER BY id DESC LIMIT ?"#,
+            room_id,
+            variable_name,
+            user,
+            user,
+            room_id,
+            limit
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| VariableChange {
+                key: row.key,
+                old_value: decode(row.old_type, row.old_value),
+                new_value: decode(row.new_type, row.new_value),
+                changed_by: row.changed_by,
+                via: row.via,
+                changed_at: row.changed_at,
+            })
+            .collect())
+    }
+
+    async fn undo_last_change(
+        &self,
+        user: &str,
+        room_id: &str,
+    ) -> Result<Option<VariableChange>, DataError> {
+        let mut tx = self.conn.begin().await?;
+
+        //The newest change that is not an undo, and was not undone.
+        let row = sqlx::query!(
+            r#"SELECT id as "id!: i64", character_id as "character_id: i64", key,
+                      old_type, old_value, new_type, new_value, changed_by, via,
+                      changed_at as "changed_at: i64"
+               FROM variable_history h
+               WHERE room_id = ? AND user_id = ? AND undo_of IS NULL
+               AND NOT EXISTS (SELECT 1 FROM variable_history u WHERE u.undo_of = h.id)
+               AND (character_id = 0
+                    OR EXISTS (SELECT 1 FROM characters c WHERE c.id = h.character_id))
+               ORDER BY id DESC LIMIT 1"#,
+            room_id,
+            user
+        )
+        .fetch_optional(&mut tx)
+        .await?;
+
+        let row = match row {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        let change = VariableChange {
+            key: row.key,
+            old_value: decode(row.old_type, row.old_value),
+            new_value: decode(row.new_type, row.new_value),
+            changed_by: row.changed_by,
+            via: row.via,
+            changed_at: row.changed_at,
+        };
+
+        let key = VariableKey {
+            user: Some(user),
+            room_id,
+            character_id: row.character_id,
+            name: &change.key,
+        };
+
+        let current = current_value(&mut tx, key).await?;
+        let restored = change.old_value.as_ref();
+        store_user_variable(&mut tx, user, room_id, key.character_id, key.name, restored).await?;
+        record_change(
+            &mut tx,
+            &self.source,
+            key,
+            current.as_ref(),
+            restored,
+            Some(row.id),
+        )
+        .await?;
+
+        tx.commit().await?;
+        Ok(Some(change))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::Database;
+    use crate::db::{Characters, RoomVariables, Variables};
+    use std::future::Future;
+
+    async fn with_db<Fut>(f: impl FnOnce(Database) -> Fut)
+    where
+        Fut: Future<Output = ()>,
+    {
+        let db_path = tempfile::NamedTempFile::new_in(".").unwrap();
+        crate::db::sqlite::migrator::migrate(db_path.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        let db = Database::new(db_path.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        f(db).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn changes_are_recorded_newest_first() {
+        with_db(|db| async move {
+            let db = db.with_change_source("myuser", "!set");
+            db.set_user_variable("myuser", "myroom", "hp", 5)
+                .await
+                .expect("Could not set variable");
+
+            db.adjust_user_variable("myuser", "myroom", "hp", -2)
+                .await
+                .expect("Could not adjust variable");
+
+            db.delete_user_variable("myuser", "myroom", "hp")
+                .await
+                .expect("Could not delete variable");
+
+            let history = db
+                .get_variable_history(Some("myuser"), "myroom", "HP", 10)
+                .await
+                .expect("Could not get history");
+
+            let values: Vec<_> = history
+                .iter()
+                .map(|change| (change.old_value.clone(), change.new_value.clone()))
+                .collect();
+
+            assert_eq!(
+                vec![
+                    (Some(StoredValue::Number(3)), None),
+                    (Some(StoredValue::Number(5)), Some(StoredValue::Number(3))),
+                    (None, Some(StoredValue::Number(5))),
+                ],
+                values
+            );
+
+            assert_eq!("myuser", history[0].changed_by);
+            assert_eq!("!set", history[0].via);
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn room_variable_history_is_separate() {
+        with_db(|db| async move {
+            let db = db.with_change_source("gm", "!set");
+            db.set_room_variable("myroom", "difficulty", 2)
+                .await
+                .expect("Could not set room variable");
+
+            db.set_user_variable("myuser", "myroom", "difficulty", 1)
+                .await
+                .expect("Could not set variable");
+
+            let history = db
+                .get_variable_history(None, "myroom", "difficulty", 10)
+                .await
+                .expect("Could not get history");
+
+            assert_eq!(1, history.len());
+            assert_eq!("gm", history[0].changed_by);
+            assert_eq!(Some(StoredValue::Number(2)), history[0].new_value);
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn undo_restores_previous_values_in_order() {
+        with_db(|db| async move {
+            db.set_user_variable("myuser", "myroom", "strength", 3)
+                .await
+                .expect("Could not set variable");
+
+            db.set_user_formula("myuser", "myroom", "strength", "dex + 1")
+                .await
+                .expect("Could not set formula");
+
+            let undone = db.undo_last_change("myuser", "myroom").await.unwrap();
+            let value = db.get_user_variable("myuser", "myroom", "strength").await;
+
+            assert_eq!(
+                Some(StoredValue::Formula("dex + 1".to_string())),
+                undone.and_then(|change| change.new_value)
+            );
+            assert!(matches!(value, Ok(3)));
+
+            db.undo_last_change("myuser", "myroom").await.unwrap();
+            let value = db.get_user_variable("myuser", "myroom", "strength").await;
+            assert!(matches!(value, Err(DataError::KeyDoesNotExist(_))));
+
+            let undone = db.undo_last_change("myuser", "myroom").await.unwrap();
+            assert_eq!(None, undone);
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn undo_applies_to_the_changed_character() {
+        with_db(|db| async move {
+            db.create_character("myuser", "myroom", "Anna")
+                .await
+                .expect("Could not create character");
+
+            db.set_active_character("myuser", "myroom", Some("Anna"))
+                .await
+                .expect("Could not switch character");
+
+            db.set_user_variable("myuser", "myroom", "hp", 7)
+                .await
+                .expect("Could not set variable");
+
+            db.set_active_character("myuser", "myroom", None)
+                .await
+                .expect("Could not switch character");
+
+            db.set_user_variable("myuser", "myroom", "hp", 4)
+                .await
+                .expect("Could not set variable");
+
+            db.undo_last_change("myuser", "myroom").await.unwrap();
+            db.undo_last_change("myuser", "myroom").await.unwrap();
+
+            let own = db.get_user_variable("myuser", "myroom", "hp").await;
+            assert!(matches!(own, Err(DataError::KeyDoesNotExist(_))));
+
+            db.set_active_character("myuser", "myroom", Some("Anna"))
+                .await
+                .expect("Could not switch character");
+
+            let anna = db.get_user_variable("myuser", "myroom", "hp").await;
+            assert!(matches!(anna, Err(DataError::KeyDoesNotExist(_))));
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn copies_are_recorded() {
+        with_db(|db| async move {
+            db.set_user_variable("myuser", "fromroom", "hp", 7)
+                .await
+                .expect("Could not set variable");
+
+            db.set_user_variable("myuser", "toroom", "hp", 4)
+                .await
+                .expect("Could not set variable");
+
+            db.set_room_variable("fromroom", "difficulty", 2)
+                .await
+                .expect("Could not set room variable");
+
+            let db = db.with_change_source("gm", "!copy");
+            db.copy_all_variables("fromroom", "toroom")
+                .await
+                .expect("Could not copy variables");
+
+            let history = db
+                .get_variable_history(Some("myuser"), "toroom", "hp", 10)
+                .await
+                .expect("Could not get history");
+
+            assert_eq!(2, history.len());
+            assert_eq!(Some(StoredValue::Number(4)), history[0].old_value);
+            assert_eq!(Some(StoredValue::Number(7)), history[0].new_value);
+            assert_eq!("!copy", history[0].via);
+
+            let history = db
+                .get_variable_history(None, "toroom", "difficulty", 10)
+                .await
+                .expect("Could not get history");
+
+            assert_eq!(1, history.len());
+            assert_eq!(None, history[0].old_value);
+
+            //Copying again changes nothing, so nothing is recorded.
+            db.copy_all_variables("fromroom", "toroom")
+                .await
+                .expect("Could not copy variables");
+
+            let history = db
+                .get_variable_history(Some("myuser"), "toroom", "hp", 10)
+                .await
+                .expect("Could not get history");
+
+            assert_eq!(2, history.len());
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn undo_skips_deleted_characters() {
+        with_db(|db| async move {
+            db.set_user_variable("myuser", "myroom", "hp", 4)
+                .await
+                .expect("Could not set variable");
+
+            db.create_character("myuser", "myroom", "Anna")
+                .await
+                .expect("Could not create character");
+
+            db.set_active_character("myuser", "myroom", Some("Anna"))
+                .await
+                .expect("Could not switch character");
+
+            db.set_user_variable("myuser", "myroom", "hp", 7)
+                .await
+                .expect("Could not set variable");
+
+            db.delete_character("myuser", "myroom", "Anna")
+                .await
+                .expect("Could not delete character");
+
+            //The deletion of Anna's hp was recorded, but is skipped.
+            let undone = db.undo_last_change("myuser", "myroom").await.unwrap();
+            assert_eq!(
+                Some(StoredValue::Number(4)),
+                undone.and_then(|change| change.new_value)
+            );
+
+            let own = db.get_user_variable("myuser", "myroom", "hp").await;
+            assert!(matches!(own, Err(DataError::KeyDoesNotExist(_))));
+
+            let undone = db.undo_last_change("myuser", "myroom").await.unwrap();
+            assert_eq!(None, undone);
+        })
+        .await;
+    }
+}
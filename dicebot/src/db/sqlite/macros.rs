@@ -0,0 +1,190 @@
+use super::Database;
+use crate::db::{errors::DataError, Macros};
+use crate::models::{Macro, MacroScope};
+use async_trait::async_trait;
+
+fn to_macro(name: String, scope: MacroScope, commands: String) -> Macro {
+    Macro {
+        name,
+        scope,
+        commands: commands.lines().map(str::to_owned).collect(),
+    }
+}
+
+#[async_trait]
+impl Macros for Database {
+    async fn get_macro(
+        &self,
+        user: &str,
+        room_id: &str,
+        name: &str,
+    ) -> Result<Option<Macro>, DataError> {
+        let name = &name.to_lowercase();
+        let row = sqlx::query!(
+            r#"SELECT name, scope as "scope: MacroScope", commands FROM macros
+               WHERE name = ? AND ((scope = 'user' AND owner_id = ?)
+                                OR (scope = 'room' AND owner_id = ?))
+               ORDER BY scope DESC LIMIT 1"#,
+            name,
+            user,
+            room_id
+        )
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(row.map(|row| to_macro(row.name, row.scope, row.commands)))
+    }
+
+    async fn get_macros(&self, user: &str, room_id: &str) -> Result<Vec<Macro>, DataError> {
+        let rows = sqlx::query!(
+            r#"SELECT name, scope as "scope: MacroScope", commands FROM macros
+               WHERE (scope = 'user' AND owner_id = ?) OR (scope = 'room' AND owner_id = ?)
+               ORDER BY scope DESC, name"#,
+            user,
+            room_id
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| to_macro(row.name, row.scope, row.commands))
+            .collect())
+    }
+
+    async fn set_macro(
+        &self,
+        scope: MacroScope,
+        owner_id: &str,
+        name: &str,
+        commands: &[String],
+    ) -> Result<(), DataError> {
+        sqlx::query(
+            "INSERT INTO macros (scope, owner_id, name, commands) VALUES (?, ?, ?, ?)
+             ON CONFLICT(scope, owner_id, name) DO UPDATE SET commands = excluded.commands",
+        )
+        .bind(scope)
+        .bind(owner_id)
+        .bind(name.to_lowercase())
+        .bind(commands.join("\n"))
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn delete_macro(
+        &self,
+        scope: MacroScope,
+        owner_id: &str,
+        name: &str,
+    ) -> Result<(), DataError> {
+        let name = name.to_lowercase();
+        let result =
+            sqlx::query("DELETE FROM macros WHERE scope = ? AND owner_id = ? AND name = ?")
+                .bind(scope)
+                .bind(owner_id)
+                .bind(&name)
+                .execute(&self.conn)
+                .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(DataError::KeyDoesNotExist(name));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::Database;
+    use std::future::Future;
+
+    async fn with_db<Fut>(f: impl FnOnce(Database) -> Fut)
+    where
+        Fut: Future<Output = ()>,
+    {
+        let db_path = tempfile::NamedTempFile::new_in(".").unwrap();
+        crate::db::sqlite::migrator::migrate(db_path.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        let db = Database::new(db_path.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        f(db).await;
+    }
+
+    fn commands(lines: &[&str]) -> Vec<String> {
+        lines.iter().map(|line| line.to_string()).collect()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn user_macro_comes_before_room_macro() {
+        with_db(|db| async move {
+            db.set_macro(
+                MacroScope::Room,
+                "myroom",
+                "Attack",
+                &commands(&["!roll 1d20"]),
+            )
+            .await
+            .expect("Could not set room macro");
+
+            let attack = db.get_macro("myuser", "myroom", "attack").await.unwrap();
+            assert_eq!(Some(MacroScope::Room), attack.map(|m| m.scope));
+
+            let lines = commands(&["!roll 1d20+{atk}", "!roll 1d8+{str}"]);
+            db.set_macro(MacroScope::User, "myuser", "attack", &lines)
+                .await
+                .expect("Could not set user macro");
+
+            let attack = db.get_macro("myuser", "myroom", "ATTACK").await.unwrap();
+            assert_eq!(
+                Some(Macro {
+                    name: "attack".to_string(),
+                    scope: MacroScope::User,
+                    commands: lines,
+                }),
+                attack
+            );
+
+            let other_room = db.get_macro("otheruser", "otherroom", "attack").await;
+            assert!(matches!(other_room, Ok(None)));
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn list_and_delete_macros() {
+        with_db(|db| async move {
+            db.set_macro(MacroScope::User, "myuser", "heal", &commands(&["!inc hp"]))
+                .await
+                .expect("Could not set macro");
+
+            db.set_macro(
+                MacroScope::Room,
+                "myroom",
+                "init",
+                &commands(&["!roll 1d10"]),
+            )
+            .await
+            .expect("Could not set macro");
+
+            let macros = db.get_macros("myuser", "myroom").await.unwrap();
+            let names: Vec<_> = macros.iter().map(|m| m.name.as_str()).collect();
+            assert_eq!(vec!["heal", "init"], names);
+
+            db.delete_macro(MacroScope::User, "myuser", "heal")
+                .await
+                .expect("Could not delete macro");
+
+            let result = db.delete_macro(MacroScope::User, "myuser", "heal").await;
+            assert!(matches!(result, Err(DataError::KeyDoesNotExist(_))));
+        })
+        .await;
+    }
+}
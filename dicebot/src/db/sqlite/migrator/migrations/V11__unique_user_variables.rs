@@ -0,0 +1,14 @@
+pub fn migration() -> String {
+    // Setting a variable used to insert a new row every time, so
+    // there can be several rows for the same key. Keep only the most
+    // recently inserted one before adding the unique index.
+    r#"
+      DELETE FROM user_variables WHERE rowid NOT IN (
+         SELECT max(rowid) FROM user_variables
+         GROUP BY user_id, room_id, key
+      );
+      CREATE UNIQUE INDEX user_variables_unique_key
+         ON user_variables (user_id, room_id, key);
+    "#
+    .to_string()
+}
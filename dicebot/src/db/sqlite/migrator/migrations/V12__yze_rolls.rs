@@ -0,0 +1,28 @@
+use barrel::backend::Sqlite;
+use barrel::{types, Migration};
+
+pub fn migration() -> String {
+    let mut m = Migration::new();
+
+    //Last Year Zero Engine roll of a user in a room, kept so that it
+    //can be pushed. Dice are stored as comma-separated lists.
+    m.create_table("yze_rolls", move |t| {
+        t.add_column("user_id", types::text());
+        t.add_column("room_id", types::text());
+        t.add_column("base_dice", types::text());
+        t.add_column("skill_dice", types::text());
+        t.add_column("gear_dice", types::text());
+        t.add_column("stress_dice", types::text().nullable(true));
+        t.add_column("pushed", types::boolean());
+    });
+
+    let mut res = m.make::<Sqlite>();
+
+    //This is a hack that gives us a composite primary key.
+    if res.ends_with(");") {
+        res.pop();
+        res.pop();
+    }
+
+    format!("{}, PRIMARY KEY (user_id, room_id));", res)
+}
@@ -0,0 +1,29 @@
+pub fn migration() -> String {
+    // Characters are owned by a user in a room. Each user can have one
+    // active character per room. Variables belong to a character, or
+    // to the user directly when character_id is 0 (no character), so
+    // existing variables are kept as the user's own.
+    r#"
+      CREATE TABLE characters (
+         id INTEGER PRIMARY KEY AUTOINCREMENT,
+         user_id TEXT NOT NULL,
+         room_id TEXT NOT NULL,
+         name TEXT NOT NULL,
+         UNIQUE (user_id, room_id, name)
+      );
+
+      CREATE TABLE active_characters (
+         user_id TEXT NOT NULL,
+         room_id TEXT NOT NULL,
+         character_id INTEGER NOT NULL,
+         PRIMARY KEY (user_id, room_id)
+      );
+
+      ALTER TABLE user_variables ADD COLUMN character_id INTEGER NOT NULL DEFAULT 0;
+
+      DROP INDEX user_variables_unique_key;
+      CREATE UNIQUE INDEX user_variables_unique_key
+         ON user_variables (user_id, room_id, character_id, key);
+    "#
+    .to_string()
+}
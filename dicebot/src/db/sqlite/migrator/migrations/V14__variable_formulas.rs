@@ -0,0 +1,8 @@
+pub fn migration() -> String {
+    // Formula variables store their expression instead of a number.
+    // Their value column is unused.
+    r#"
+      ALTER TABLE user_variables ADD COLUMN formula TEXT;
+    "#
+    .to_string()
+}
@@ -0,0 +1,12 @@
+pub fn migration() -> String {
+    // Optional bounds for incrementing and decrementing variables.
+    // Values going out of bounds are clamped, unless refuse_out_of_bounds
+    // is set, in which case the update is refused.
+    r#"
+      ALTER TABLE user_variables ADD COLUMN min_value INTEGER;
+      ALTER TABLE user_variables ADD COLUMN max_value INTEGER;
+      ALTER TABLE user_variables
+         ADD COLUMN refuse_out_of_bounds BOOLEAN NOT NULL DEFAULT 0;
+    "#
+    .to_string()
+}
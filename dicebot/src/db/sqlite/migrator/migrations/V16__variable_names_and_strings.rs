@@ -0,0 +1,16 @@
+pub fn migration() -> String {
+    // Variable names are now case-insensitive, and stored in lower
+    // case. If names only differing in case exist, keep the most
+    // recently inserted one. String variables store their value in
+    // string_value, and their value column is unused.
+    r#"
+      DELETE FROM user_variables WHERE rowid NOT IN (
+         SELECT max(rowid) FROM user_variables
+         GROUP BY user_id, room_id, character_id, lower(key)
+      );
+
+      UPDATE user_variables SET key = lower(key);
+      ALTER TABLE user_variables ADD COLUMN string_value TEXT;
+    "#
+    .to_string()
+}
@@ -0,0 +1,16 @@
+pub fn migration() -> String {
+    // Room variables are shared by everyone in the room, and can be
+    // referenced by any player's rolls. Like user variables, they
+    // hold a number, a formula or a string.
+    r#"
+      CREATE TABLE room_variables (
+         room_id TEXT NOT NULL,
+         key TEXT NOT NULL,
+         value INTEGER NOT NULL,
+         formula TEXT,
+         string_value TEXT,
+         PRIMARY KEY (room_id, key)
+      );
+    "#
+    .to_string()
+}
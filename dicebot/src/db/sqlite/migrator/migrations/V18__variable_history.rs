@@ -0,0 +1,29 @@
+pub fn migration() -> String {
+    // Every change to a user or room variable, so that changes can be
+    // reviewed and undone. Values are stored with their type (number,
+    // formula or string); a missing old value means the variable was
+    // created, and a missing new value means it was deleted. Room
+    // variables have no user ID. Undo records point to the change
+    // that they undid.
+    r#"
+      CREATE TABLE variable_history (
+         id INTEGER PRIMARY KEY AUTOINCREMENT,
+         room_id TEXT NOT NULL,
+         user_id TEXT,
+         character_id INTEGER NOT NULL DEFAULT 0,
+         key TEXT NOT NULL,
+         old_type TEXT,
+         old_value TEXT,
+         new_type TEXT,
+         new_value TEXT,
+         changed_by TEXT NOT NULL,
+         via TEXT NOT NULL,
+         changed_at INTEGER NOT NULL,
+         undo_of INTEGER REFERENCES variable_history(id)
+      );
+
+      CREATE INDEX variable_history_by_variable
+         ON variable_history (room_id, user_id, key);
+    "#
+    .to_string()
+}
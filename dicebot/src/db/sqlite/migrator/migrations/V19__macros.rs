@@ -0,0 +1,16 @@
+pub fn migration() -> String {
+    // Named lists of commands. User macros belong to a user in every
+    // room, and room macros are shared by everyone in a room. The
+    // owner is the user ID or room ID, depending on the scope.
+    // Commands are stored one per line.
+    r#"
+      CREATE TABLE macros (
+         scope TEXT NOT NULL,
+         owner_id TEXT NOT NULL,
+         name TEXT NOT NULL,
+         commands TEXT NOT NULL,
+         PRIMARY KEY (scope, owner_id, name)
+      );
+    "#
+    .to_string()
+}
@@ -0,0 +1,14 @@
+pub fn migration() -> String {
+    // Settings of the bot in a room. Rooms without a row use the
+    // defaults: inline rolls between [[ and ]] are turned off until a
+    // moderator turns them on.
+    r#"
+      CREATE TABLE room_settings (
+         room_id TEXT PRIMARY KEY NOT NULL,
+         inline_rolls INTEGER NOT NULL DEFAULT 0,
+         inline_roll_open TEXT NOT NULL DEFAULT '[[',
+         inline_roll_close TEXT NOT NULL DEFAULT ']]'
+      );
+    "#
+    .to_string()
+}
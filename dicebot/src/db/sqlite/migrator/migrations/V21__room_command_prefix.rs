@@ -0,0 +1,4 @@
+pub fn migration() -> String {
+    // Command prefix of a room. NULL uses the prefix from the config.
+    r#"ALTER TABLE room_settings ADD COLUMN command_prefix TEXT;"#.to_string()
+}
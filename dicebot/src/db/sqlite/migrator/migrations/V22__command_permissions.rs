@@ -0,0 +1,13 @@
+pub fn migration() -> String {
+    // Roles needed to run commands in a room, overriding the role
+    // that each command needs by default.
+    r#"
+      CREATE TABLE command_permissions (
+         room_id TEXT NOT NULL,
+         command TEXT NOT NULL,
+         role TEXT NOT NULL,
+         PRIMARY KEY (room_id, command)
+      );
+    "#
+    .to_string()
+}
@@ -1,25 +1,49 @@
 use crate::db::errors::DataError;
+use crate::models::ChangeSource;
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
 use sqlx::ConnectOptions;
 use std::clone::Clone;
 use std::str::FromStr;
 
+pub mod characters;
+pub mod history;
+pub mod macros;
 pub mod migrator;
+pub mod room_variables;
 pub mod rooms;
 pub mod state;
 pub mod users;
 pub mod variables;
+pub mod year_zero;
 
 pub struct Database {
     conn: SqlitePool,
+    source: ChangeSource,
 }
 
 impl Database {
     fn new_db(conn: SqlitePool) -> Result<Database, DataError> {
-        let database = Database { conn: conn.clone() };
+        let database = Database {
+            conn: conn.clone(),
+            source: ChangeSource::default(),
+        };
+
         Ok(database)
     }
 
+    /// A handle to the same database that records variable changes
+    /// as made by the given user, with the given command or RPC
+    /// method.
+    pub fn with_change_source(&self, changed_by: &str, via: &str) -> Database {
+        Database {
+            conn: self.conn.clone(),
+            source: ChangeSource {
+                changed_by: Some(changed_by.to_string()),
+                via: via.to_string(),
+            },
+        }
+    }
+
     pub async fn new(path: &str) -> Result<Database, DataError> {
         //Create database if missing.
         let conn = SqliteConnectOptions::from_str(path)?
@@ -46,6 +70,7 @@ impl Clone for Database {
     fn clone(&self) -> Self {
         Database {
             conn: self.conn.clone(),
+            source: self.source.clone(),
         }
     }
 }
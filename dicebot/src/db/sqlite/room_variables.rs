@@ -0,0 +1,401 @@
+use super::history::{record_change, record_copies, write_variable, CopiedVariable, VariableKey};
+use super::variables::copy_variables;
+use super::Database;
+use crate::db::{errors::DataError, RoomVariables};
+use crate::models::StoredValue;
+use async_trait::async_trait;
+use sqlx::{Sqlite, Transaction};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+
+fn room_variable_key<'a>(room_id: &'a str, variable_name: &'a str) -> VariableKey<'a> {
+    VariableKey {
+        user: None,
+        room_id,
+        character_id: 0,
+        name: variable_name,
+    }
+}
+
+/// Insert or replace a room variable, or delete it if there is no
+/// value. Only the column of the given kind of value is set; the
+/// others are cleared.
+pub(super) async fn store_room_variable(
+    tx: &mut Transaction<'_, Sqlite>,
+    room_id: &str,
+    variable_name: &str,
+    value: Option<&StoredValue>,
+) -> Result<(), DataError> {
+    let (number, formula, text) = match value {
+        Some(StoredValue::Number(number)) => (*number, None, None),
+        Some(StoredValue::Formula(formula)) => (0, Some(formula), None),
+        Some(StoredValue::Text(text)) => (0, None, Some(text)),
+        None => {
+            sqlx::query("DELETE FROM room_variables WHERE room_id = ? AND key = ?")
+                .bind(room_id)
+                .bind(variable_name.to_lowercase())
+                .execute(&mut *tx)
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    sqlx::query(
+        "INSERT INTO room_variables (room_id, key, value, formula, string_value)
+         values (?, ?, ?, ?, ?)
+         ON CONFLICT(room_id, key) DO
+         UPDATE SET value = excluded.value, formula = excluded.formula,
+                    string_value = excluded.string_value",
+    )
+    .bind(room_id)
+    .bind(variable_name.to_lowercase())
+    .bind(number)
+    .bind(formula)
+    .bind(text)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(())
+}
+
+impl Database {
+    /// Store a room variable in its own transaction, recording the
+    /// change in the variable history.
+    async fn set_room_value(
+        &self,
+        room_id: &str,
+        variable_name: &str,
+        value: StoredValue,
+    ) -> Result<(), DataError> {
+        let mut tx = self.conn.begin().await?;
+        let key = room_variable_key(room_id, variable_name);
+        write_variable(&mut tx, &self.source, key, Some(&value)).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl RoomVariables for Database {
+    async fn get_room_variables(&self, room_id: &str) -> Result<HashMap<String, i32>, DataError> {
+        let rows = sqlx::query!(
+            r#"SELECT key, value as "value: i32" FROM room_variables
+               WHERE room_id = ? AND formula IS NULL AND string_value IS NULL"#,
+            room_id
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| (row.key, row.value)).collect())
+    }
+
+    async fn set_room_variable(
+        &self,
+        room_id: &str,
+        variable_name: &str,
+        value: i32,
+    ) -> Result<(), DataError> {
+        self.set_room_value(room_id, variable_name, StoredValue::Number(value))
+            .await
+    }
+
+    async fn get_room_formulas(&self, room_id: &str) -> Result<HashMap<String, String>, DataError> {
+        let rows = sqlx::query!(
+            r#"SELECT key, formula as "formula!: String" FROM room_variables
+               WHERE room_id = ? AND formula IS NOT NULL"#,
+            room_id
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| (row.key, row.formula)).collect())
+    }
+
+    async fn set_room_formula(
+        &self,
+        room_id: &str,
+        variable_name: &str,
+        formula: &str,
+    ) -> Result<(), DataError> {
+        let value = StoredValue::Formula(formula.to_string());
+        self.set_room_value(room_id, variable_name, value).await
+    }
+
+    async fn get_room_strings(&self, room_id: &str) -> Result<HashMap<String, String>, DataError> {
+        let rows = sqlx::query!(
+            r#"SELECT key, string_value as "string_value!: String" FROM room_variables
+               WHERE room_id = ? AND string_value IS NOT NULL"#,
+            room_id
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.key, row.string_value))
+            .collect())
+    }
+
+    async fn set_room_string(
+        &self,
+        room_id: &str,
+        variable_name: &str,
+        value: &str,
+    ) -> Result<(), DataError> {
+        let value = StoredValue::Text(value.to_string());
+        self.set_room_value(room_id, variable_name, value).await
+    }
+
+    async fn adjust_room_variable(
+        &self,
+        room_id: &str,
+        variable_name: &str,
+        amount: i32,
+    ) -> Result<(i32, i32), DataError> {
+        let variable_name = &variable_name.to_lowercase();
+        let mut tx = self.conn.begin().await?;
+
+        //Add the amount in a single statement, so concurrent changes
+        //are never lost. Should be query_scalar! macro, but it does
+        //not support RETURNING.
+        let new_value = sqlx::query_scalar::<_, i64>(
+            "UPDATE room_variables SET value = value + ?
+             WHERE room_id = ? AND key = ? AND formula IS NULL AND string_value IS NULL
+             RETURNING value",
+        )
+        .bind(amount)
+        .bind(room_id)
+        .bind(variable_name)
+        .fetch_optional(&mut tx)
+        .await?
+        .ok_or_else(|| DataError::KeyDoesNotExist(variable_name.to_string()))?;
+
+        let to_i32 = |value: i64| i32::try_from(value).map_err(|_| DataError::I32SchemaViolation);
+        let (old_value, new_value) = (to_i32(new_value - i64::from(amount))?, to_i32(new_value)?);
+
+        let key = room_variable_key(room_id, variable_name);
+        let (old, new) = (
+            StoredValue::Number(old_value),
+            StoredValue::Number(new_value),
+        );
+        record_change(&mut tx, &self.source, key, Some(&old), Some(&new), None).await?;
+
+        tx.commit().await?;
+        Ok((old_value, new_value))
+    }
+
+    async fn delete_room_variable(
+        &self,
+        room_id: &str,
+        variable_name: &str,
+    ) -> Result<(), DataError> {
+        let mut tx = self.conn.begin().await?;
+        let key = room_variable_key(room_id, variable_name);
+        let old_value = write_variable(&mut tx, &self.source, key, None).await?;
+
+        if old_value.is_none() {
+            return Err(DataError::KeyDoesNotExist(variable_name.to_lowercase()));
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn copy_all_variables(&self, from_room: &str, to_room: &str) -> Result<u64, DataError> {
+        let mut tx = self.conn.begin().await?;
+        let copied = copy_variables(&mut tx, &self.source, None, from_room, to_room).await?;
+
+        //Each copied room variable, along with the value it replaces.
+        //Should be query_as! macro, but the left join breaks it.
+        let copies: Vec<CopiedVariable> = sqlx::query_as(
+            r#"SELECT NULL as "user_id", 0 as "character_id", v.key,
+                      v.value, v.formula, v.string_value,
+                      d.key as "old_key", d.value as "old_value",
+                      d.formula as "old_formula", d.string_value as "old_string_value"
+               FROM room_variables v
+               LEFT JOIN room_variables d ON d.room_id = ? AND d.key = v.key
+               WHERE v.room_id = ?"#,
+        )
+        .bind(to_room)
+        .bind(from_room)
+        .fetch_all(&mut tx)
+        .await?;
+
+        record_copies(&mut tx, &self.source, to_room, copies).await?;
+
+        sqlx::query(
+            "INSERT INTO room_variables (room_id, key, value, formula, string_value)
+             SELECT ?, key, value, formula, string_value FROM room_variables
+             WHERE room_id = ?
+             ON CONFLICT(room_id, key) DO
+             UPDATE SET value = excluded.value, formula = excluded.formula,
+                        string_value = excluded.string_value",
+        )
+        .bind(to_room)
+        .bind(from_room)
+        .execute(&mut tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO room_info (room_id, game_system)
+             SELECT ?, game_system FROM room_info WHERE room_id = ?",
+        )
+        .bind(to_room)
+        .bind(from_room)
+        .execute(&mut tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO room_settings
+                    (room_id, inline_rolls, inline_roll_open, inline_roll_close, command_prefix)
+             SELECT ?, inline_rolls, inline_roll_open, inline_roll_close, command_prefix
+             FROM room_settings WHERE room_id = ?",
+        )
+        .bind(to_room)
+        .bind(from_room)
+        .execute(&mut tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO command_permissions (room_id, command, role)
+             SELECT ?, command, role FROM command_permissions WHERE room_id = ?",
+        )
+        .bind(to_room)
+        .bind(from_room)
+        .execute(&mut tx)
+        .await?;
+
+        tx.commit().await?;
+        Ok(copied)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::Database;
+    use crate::db::Variables;
+    use std::future::Future;
+
+    async fn with_db<Fut>(f: impl FnOnce(Database) -> Fut)
+    where
+        Fut: Future<Output = ()>,
+    {
+        let db_path = tempfile::NamedTempFile::new_in(".").unwrap();
+        crate::db::sqlite::migrator::migrate(db_path.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        let db = Database::new(db_path.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        f(db).await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn room_variables_are_separate_from_user_variables() {
+        with_db(|db| async move {
+            db.set_room_variable("myroom", "Difficulty", 3)
+                .await
+                .expect("Could not set room variable");
+
+            db.set_user_variable("myuser", "myroom", "difficulty", 1)
+                .await
+                .expect("Could not set user variable");
+
+            let room = db.get_room_variables("myroom").await.unwrap();
+            assert_eq!(Some(&3), room.get("difficulty"));
+
+            let other_room = db.get_room_variables("otherroom").await.unwrap();
+            assert!(other_room.is_empty());
+
+            let user = db.get_user_variable("myuser", "myroom", "difficulty").await;
+            assert!(matches!(user, Ok(1)));
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn room_variable_replaces_other_kinds() {
+        with_db(|db| async move {
+            db.set_room_formula("myroom", "modifier", "difficulty - 1")
+                .await
+                .expect("Could not set room formula");
+
+            db.set_room_string("myroom", "modifier", "none")
+                .await
+                .expect("Could not set room string");
+
+            let formulas = db.get_room_formulas("myroom").await.unwrap();
+            let strings = db.get_room_strings("myroom").await.unwrap();
+
+            assert!(formulas.is_empty());
+            assert_eq!(Some("none"), strings.get("modifier").map(String::as_str));
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn adjust_and_delete_room_variable() {
+        with_db(|db| async move {
+            db.set_room_variable("myroom", "scene_modifier", 1)
+                .await
+                .expect("Could not set room variable");
+
+            let change = db
+                .adjust_room_variable("myroom", "scene_modifier", -3)
+                .await
+                .expect("Could not adjust room variable");
+
+            assert_eq!((1, -2), change);
+
+            db.delete_room_variable("myroom", "scene_modifier")
+                .await
+                .expect("Could not delete room variable");
+
+            let result = db.delete_room_variable("myroom", "scene_modifier").await;
+            assert!(matches!(result, Err(DataError::KeyDoesNotExist(_))));
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn copy_all_variables_with_characters() {
+        use crate::db::Characters;
+
+        with_db(|db| async move {
+            db.create_character("myuser", "oldroom", "Anna")
+                .await
+                .expect("Could not create character");
+
+            db.set_active_character("myuser", "oldroom", Some("Anna"))
+                .await
+                .expect("Could not switch character");
+
+            db.set_user_variable("myuser", "oldroom", "strength", 4)
+                .await
+                .expect("Could not set variable");
+
+            db.set_room_variable("oldroom", "difficulty", 2)
+                .await
+                .expect("Could not set room variable");
+
+            let copied = db
+                .copy_all_variables("oldroom", "newroom")
+                .await
+                .expect("Could not copy variables");
+
+            let active = db.get_active_character("myuser", "newroom").await.unwrap();
+            let value = db.get_user_variable("myuser", "newroom", "strength").await;
+            let room = db.get_room_variables("newroom").await.unwrap();
+
+            assert_eq!(1, copied);
+            assert_eq!(Some("Anna".to_string()), active);
+            assert!(matches!(value, Ok(4)));
+            assert_eq!(Some(&2), room.get("difficulty"));
+        })
+        .await;
+    }
+}
@@ -1,5 +1,7 @@
 use super::Database;
 use crate::db::{errors::DataError, Rooms};
+use crate::models::{Role, RoomSettings};
+use crate::systems::GameSystem;
 use async_trait::async_trait;
 use sqlx::SqlitePool;
 use std::time::{SystemTime, UNIX_EPOCH};
@@ -47,12 +49,142 @@ impl Rooms for Database {
             }
         }
     }
+
+    async fn get_game_system(&self, room_id: &str) -> Result<Option<GameSystem>, DataError> {
+        let row = sqlx::query!(
+            r#"SELECT game_system FROM room_info WHERE room_id = ?"#,
+            room_id
+        )
+        .fetch_optional(&self.conn)
+        .await?;
+
+        row.map(|row| row.game_system.parse().map_err(|_| DataError::InvalidValue))
+            .transpose()
+    }
+
+    async fn set_game_system(&self, room_id: &str, system: GameSystem) -> Result<(), DataError> {
+        sqlx::query(
+            "INSERT INTO room_info (room_id, game_system) VALUES (?, ?)
+             ON CONFLICT(room_id) DO UPDATE SET game_system = excluded.game_system",
+        )
+        .bind(room_id)
+        .bind(system.as_ref())
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_room_settings(&self, room_id: &str) -> Result<RoomSettings, DataError> {
+        let row = sqlx::query!(
+            r#"SELECT inline_rolls as "inline_rolls: bool", inline_roll_open, inline_roll_close,
+                      command_prefix
+               FROM room_settings WHERE room_id = ?"#,
+            room_id
+        )
+        .fetch_optional(&self.conn)
+        .await?;
+
+        let settings = row.map(|row| RoomSettings {
+            inline_rolls: row.inline_rolls,
+            inline_roll_open: row.inline_roll_open,
+            inline_roll_close: row.inline_roll_close,
+            command_prefix: row.command_prefix,
+        });
+
+        Ok(settings.unwrap_or_default())
+    }
+
+    async fn set_room_settings(
+        &self,
+        room_id: &str,
+        settings: &RoomSettings,
+    ) -> Result<(), DataError> {
+        sqlx::query(
+            "INSERT INTO room_settings
+                    (room_id, inline_rolls, inline_roll_open, inline_roll_close, command_prefix)
+             VALUES (?, ?, ?, ?, ?)
+             ON CONFLICT(room_id) DO UPDATE SET inline_rolls = excluded.inline_rolls,
+                 inline_roll_open = excluded.inline_roll_open,
+                 inline_roll_close = excluded.inline_roll_close,
+                 command_prefix = excluded.command_prefix",
+        )
+        .bind(room_id)
+        .bind(settings.inline_rolls)
+        .bind(&settings.inline_roll_open)
+        .bind(&settings.inline_roll_close)
+        .bind(&settings.command_prefix)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+
+    async fn get_command_roles(&self, room_id: &str) -> Result<Vec<(String, Role)>, DataError> {
+        let rows = sqlx::query!(
+            r#"SELECT command, role as "role: Role" FROM command_permissions
+               WHERE room_id = ? ORDER BY command"#,
+            room_id
+        )
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.command, row.role))
+            .collect())
+    }
+
+    async fn get_command_role(
+        &self,
+        room_id: &str,
+        command: &str,
+    ) -> Result<Option<Role>, DataError> {
+        let row = sqlx::query!(
+            r#"SELECT role as "role: Role" FROM command_permissions
+               WHERE room_id = ? AND command = ?"#,
+            room_id,
+            command
+        )
+        .fetch_optional(&self.conn)
+        .await?;
+
+        Ok(row.map(|row| row.role))
+    }
+
+    async fn set_command_role(
+        &self,
+        room_id: &str,
+        command: &str,
+        role: Option<Role>,
+    ) -> Result<(), DataError> {
+        match role {
+            Some(role) => sqlx::query(
+                "INSERT INTO command_permissions (room_id, command, role) VALUES (?, ?, ?)
+                 ON CONFLICT(room_id, command) DO UPDATE SET role = excluded.role",
+            )
+            .bind(room_id)
+            .bind(command)
+            .bind(role),
+            None => {
+                sqlx::query("DELETE FROM command_permissions WHERE room_id = ? AND command = ?")
+                    .bind(room_id)
+                    .bind(command)
+            }
+        }
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::db::sqlite::Database;
     use crate::db::Rooms;
+    use crate::models::{Role, RoomSettings};
+    use crate::systems::GameSystem;
     use std::future::Future;
 
     async fn with_db<Fut>(f: impl FnOnce(Database) -> Fut)
@@ -90,4 +222,115 @@ mod tests {
         })
         .await;
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn no_game_system_returns_none() {
+        with_db(|db| async move {
+            let system = db
+                .get_game_system("myroom")
+                .await
+                .expect("get_game_system failed");
+
+            assert_eq!(None, system);
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn set_game_system_replaces_previous() {
+        with_db(|db| async move {
+            db.set_game_system("myroom", GameSystem::MageTheAwakening)
+                .await
+                .expect("first set_game_system failed");
+
+            db.set_game_system("myroom", GameSystem::MummyTheCurse)
+                .await
+                .expect("second set_game_system failed");
+
+            let system = db
+                .get_game_system("myroom")
+                .await
+                .expect("get_game_system failed");
+
+            assert_eq!(Some(GameSystem::MummyTheCurse), system);
+
+            let other_room = db
+                .get_game_system("otherroom")
+                .await
+                .expect("get_game_system failed");
+
+            assert_eq!(None, other_room);
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn room_settings_default_until_set() {
+        with_db(|db| async move {
+            let settings = db
+                .get_room_settings("myroom")
+                .await
+                .expect("get_room_settings failed");
+
+            assert_eq!(RoomSettings::default(), settings);
+
+            let changed = RoomSettings {
+                inline_rolls: true,
+                inline_roll_open: "((".to_owned(),
+                inline_roll_close: "))".to_owned(),
+                command_prefix: Some(".".to_owned()),
+            };
+
+            db.set_room_settings("myroom", &changed)
+                .await
+                .expect("set_room_settings failed");
+
+            let settings = db
+                .get_room_settings("myroom")
+                .await
+                .expect("get_room_settings failed");
+
+            assert_eq!(changed, settings);
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn command_roles_can_be_reset() {
+        with_db(|db| async move {
+            db.set_command_role("myroom", "roll", Some(Role::GameMaster))
+                .await
+                .expect("set_command_role failed");
+
+            db.set_command_role("myroom", "macro", Some(Role::RoomAdmin))
+                .await
+                .expect("set_command_role failed");
+
+            let role = db
+                .get_command_role("myroom", "roll")
+                .await
+                .expect("get_command_role failed");
+
+            assert_eq!(Some(Role::GameMaster), role);
+
+            db.set_command_role("myroom", "roll", None)
+                .await
+                .expect("set_command_role failed");
+
+            let roles = db
+                .get_command_roles("myroom")
+                .await
+                .expect("get_command_roles failed");
+
+            assert_eq!(vec![("macro".to_owned(), Role::RoomAdmin)], roles);
+
+            let other_room = db
+                .get_command_role("otherroom", "macro")
+                .await
+                .expect("get_command_role failed");
+
+            assert_eq!(None, other_room);
+        })
+        .await;
+    }
 }
@@ -1,7 +1,375 @@
+use super::history::{record_change, record_copies, write_variable, CopiedVariable, VariableKey};
 use super::Database;
 use crate::db::{errors::DataError, Variables};
+use crate::models::{ChangeSource, StoredValue, VariableBounds, VariableSet};
 use async_trait::async_trait;
+use sqlx::{Sqlite, Transaction};
 use std::collections::HashMap;
+use std::convert::TryFrom;
+
+/// The ID of the user's active character in the room, or 0 if the
+/// user has no active character.
+async fn active_character_id(
+    tx: &mut Transaction<'_, Sqlite>,
+    user: &str,
+    room_id: &str,
+) -> Result<i64, DataError> {
+    let row = sqlx::query!(
+        r#"SELECT character_id as "character_id: i64" FROM active_characters
+           WHERE user_id = ? AND room_id = ?"#,
+        user,
+        room_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    Ok(row.map(|row| row.character_id).unwrap_or(0))
+}
+
+/// Insert or replace a variable of one of the user's characters (0
+/// for the user's own variables) in the room, or delete it if there
+/// is no value. Only the column of the given kind of value is set;
+/// the others are cleared.
+pub(super) async fn store_user_variable(
+    tx: &mut Transaction<'_, Sqlite>,
+    user: &str,
+    room_id: &str,
+    character_id: i64,
+    variable_name: &str,
+    value: Option<&StoredValue>,
+) -> Result<(), DataError> {
+    let (number, formula, text) = match value {
+        Some(StoredValue::Number(number)) => (*number, None, None),
+        Some(StoredValue::Formula(formula)) => (0, Some(formula), None),
+        Some(StoredValue::Text(text)) => (0, None, Some(text)),
+        None => {
+            sqlx::query(
+                "DELETE FROM user_variables
+                 WHERE user_id = ? AND room_id = ? AND character_id = ? AND key = ?",
+            )
+            .bind(user)
+            .bind(room_id)
+            .bind(character_id)
+            .bind(variable_name.to_lowercase())
+            .execute(&mut *tx)
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    sqlx::query(
+        "INSERT INTO user_variables
+                (user_id, room_id, character_id, key, value, formula, string_value)
+                values (?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT(user_id, room_id, character_id, key) DO
+                UPDATE SET value = excluded.value, formula = excluded.formula,
+                           string_value = excluded.string_value",
+    )
+    .bind(user)
+    .bind(room_id)
+    .bind(character_id)
+    .bind(variable_name.to_lowercase())
+    .bind(number)
+    .bind(formula)
+    .bind(text)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(())
+}
+
+impl Database {
+    /// Store (or delete) a variable of the user's active character
+    /// (or the user) in the room, recording the change in the
+    /// variable history.
+    async fn write_user_variable(
+        &self,
+        tx: &mut Transaction<'_, Sqlite>,
+        user: &str,
+        room_id: &str,
+        variable_name: &str,
+        value: Option<&StoredValue>,
+    ) -> Result<Option<StoredValue>, DataError> {
+        let key = VariableKey {
+            user: Some(user),
+            room_id,
+            character_id: active_character_id(tx, user, room_id).await?,
+            name: variable_name,
+        };
+
+        write_variable(tx, &self.source, key, value).await
+    }
+
+    /// Store a variable in its own transaction.
+    async fn set_user_value(
+        &self,
+        user: &str,
+        room_id: &str,
+        variable_name: &str,
+        value: StoredValue,
+    ) -> Result<(), DataError> {
+        let mut tx = self.conn.begin().await?;
+        self.write_user_variable(&mut tx, user, room_id, variable_name, Some(&value))
+            .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Copy variables from one room to another, for a single user or all
+/// users. Characters and active characters are copied along with
+/// their variables. Copied variables replace variables of the same
+/// name in the destination room, and every changed value is recorded
+/// in the history. Returns the number of variables copied.
+pub(super) async fn copy_variables(
+    tx: &mut Transaction<'_, Sqlite>,
+    source: &ChangeSource,
+    user: Option<&str>,
+    from_room: &str,
+    to_room: &str,
+) -> Result<u64, DataError> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO characters (user_id, room_id, name)
+         SELECT user_id, ?, name FROM characters
+         WHERE room_id = ? AND (? IS NULL OR user_id = ?)",
+    )
+    .bind(to_room)
+    .bind(from_room)
+    .bind(user)
+    .bind(user)
+    .execute(&mut *tx)
+    .await?;
+
+    //Each copied variable, along with the value it replaces. Should
+    //be query_as! macro, but the left joins break it.
+    let copies: Vec<CopiedVariable> = sqlx::query_as(
+        r#"SELECT v.user_id, ifnull(new.id, 0) as "character_id", v.key,
+                  v.value, v.formula, v.string_value,
+                  d.key as "old_key", d.value as "old_value",
+                  d.formula as "old_formula", d.string_value as "old_string_value"
+           FROM user_variables v
+           LEFT JOIN characters old ON old.id = v.character_id
+           LEFT JOIN characters new
+             ON new.user_id = old.user_id AND new.room_id = ? AND new.name = old.name
+           LEFT JOIN user_variables d
+             ON d.user_id = v.user_id AND d.room_id = ?
+             AND d.character_id = ifnull(new.id, 0) AND d.key = v.key
+           WHERE v.room_id = ? AND (? IS NULL OR v.user_id = ?)"#,
+    )
+    .bind(to_room)
+    .bind(to_room)
+    .bind(from_room)
+    .bind(user)
+    .bind(user)
+    .fetch_all(&mut *tx)
+    .await?;
+
+    record_copies(tx, source, to_room, copies).await?;
+
+    let copied = sqlx::query(
+        "INSERT INTO user_variables
+                (user_id, room_id, character_id, key, value, formula, string_value,
+                 min_value, max_value, refuse_out_of_bounds)
+         SELECT v.user_id, ?, ifnull(new.id, 0), v.key, v.value, v.formula, v.string_value,
+                v.min_value, v.max_value, v.refuse_out_of_bounds
+         FROM user_variables v
+         LEFT JOIN characters old ON old.id = v.character_id
+         LEFT JOIN characters new
+           ON new.user_id = old.user_id AND new.room_id = ? AND new.name = old.name
+         WHERE v.room_id = ? AND (? IS NULL OR v.user_id = ?)
+         ON CONFLICT(user_id, room_id, character_id, key) DO
+         UPDATE SET value = excluded.value, formula = excluded.formula,
+                    string_value = excluded.string_value, min_value = excluded.min_value,
+                    max_value = excluded.max_value,
+                    refuse_out_of_bounds = excluded.refuse_out_of_bounds",
+    )
+    .bind(to_room)
+    .bind(to_room)
+    .bind(from_room)
+    .bind(user)
+    .bind(user)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT OR IGNORE INTO active_characters (user_id, room_id, character_id)
+         SELECT a.user_id, ?, new.id FROM active_characters a
+         JOIN characters old ON old.id = a.character_id
+         JOIN characters new
+           ON new.user_id = old.user_id AND new.room_id = ? AND new.name = old.name
+         WHERE a.room_id = ? AND (? IS NULL OR a.user_id = ?)",
+    )
+    .bind(to_room)
+    .bind(to_room)
+    .bind(from_room)
+    .bind(user)
+    .bind(user)
+    .execute(&mut *tx)
+    .await?;
+
+    Ok(copied.rows_affected())
+}
+
+/// A numeric variable after an amount was added to it, with the
+/// bounds it still has to be clamped to.
+#[derive(sqlx::FromRow)]
+struct AdjustedVariable {
+    character_id: i64,
+    value: i64,
+    min_value: Option<i32>,
+    max_value: Option<i32>,
+    refuse_out_of_bounds: bool,
+}
+
+/// Add an amount to a numeric variable of the user's active
+/// character (or the user) in the room, respecting its bounds and an
+/// optional floor that is always refused. Returns the old and new
+/// values.
+async fn adjust_variable(
+    db: &Database,
+    user: &str,
+    room_id: &str,
+    variable_name: &str,
+    amount: i32,
+    floor: Option<i32>,
+) -> Result<(i32, i32), DataError> {
+    let variable_name = &variable_name.to_lowercase();
+    let mut tx = db.conn.begin().await?;
+
+    //Add the amount in a single statement, which only changes the
+    //variable if the new value stays above the floor and within
+    //bounds that refuse changes, so concurrent changes are never
+    //lost. It is the first statement of the transaction, so that the
+    //transaction never reads a value that another one changes before
+    //this one writes. Should be query_as! macro, but it does not
+    //support RETURNING.
+    let adjusted = sqlx::query_as::<_, AdjustedVariable>(
+        "UPDATE user_variables SET value = value + ?
+         WHERE user_id = ? AND room_id = ? AND key = ?
+         AND character_id = ifnull(
+           (SELECT character_id FROM active_characters
+            WHERE user_id = ? AND room_id = ?), 0)
+         AND formula IS NULL AND string_value IS NULL
+         AND (? IS NULL OR value + ? >= ?)
+         AND (NOT refuse_out_of_bounds
+              OR ((min_value IS NULL OR value + ? >= min_value)
+                  AND (max_value IS NULL OR value + ? <= max_value)))
+         RETURNING character_id, value, min_value, max_value, refuse_out_of_bounds",
+    )
+    .bind(amount)
+    .bind(user)
+    .bind(room_id)
+    .bind(variable_name)
+    .bind(user)
+    .bind(room_id)
+    .bind(floor)
+    .bind(amount)
+    .bind(floor)
+    .bind(amount)
+    .bind(amount)
+    .fetch_optional(&mut tx)
+    .await?;
+
+    let key = |character_id| VariableKey {
+        user: Some(user),
+        room_id,
+        character_id,
+        name: variable_name,
+    };
+
+    let adjusted = match adjusted {
+        Some(adjusted) => adjusted,
+        None => {
+            let character_id = active_character_id(&mut tx, user, room_id).await?;
+            return Err(refusal(&mut tx, key(character_id), amount, floor).await?);
+        }
+    };
+
+    let key = key(adjusted.character_id);
+
+    let to_i32 = |value: i64| i32::try_from(value).map_err(|_| DataError::I32SchemaViolation);
+    let old_value = to_i32(adjusted.value - i64::from(amount))?;
+
+    //The variable stays locked by the transaction until it commits,
+    //so clamping it to its bounds afterwards is still atomic.
+    let mut new_value = adjusted.value;
+    if !adjusted.refuse_out_of_bounds {
+        new_value = adjusted
+            .max_value
+            .map_or(new_value, |max| std::cmp::min(new_value, max.into()));
+        new_value = adjusted
+            .min_value
+            .map_or(new_value, |min| std::cmp::max(new_value, min.into()));
+    }
+
+    let new_value = to_i32(new_value)?;
+    if i64::from(new_value) != adjusted.value {
+        sqlx::query(
+            "UPDATE user_variables SET value = ?
+             WHERE user_id = ? AND room_id = ? AND key = ? AND character_id = ?",
+        )
+        .bind(new_value)
+        .bind(user)
+        .bind(room_id)
+        .bind(variable_name)
+        .bind(key.character_id)
+        .execute(&mut tx)
+        .await?;
+    }
+
+    if old_value != new_value {
+        let (old, new) = (
+            StoredValue::Number(old_value),
+            StoredValue::Number(new_value),
+        );
+        record_change(&mut tx, &db.source, key, Some(&old), Some(&new), None).await?;
+    }
+
+    tx.commit().await?;
+    Ok((old_value, new_value))
+}
+
+/// Why adjust_variable refused to add an amount to a variable: it is
+/// missing, or the new value would go past the floor or its bounds.
+/// The variable is read in the same transaction as the refused
+/// update, so this is the value that the update saw.
+async fn refusal(
+    tx: &mut Transaction<'_, Sqlite>,
+    key: VariableKey<'_>,
+    amount: i32,
+    floor: Option<i32>,
+) -> Result<DataError, DataError> {
+    let row = sqlx::query!(
+        r#"SELECT value as "value: i32",
+                  min_value as "min_value: i32",
+                  max_value as "max_value: i32"
+           FROM user_variables
+           WHERE user_id = ? AND room_id = ? AND key = ? AND character_id = ?
+           AND formula IS NULL AND string_value IS NULL"#,
+        key.user,
+        key.room_id,
+        key.name,
+        key.character_id
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let name = key.name.to_string();
+    let row = match row {
+        Some(row) => row,
+        None => return Ok(DataError::KeyDoesNotExist(name)),
+    };
+
+    let new_value = i64::from(row.value) + i64::from(amount);
+    let error = match (floor, row.max_value) {
+        (Some(floor), _) if new_value < floor.into() => DataError::BelowMinimum(name, floor),
+        (_, Some(max)) if new_value > max.into() => DataError::AboveMaximum(name, max),
+        _ => DataError::BelowMinimum(name, row.min_value.unwrap_or_default()),
+    };
+
+    Ok(error)
+}
 
 #[async_trait]
 impl Variables for Database {
@@ -12,9 +380,14 @@ impl Variables for Database {
     ) -> Result<HashMap<String, i32>, DataError> {
         let rows = sqlx::query!(
             r#"SELECT key, value as "value: i32" FROM user_variables
-               WHERE room_id = ? AND user_id = ?"#,
+               WHERE room_id = ? AND user_id = ? AND formula IS NULL AND string_value IS NULL
+               AND character_id = ifnull(
+                 (SELECT character_id FROM active_characters
+                  WHERE user_id = ? AND room_id = ?), 0)"#,
             room_id,
-            user
+            user,
+            user,
+            room_id
         )
         .fetch_all(&self.conn)
         .await?;
@@ -25,9 +398,13 @@ impl Variables for Database {
     async fn get_variable_count(&self, user: &str, room_id: &str) -> Result<i32, DataError> {
         let row = sqlx::query!(
             r#"SELECT count(*) as "count: i32" FROM user_variables
-               WHERE room_id = ? and user_id = ?"#,
+               WHERE room_id = ? and user_id = ? AND character_id = ifnull(
+                 (SELECT character_id FROM active_characters
+                  WHERE user_id = ? AND room_id = ?), 0)"#,
             room_id,
-            user
+            user,
+            user,
+            room_id
         )
         .fetch_optional(&self.conn)
         .await?;
@@ -41,12 +418,18 @@ impl Variables for Database {
         room_id: &str,
         variable_name: &str,
     ) -> Result<i32, DataError> {
+        let variable_name = &variable_name.to_lowercase();
         let row = sqlx::query!(
             r#"SELECT value as "value: i32" FROM user_variables
-               WHERE user_id = ? AND room_id = ? AND key = ?"#,
+               WHERE user_id = ? AND room_id = ? AND key = ? AND formula IS NULL AND string_value IS NULL
+               AND character_id = ifnull(
+                 (SELECT character_id FROM active_characters
+                  WHERE user_id = ? AND room_id = ?), 0)"#,
             user,
             room_id,
-            variable_name
+            variable_name,
+            user,
+            room_id
         )
         .fetch_optional(&self.conn)
         .await?;
@@ -62,37 +445,231 @@ impl Variables for Database {
         variable_name: &str,
         value: i32,
     ) -> Result<(), DataError> {
-        sqlx::query(
-            "INSERT INTO user_variables
-                    (user_id, room_id, key, value)
-                    values (?, ?, ?, ?)",
+        let value = StoredValue::Number(value);
+        self.set_user_value(user, room_id, variable_name, value)
+            .await
+    }
+
+    async fn get_user_formulas(
+        &self,
+        user: &str,
+        room_id: &str,
+    ) -> Result<HashMap<String, String>, DataError> {
+        let rows = sqlx::query!(
+            r#"SELECT key, formula as "formula!: String" FROM user_variables
+               WHERE room_id = ? AND user_id = ? AND formula IS NOT NULL
+               AND character_id = ifnull(
+                 (SELECT character_id FROM active_characters
+                  WHERE user_id = ? AND room_id = ?), 0)"#,
+            room_id,
+            user,
+            user,
+            room_id
         )
-        .bind(user)
-        .bind(room_id)
-        .bind(variable_name)
-        .bind(value)
-        .execute(&self.conn)
+        .fetch_all(&self.conn)
+        .await?;
+
+        Ok(rows.into_iter().map(|row| (row.key, row.formula)).collect())
+    }
+
+    async fn set_user_formula(
+        &self,
+        user: &str,
+        room_id: &str,
+        variable_name: &str,
+        formula: &str,
+    ) -> Result<(), DataError> {
+        let value = StoredValue::Formula(formula.to_string());
+        self.set_user_value(user, room_id, variable_name, value)
+            .await
+    }
+
+    async fn get_user_strings(
+        &self,
+        user: &str,
+        room_id: &str,
+    ) -> Result<HashMap<String, String>, DataError> {
+        let rows = sqlx::query!(
+            r#"SELECT key, string_value as "string_value!: String" FROM user_variables
+               WHERE room_id = ? AND user_id = ? AND string_value IS NOT NULL
+               AND character_id = ifnull(
+                 (SELECT character_id FROM active_characters
+                  WHERE user_id = ? AND room_id = ?), 0)"#,
+            room_id,
+            user,
+            user,
+            room_id
+        )
+        .fetch_all(&self.conn)
         .await?;
 
+        Ok(rows
+            .into_iter()
+            .map(|row| (row.key, row.string_value))
+            .collect())
+    }
+
+    async fn set_user_string(
+        &self,
+        user: &str,
+        room_id: &str,
+        variable_name: &str,
+        value: &str,
+    ) -> Result<(), DataError> {
+        let value = StoredValue::Text(value.to_string());
+        self.set_user_value(user, room_id, variable_name, value)
+            .await
+    }
+
+    async fn import_user_variables(
+        &self,
+        user: &str,
+        room_id: &str,
+        variables: &VariableSet,
+        delete: &[String],
+    ) -> Result<(), DataError> {
+        let mut tx = self.conn.begin().await?;
+
+        for name in delete {
+            self.write_user_variable(&mut tx, user, room_id, name, None)
+                .await?;
+        }
+
+        let values = variables
+            .variables
+            .iter()
+            .map(|(name, value)| (name, StoredValue::Number(*value)))
+            .chain(
+                variables
+                    .formulas
+                    .iter()
+                    .map(|(name, formula)| (name, StoredValue::Formula(formula.clone()))),
+            )
+            .chain(
+                variables
+                    .strings
+                    .iter()
+                    .map(|(name, text)| (name, StoredValue::Text(text.clone()))),
+            );
+
+        for (name, value) in values {
+            self.write_user_variable(&mut tx, user, room_id, name, Some(&value))
+                .await?;
+        }
+
+        tx.commit().await?;
         Ok(())
     }
 
-    async fn delete_user_variable(
+    async fn copy_user_variables(
+        &self,
+        user: &str,
+        from_room: &str,
+        to_room: &str,
+    ) -> Result<u64, DataError> {
+        let mut tx = self.conn.begin().await?;
+        let copied = copy_variables(&mut tx, &self.source, Some(user), from_room, to_room).await?;
+        tx.commit().await?;
+        Ok(copied)
+    }
+
+    async fn adjust_user_variable(
         &self,
         user: &str,
         room_id: &str,
         variable_name: &str,
+        amount: i32,
+    ) -> Result<(i32, i32), DataError> {
+        adjust_variable(self, user, room_id, variable_name, amount, None).await
+    }
+
+    async fn spend_user_variable(
+        &self,
+        user: &str,
+        room_id: &str,
+        variable_name: &str,
+        amount: i32,
+    ) -> Result<(i32, i32), DataError> {
+        let amount = amount.checked_neg().ok_or(DataError::I32SchemaViolation)?;
+        adjust_variable(self, user, room_id, variable_name, amount, Some(0)).await
+    }
+
+    async fn get_variable_bounds(
+        &self,
+        user: &str,
+        room_id: &str,
+        variable_name: &str,
+    ) -> Result<VariableBounds, DataError> {
+        let variable_name = &variable_name.to_lowercase();
+        let row = sqlx::query!(
+            r#"SELECT min_value as "min_value: i32",
+                      max_value as "max_value: i32",
+                      refuse_out_of_bounds as "refuse_out_of_bounds: bool"
+               FROM user_variables
+               WHERE user_id = ? AND room_id = ? AND key = ?
+               AND character_id = ifnull(
+                 (SELECT character_id FROM active_characters
+                  WHERE user_id = ? AND room_id = ?), 0)"#,
+            user,
+            room_id,
+            variable_name,
+            user,
+            room_id
+        )
+        .fetch_optional(&self.conn)
+        .await?;
+
+        row.map(|r| VariableBounds {
+            min: r.min_value,
+            max: r.max_value,
+            refuse: r.refuse_out_of_bounds,
+        })
+        .ok_or_else(|| DataError::KeyDoesNotExist(variable_name.to_string()))
+    }
+
+    async fn set_variable_bounds(
+        &self,
+        user: &str,
+        room_id: &str,
+        variable_name: &str,
+        bounds: &VariableBounds,
     ) -> Result<(), DataError> {
-        sqlx::query(
-            "DELETE FROM user_variables
-             WHERE user_id = ? AND room_id = ? AND key = ?",
+        let variable_name = &variable_name.to_lowercase();
+        let result = sqlx::query(
+            "UPDATE user_variables
+             SET min_value = ?, max_value = ?, refuse_out_of_bounds = ?
+             WHERE user_id = ? AND room_id = ? AND key = ? AND character_id = ifnull(
+               (SELECT character_id FROM active_characters
+                WHERE user_id = ? AND room_id = ?), 0)",
         )
+        .bind(bounds.min)
+        .bind(bounds.max)
+        .bind(bounds.refuse)
         .bind(user)
         .bind(room_id)
         .bind(variable_name)
+        .bind(user)
+        .bind(room_id)
         .execute(&self.conn)
         .await?;
 
+        if result.rows_affected() == 0 {
+            return Err(DataError::KeyDoesNotExist(variable_name.to_string()));
+        }
+
+        Ok(())
+    }
+
+    async fn delete_user_variable(
+        &self,
+        user: &str,
+        room_id: &str,
+        variable_name: &str,
+    ) -> Result<(), DataError> {
+        let mut tx = self.conn.begin().await?;
+        self.write_user_variable(&mut tx, user, room_id, variable_name, None)
+            .await?;
+        tx.commit().await?;
         Ok(())
     }
 }
@@ -101,7 +678,7 @@ impl Variables for Database {
 mod tests {
     use super::*;
     use crate::db::sqlite::Database;
-    use crate::db::Variables;
+    use crate::db::{VariableHistory, Variables};
     use std::future::Future;
 
     async fn with_db<Fut>(f: impl FnOnce(Database) -> Fut)
@@ -137,6 +714,33 @@ mod tests {
         .await;
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn set_variable_overwrites_existing_value_test() {
+        with_db(|db| async move {
+            db.set_user_variable("myuser", "myroom", "myvariable", 1)
+                .await
+                .expect("Could not set variable");
+
+            db.set_user_variable("myuser", "myroom", "myvariable", 2)
+                .await
+                .expect("Could not overwrite variable");
+
+            let value = db
+                .get_user_variable("myuser", "myroom", "myvariable")
+                .await
+                .expect("Could not get variable");
+
+            let count = db
+                .get_variable_count("myuser", "myroom")
+                .await
+                .expect("Could not get count.");
+
+            assert_eq!(value, 2);
+            assert_eq!(count, 1);
+        })
+        .await;
+    }
+
     #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
     async fn get_missing_variable_test() {
         with_db(|db| async move {
@@ -254,4 +858,287 @@ mod tests {
         })
         .await;
     }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn formula_replaces_number_test() {
+        with_db(|db| async move {
+            db.set_user_variable("myuser", "myroom", "defense", 3)
+                .await
+                .expect("Could not set variable");
+
+            db.set_user_formula("myuser", "myroom", "defense", "wits + athletics")
+                .await
+                .expect("Could not set formula");
+
+            let value = db.get_user_variable("myuser", "myroom", "defense").await;
+            assert!(matches!(value, Err(DataError::KeyDoesNotExist(_))));
+
+            let formulas = db
+                .get_user_formulas("myuser", "myroom")
+                .await
+                .expect("Could not get formulas");
+
+            assert_eq!(
+                Some(&"wits + athletics".to_string()),
+                formulas.get("defense")
+            );
+
+            db.set_user_variable("myuser", "myroom", "defense", 4)
+                .await
+                .expect("Could not set variable");
+
+            let formulas = db.get_user_formulas("myuser", "myroom").await.unwrap();
+            assert!(formulas.is_empty());
+
+            let value = db.get_user_variable("myuser", "myroom", "defense").await;
+            assert_eq!(4, value.unwrap());
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn adjust_variable_test() {
+        with_db(|db| async move {
+            db.set_user_variable("myuser", "myroom", "hp", 10)
+                .await
+                .expect("Could not set variable");
+
+            let result = db.adjust_user_variable("myuser", "myroom", "hp", -3).await;
+            assert_eq!((10, 7), result.unwrap());
+
+            let result = db.adjust_user_variable("myuser", "myroom", "hp", 2).await;
+            assert_eq!((7, 9), result.unwrap());
+
+            let missing = db.adjust_user_variable("myuser", "myroom", "ammo", 1).await;
+            assert!(matches!(missing, Err(DataError::KeyDoesNotExist(_))));
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn adjust_variable_clamps_to_bounds_test() {
+        with_db(|db| async move {
+            db.set_user_variable("myuser", "myroom", "hp", 5)
+                .await
+                .expect("Could not set variable");
+
+            let bounds = VariableBounds {
+                min: Some(0),
+                max: Some(7),
+                refuse: false,
+            };
+
+            db.set_variable_bounds("myuser", "myroom", "hp", &bounds)
+                .await
+                .expect("Could not set bounds");
+
+            let stored = db.get_variable_bounds("myuser", "myroom", "hp").await;
+            assert_eq!(bounds, stored.unwrap());
+
+            let result = db.adjust_user_variable("myuser", "myroom", "hp", 5).await;
+            assert_eq!((5, 7), result.unwrap());
+
+            let result = db.adjust_user_variable("myuser", "myroom", "hp", -10).await;
+            assert_eq!((7, 0), result.unwrap());
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn adjust_variable_refuses_out_of_bounds_test() {
+        with_db(|db| async move {
+            db.set_user_variable("myuser", "myroom", "ammo", 2)
+                .await
+                .expect("Could not set variable");
+
+            let bounds = VariableBounds {
+                min: Some(0),
+                max: None,
+                refuse: true,
+            };
+
+            db.set_variable_bounds("myuser", "myroom", "ammo", &bounds)
+                .await
+                .expect("Could not set bounds");
+
+            let result = db
+                .adjust_user_variable("myuser", "myroom", "ammo", -3)
+                .await;
+            assert!(matches!(result, Err(DataError::BelowMinimum(_, 0))));
+
+            let value = db.get_user_variable("myuser", "myroom", "ammo").await;
+            assert_eq!(2, value.unwrap());
+
+            let result = db
+                .adjust_user_variable("myuser", "myroom", "ammo", 100)
+                .await;
+            assert_eq!((2, 102), result.unwrap());
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn spend_variable_refuses_to_go_below_zero_test() {
+        with_db(|db| async move {
+            db.set_user_variable("myuser", "myroom", "mana", 3)
+                .await
+                .expect("Could not set variable");
+
+            let result = db.spend_user_variable("myuser", "myroom", "mana", 2).await;
+            assert_eq!((3, 1), result.unwrap());
+
+            let result = db.spend_user_variable("myuser", "myroom", "mana", 2).await;
+            assert!(matches!(result, Err(DataError::BelowMinimum(_, 0))));
+
+            let value = db.get_user_variable("myuser", "myroom", "mana").await;
+            assert_eq!(1, value.unwrap());
+
+            let history = db
+                .get_variable_history(Some("myuser"), "myroom", "mana", 10)
+                .await;
+            assert_eq!(2, history.unwrap().len());
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn concurrent_spends_are_not_lost_test() {
+        with_db(|db| async move {
+            db.set_user_variable("myuser", "myroom", "bennies", 10)
+                .await
+                .expect("Could not set variable");
+
+            let spends = (0..12).map(|_| db.spend_user_variable("myuser", "myroom", "bennies", 1));
+            let results = futures::future::join_all(spends).await;
+
+            let spent = results.iter().filter(|result| result.is_ok()).count();
+            let refused = results
+                .iter()
+                .filter(|result| matches!(result, Err(DataError::BelowMinimum(_, 0))))
+                .count();
+
+            assert_eq!((10, 2), (spent, refused));
+
+            let value = db.get_user_variable("myuser", "myroom", "bennies").await;
+            assert_eq!(0, value.unwrap());
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn bounds_for_missing_variable_test() {
+        with_db(|db| async move {
+            let result = db
+                .set_variable_bounds("myuser", "myroom", "hp", &VariableBounds::default())
+                .await;
+
+            assert!(matches!(result, Err(DataError::KeyDoesNotExist(_))));
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn variable_names_are_case_insensitive_test() {
+        with_db(|db| async move {
+            db.set_user_variable("myuser", "myroom", "Melee-Weapons", 3)
+                .await
+                .expect("Could not set variable");
+
+            let value = db
+                .get_user_variable("myuser", "myroom", "MELEE-weapons")
+                .await;
+
+            assert_eq!(3, value.unwrap());
+
+            let variables = db.get_user_variables("myuser", "myroom").await.unwrap();
+            assert_eq!(Some(&3), variables.get("melee-weapons"));
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn string_variables_test() {
+        with_db(|db| async move {
+            db.set_user_string("myuser", "myroom", "weapon", "Longsword")
+                .await
+                .expect("Could not set string");
+
+            let value = db.get_user_variable("myuser", "myroom", "weapon").await;
+            assert!(matches!(value, Err(DataError::KeyDoesNotExist(_))));
+
+            let strings = db.get_user_strings("myuser", "myroom").await.unwrap();
+            assert_eq!(Some(&"Longsword".to_string()), strings.get("weapon"));
+
+            db.set_user_variable("myuser", "myroom", "weapon", 2)
+                .await
+                .expect("Could not set variable");
+
+            let strings = db.get_user_strings("myuser", "myroom").await.unwrap();
+            assert!(strings.is_empty());
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn import_variables_test() {
+        with_db(|db| async move {
+            db.set_user_variable("myuser", "myroom", "strength", 2)
+                .await
+                .expect("Could not set variable");
+
+            db.set_user_variable("myuser", "myroom", "wits", 1)
+                .await
+                .expect("Could not set variable");
+
+            let mut variables = VariableSet::default();
+            variables.variables.insert("strength".to_string(), 3);
+            variables
+                .formulas
+                .insert("defense".to_string(), "wits + 1".to_string());
+
+            db.import_user_variables("myuser", "myroom", &variables, &["wits".to_string()])
+                .await
+                .expect("Could not import variables");
+
+            let numbers = db.get_user_variables("myuser", "myroom").await.unwrap();
+            let formulas = db.get_user_formulas("myuser", "myroom").await.unwrap();
+
+            assert_eq!(Some(&3), numbers.get("strength"));
+            assert_eq!(None, numbers.get("wits"));
+            assert_eq!(Some(&"wits + 1".to_string()), formulas.get("defense"));
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn copy_variables_test() {
+        with_db(|db| async move {
+            db.set_user_variable("myuser", "oldroom", "strength", 3)
+                .await
+                .expect("Could not set variable");
+
+            db.set_user_variable("otheruser", "oldroom", "strength", 1)
+                .await
+                .expect("Could not set variable");
+
+            db.set_user_variable("myuser", "newroom", "strength", 2)
+                .await
+                .expect("Could not set variable");
+
+            let copied = db
+                .copy_user_variables("myuser", "oldroom", "newroom")
+                .await
+                .expect("Could not copy variables");
+
+            let value = db.get_user_variable("myuser", "newroom", "strength").await;
+            let other = db
+                .get_user_variable("otheruser", "newroom", "strength")
+                .await;
+
+            assert_eq!(1, copied);
+            assert!(matches!(value, Ok(3)));
+            assert!(matches!(other, Err(DataError::KeyDoesNotExist(_))));
+        })
+        .await;
+    }
 }
@@ -0,0 +1,180 @@
+use super::Database;
+use crate::db::{errors::DataError, YearZeroRolls};
+use crate::year_zero::dice::RolledYze;
+use async_trait::async_trait;
+use itertools::Itertools;
+
+fn to_dice_list(rolls: &[u32]) -> String {
+    rolls.iter().join(",")
+}
+
+fn from_dice_list(list: &str) -> Result<Vec<u32>, DataError> {
+    list.split(',')
+        .filter(|roll| !roll.is_empty())
+        .map(|roll| roll.parse::<u32>().map_err(|_| DataError::InvalidValue))
+        .collect()
+}
+
+#[async_trait]
+impl YearZeroRolls for Database {
+    async fn get_last_yze_roll(
+        &self,
+        user: &str,
+        room_id: &str,
+    ) -> Result<Option<RolledYze>, DataError> {
+        let row = sqlx::query!(
+            r#"SELECT base_dice, skill_dice, gear_dice, stress_dice,
+                      pushed as "pushed: bool"
+               FROM yze_rolls WHERE user_id = ? AND room_id = ?"#,
+            user,
+            room_id
+        )
+        .fetch_optional(&self.conn)
+        .await?;
+
+        match row {
+            Some(row) => Ok(Some(RolledYze {
+                base: from_dice_list(&row.base_dice)?,
+                skill: from_dice_list(&row.skill_dice)?,
+                gear: from_dice_list(&row.gear_dice)?,
+                stress: row.stress_dice.as_deref().map(from_dice_list).transpose()?,
+                pushed: row.pushed,
+            })),
+            None => Ok(None),
+        }
+    }
+
+    async fn set_last_yze_roll(
+        &self,
+        user: &str,
+        room_id: &str,
+        roll: &RolledYze,
+    ) -> Result<(), DataError> {
+        let stress = roll.stress.as_deref().map(to_dice_list);
+
+        sqlx::query(
+            "INSERT INTO yze_rolls
+                    (user_id, room_id, base_dice, skill_dice, gear_dice, stress_dice, pushed)
+                    VALUES (?, ?, ?, ?, ?, ?, ?)
+                    ON CONFLICT(user_id, room_id) DO UPDATE SET
+                    base_dice = excluded.base_dice,
+                    skill_dice = excluded.skill_dice,
+                    gear_dice = excluded.gear_dice,
+                    stress_dice = excluded.stress_dice,
+                    pushed = excluded.pushed",
+        )
+        .bind(user)
+        .bind(room_id)
+        .bind(to_dice_list(&roll.base))
+        .bind(to_dice_list(&roll.skill))
+        .bind(to_dice_list(&roll.gear))
+        .bind(stress)
+        .bind(roll.pushed)
+        .execute(&self.conn)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::future::Future;
+
+    async fn with_db<Fut>(f: impl FnOnce(Database) -> Fut)
+    where
+        Fut: Future<Output = ()>,
+    {
+        let db_path = tempfile::NamedTempFile::new_in(".").unwrap();
+        crate::db::sqlite::migrator::migrate(db_path.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        let db = Database::new(db_path.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        f(db).await;
+    }
+
+    fn rolled(stress: Option<Vec<u32>>, pushed: bool) -> RolledYze {
+        RolledYze {
+            base: vec![6, 1, 3],
+            skill: vec![],
+            gear: vec![2],
+            stress,
+            pushed,
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn no_roll_returns_none() {
+        with_db(|db| async move {
+            let roll = db
+                .get_last_yze_roll("myuser", "myroom")
+                .await
+                .expect("Could not get roll");
+
+            assert_eq!(None, roll);
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn set_and_get_roll() {
+        with_db(|db| async move {
+            let roll = rolled(Some(vec![1, 4]), false);
+            db.set_last_yze_roll("myuser", "myroom", &roll)
+                .await
+                .expect("Could not set roll");
+
+            let stored = db
+                .get_last_yze_roll("myuser", "myroom")
+                .await
+                .expect("Could not get roll");
+
+            assert_eq!(Some(roll), stored);
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn set_roll_replaces_previous_roll() {
+        with_db(|db| async move {
+            db.set_last_yze_roll("myuser", "myroom", &rolled(Some(vec![]), false))
+                .await
+                .expect("Could not set roll");
+
+            let pushed = rolled(None, true);
+            db.set_last_yze_roll("myuser", "myroom", &pushed)
+                .await
+                .expect("Could not set roll");
+
+            let stored = db
+                .get_last_yze_roll("myuser", "myroom")
+                .await
+                .expect("Could not get roll");
+
+            assert_eq!(Some(pushed), stored);
+        })
+        .await;
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn rolls_are_per_room() {
+        with_db(|db| async move {
+            db.set_last_yze_roll("myuser", "myroom", &rolled(None, false))
+                .await
+                .expect("Could not set roll");
+
+            let other_room = db
+                .get_last_yze_roll("myuser", "otherroom")
+                .await
+                .expect("Could not get roll");
+
+            assert_eq!(None, other_room);
+        })
+        .await;
+    }
+}
@@ -0,0 +1,88 @@
+//! Die rolling building blocks shared by the game systems. Each
+//! system rolls through a DieRoller, so tests can substitute a
+//! sequence of known results for the random number generator.
+
+pub(crate) trait DieRoller {
+    fn roll_number(&mut self, sides: u32) -> u32;
+}
+
+///A version of DieRoller that uses a rand::Rng to roll numbers.
+pub(crate) struct RngDieRoller<R: rand::Rng>(pub R);
+
+impl<R: rand::Rng> DieRoller for RngDieRoller<R> {
+    fn roll_number(&mut self, sides: u32) -> u32 {
+        self.0.gen_range(1..=sides)
+    }
+}
+
+///Roll a die that "explodes" on a given number or higher. Dice will keep
+///being rolled until the result is lower than the explode number.
+///Statistically speaking, usually one result will be returned from this function.
+pub(crate) fn roll_exploding_die<R: DieRoller>(
+    roller: &mut R,
+    sides: u32,
+    explode_on_or_higher: u32,
+) -> Vec<u32> {
+    let mut results = vec![];
+    loop {
+        let roll = roller.roll_number(sides);
+        results.push(roll);
+        if roll < explode_on_or_higher {
+            break;
+        }
+    }
+    results
+}
+
+///Instead of being random, generate a series of numbers we have complete
+///control over.
+#[cfg(test)]
+pub(crate) struct SequentialDieRoller {
+    results: Vec<u32>,
+    position: usize,
+}
+
+#[cfg(test)]
+impl SequentialDieRoller {
+    pub fn new(results: Vec<u32>) -> SequentialDieRoller {
+        SequentialDieRoller {
+            results,
+            position: 0,
+        }
+    }
+}
+
+#[cfg(test)]
+impl DieRoller for SequentialDieRoller {
+    fn roll_number(&mut self, _sides: u32) -> u32 {
+        let roll = self.results[self.position];
+        self.position += 1;
+        roll
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explodes_on_max_test() {
+        let mut roller = SequentialDieRoller::new(vec![10, 10, 3, 1]);
+        let rolls = roll_exploding_die(&mut roller, 10, 10);
+        assert_eq!(vec![10, 10, 3], rolls);
+    }
+
+    #[test]
+    fn explodes_on_or_higher_test() {
+        let mut roller = SequentialDieRoller::new(vec![9, 8, 7, 10]);
+        let rolls = roll_exploding_die(&mut roller, 10, 8);
+        assert_eq!(vec![9, 8, 7], rolls);
+    }
+
+    #[test]
+    fn stops_below_explode_number_test() {
+        let mut roller = SequentialDieRoller::new(vec![5, 6]);
+        let rolls = roll_exploding_die(&mut roller, 6, 6);
+        assert_eq!(vec![5], rolls);
+    }
+}
@@ -3,6 +3,8 @@ use std::net::AddrParseError;
 use crate::commands::CommandError;
 use crate::config::ConfigError;
 use crate::db::errors::DataError;
+use crate::models::Role;
+use crate::suggestions::did_you_mean;
 use thiserror::Error;
 use tonic::metadata::errors::InvalidMetadataValue;
 
@@ -36,8 +38,9 @@ pub enum BotError {
     #[error("error in matrix state store: {0}")]
     MatrixStateStoreError(#[from] matrix_sdk::StoreError),
 
+    //Boxed, so that results that can fail with a bot error stay small.
     #[error("uncategorized matrix SDK error: {0}")]
-    MatrixError(#[from] matrix_sdk::Error),
+    MatrixError(Box<matrix_sdk::Error>),
 
     #[error("future canceled")]
     FutureCanceledError,
@@ -94,6 +97,69 @@ pub enum BotError {
     #[error("room name or id does not exist")]
     RoomDoesNotExist,
 
+    #[error("no bennies left to spend")]
+    NoBenniesLeft,
+
+    #[error("too many bennies to keep track of")]
+    TooManyBennies,
+
+    #[error("no previous roll to push")]
+    NoRollToPush,
+
+    #[error("the last roll has already been pushed")]
+    RollAlreadyPushed,
+
+    #[error("not enough points in {0} pool to pay for effort")]
+    PoolTooLow(String),
+
+    #[error("unknown game system: {0}")]
+    UnknownGameSystem(String),
+
+    #[error("this command requires the room's game system to be {0}")]
+    WrongGameSystem(String),
+
+    #[error("arcanum is too low to cast a spell of that level")]
+    ArcanumTooLow,
+
+    #[error("cannot spend more than {0} {1} at once")]
+    SpendLimitExceeded(i32, String),
+
+    #[error("not enough {0} to spend")]
+    NotEnoughResource(String),
+
+    #[error("template error: {0}")]
+    TemplateError(#[from] crate::templating::TemplateError),
+
+    #[error("!{0} is already a command")]
+    MacroNameTaken(String),
+
+    #[error("macros are nested too deeply in !{0}")]
+    MacroTooDeep(String),
+
+    #[error("!{0} needs the {1} role or higher in this room")]
+    RoleTooLow(String, Role),
+
+    #[error("the role needed for !{0} cannot be changed")]
+    RoleNotChangeable(String),
+
+    #[error("cannot copy variables from a room to itself")]
+    CopyToSameRoom,
+
+    #[error("this room did not replace another room")]
+    NoPredecessorRoom,
+
+    #[error("reply to a file message to import it")]
+    NotAFileMessage,
+
+    #[error("files must state their size and be at most {0} KiB")]
+    FileTooLarge(u64),
+
+    #[error("import error: {0}")]
+    ImportError(#[from] crate::import_export::ImportError),
+
+    #[error("no character sheet template for game system: {0}")]
+    NoSheetTemplate(String),
+
     #[error("tonic transport error: {0}")]
     TonicTransportError(#[from] tonic::transport::Error),
 
@@ -104,10 +170,25 @@ pub enum BotError {
     TonicInvalidMetadata(#[from] InvalidMetadataValue),
 }
 
+impl From<matrix_sdk::Error> for BotError {
+    fn from(error: matrix_sdk::Error) -> Self {
+        BotError::MatrixError(Box::new(error))
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum DiceRollingError {
-    #[error("variable not found: {0}")]
-    VariableNotFound(String),
+    #[error("variable not found: {0}{}", did_you_mean("", .1))]
+    VariableNotFound(String, Option<String>),
+
+    #[error("variable is not a number: {0}")]
+    VariableNotNumeric(String),
+
+    #[error("variables refer to each other in a cycle: {0}")]
+    VariableCycle(String),
+
+    #[error("invalid formula in variable {0}: {1}")]
+    InvalidFormula(String, crate::parser::formula::FormulaParsingError),
 
     #[error("invalid amount")]
     InvalidAmount,
@@ -0,0 +1,425 @@
+use crate::dice::{DieRoller, RngDieRoller};
+use itertools::Itertools;
+use std::fmt;
+use strum::{EnumIter, IntoEnumIterator};
+
+/// A symbol that can appear on the face of a narrative die.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Symbol {
+    Success,
+    Advantage,
+    Triumph,
+    Failure,
+    Threat,
+    Despair,
+    LightSide,
+    DarkSide,
+}
+
+use Symbol::*;
+
+const BOOST_FACES: [&[Symbol]; 6] = [
+    &[],
+    &[],
+    &[Success],
+    &[Success, Advantage],
+    &[Advantage, Advantage],
+    &[Advantage],
+];
+
+const SETBACK_FACES: [&[Symbol]; 6] = [&[], &[], &[Failure], &[Failure], &[Threat], &[Threat]];
+
+const ABILITY_FACES: [&[Symbol]; 8] = [
+    &[],
+    &[Success],
+    &[Success],
+    &[Success, Success],
+    &[Advantage],
+    &[Advantage],
+    &[Success, Advantage],
+    &[Advantage, Advantage],
+];
+
+const DIFFICULTY_FACES: [&[Symbol]; 8] = [
+    &[],
+    &[Failure],
+    &[Failure, Failure],
+    &[Threat],
+    &[Threat],
+    &[Threat],
+    &[Threat, Threat],
+    &[Failure, Threat],
+];
+
+const PROFICIENCY_FACES: [&[Symbol]; 12] = [
+    &[],
+    &[Success],
+    &[Success],
+    &[Success, Success],
+    &[Success, Success],
+    &[Advantage],
+    &[Success, Advantage],
+    &[Success, Advantage],
+    &[Success, Advantage],
+    &[Advantage, Advantage],
+    &[Advantage, Advantage],
+    &[Triumph],
+];
+
+const CHALLENGE_FACES: [&[Symbol]; 12] = [
+    &[],
+    &[Failure],
+    &[Failure],
+    &[Failure, Failure],
+    &[Failure, Failure],
+    &[Threat],
+    &[Threat],
+    &[Failure, Threat],
+    &[Failure, Threat],
+    &[Threat, Threat],
+    &[Threat, Threat],
+    &[Despair],
+];
+
+const FORCE_FACES: [&[Symbol]; 12] = [
+    &[DarkSide],
+    &[DarkSide],
+    &[DarkSide],
+    &[DarkSide],
+    &[DarkSide],
+    &[DarkSide],
+    &[DarkSide, DarkSide],
+    &[LightSide],
+    &[LightSide],
+    &[LightSide, LightSide],
+    &[LightSide, LightSide],
+    &[LightSide, LightSide],
+];
+
+/// The types of narrative dice.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, EnumIter)]
+pub enum NarrativeDie {
+    Ability,
+    Proficiency,
+    Difficulty,
+    Challenge,
+    Boost,
+    Setback,
+    Force,
+}
+
+impl NarrativeDie {
+    /// The faces of the die. The number of faces is also the number
+    /// of sides.
+    pub fn faces(&self) -> &'static [&'static [Symbol]] {
+        match self {
+            Self::Ability => &ABILITY_FACES,
+            Self::Proficiency => &PROFICIENCY_FACES,
+            Self::Difficulty => &DIFFICULTY_FACES,
+            Self::Challenge => &CHALLENGE_FACES,
+            Self::Boost => &BOOST_FACES,
+            Self::Setback => &SETBACK_FACES,
+            Self::Force => &FORCE_FACES,
+        }
+    }
+
+    /// Single letter abbreviation, from the colour of the die.
+    pub fn letter(&self) -> char {
+        match self {
+            Self::Ability => 'g',
+            Self::Proficiency => 'y',
+            Self::Difficulty => 'p',
+            Self::Challenge => 'r',
+            Self::Boost => 'b',
+            Self::Setback => 'k',
+            Self::Force => 'w',
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<NarrativeDie> {
+        NarrativeDie::iter().find(|die| die.letter() == letter)
+    }
+
+    pub fn from_name(name: &str) -> Option<NarrativeDie> {
+        let name = name.strip_suffix('s').unwrap_or(name);
+        NarrativeDie::iter().find(|die| die.name() == name)
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Ability => "ability",
+            Self::Proficiency => "proficiency",
+            Self::Difficulty => "difficulty",
+            Self::Challenge => "challenge",
+            Self::Boost => "boost",
+            Self::Setback => "setback",
+            Self::Force => "force",
+        }
+    }
+}
+
+/// A pool of narrative dice: how many of each die to roll.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct NarrativePool {
+    pub dice: Vec<(NarrativeDie, u32)>,
+}
+
+impl NarrativePool {
+    /// Add dice to the pool, merging with dice of the same type
+    /// already in it.
+    pub fn add(&mut self, die: NarrativeDie, amount: u32) {
+        match self.dice.iter_mut().find(|(existing, _)| *existing == die) {
+            Some((_, existing_amount)) => *existing_amount += amount,
+            None => self.dice.push((die, amount)),
+        }
+    }
+
+    pub fn total_dice(&self) -> u32 {
+        self.dice.iter().map(|(_, amount)| amount).sum()
+    }
+}
+
+impl fmt::Display for NarrativePool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dice = self
+            .dice
+            .iter()
+            .map(|(die, amount)| format!("{} {}", amount, die.name()))
+            .join(", ");
+
+        write!(f, "{}", dice)
+    }
+}
+
+/// The symbols rolled on a pool of narrative dice, before and after
+/// cancelling. Triumphs count as a success and despairs count as a
+/// failure, but triumphs and despairs themselves never cancel out.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct NarrativeResult {
+    pub successes: u32,
+    pub failures: u32,
+    pub advantages: u32,
+    pub threats: u32,
+    pub triumphs: u32,
+    pub despairs: u32,
+    pub light_side: u32,
+    pub dark_side: u32,
+}
+
+impl NarrativeResult {
+    fn add(&mut self, symbol: Symbol) {
+        match symbol {
+            Success => self.successes += 1,
+            Advantage => self.advantages += 1,
+            Triumph => self.triumphs += 1,
+            Failure => self.failures += 1,
+            Threat => self.threats += 1,
+            Despair => self.despairs += 1,
+            LightSide => self.light_side += 1,
+            DarkSide => self.dark_side += 1,
+        }
+    }
+
+    /// Successes minus failures, after cancelling. Negative numbers
+    /// are net failures.
+    pub fn net_successes(&self) -> i32 {
+        (self.successes + self.triumphs) as i32 - (self.failures + self.despairs) as i32
+    }
+
+    /// Advantages minus threats, after cancelling. Negative numbers
+    /// are net threats.
+    pub fn net_advantages(&self) -> i32 {
+        self.advantages as i32 - self.threats as i32
+    }
+
+    /// A check succeeds with at least one net success.
+    pub fn is_success(&self) -> bool {
+        self.net_successes() > 0
+    }
+}
+
+fn fmt_count(count: u32, singular: &str, plural: &str) -> Option<String> {
+    match count {
+        0 => None,
+        1 => Some(format!("1 {}", singular)),
+        _ => Some(format!("{} {}", count, plural)),
+    }
+}
+
+impl fmt::Display for NarrativeResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let net_successes = self.net_successes();
+        let net_advantages = self.net_advantages();
+
+        let (success_count, success_name) = if net_successes > 0 {
+            (net_successes as u32, ("success", "successes"))
+        } else {
+            (net_successes.unsigned_abs(), ("failure", "failures"))
+        };
+
+        let (advantage_count, advantage_name) = if net_advantages >= 0 {
+            (net_advantages as u32, ("advantage", "advantages"))
+        } else {
+            (net_advantages.unsigned_abs(), ("threat", "threats"))
+        };
+
+        let symbols = vec![
+            fmt_count(success_count, success_name.0, success_name.1),
+            fmt_count(advantage_count, advantage_name.0, advantage_name.1),
+            fmt_count(self.triumphs, "triumph", "triumphs"),
+            fmt_count(self.despairs, "despair", "despairs"),
+            fmt_count(self.light_side, "light side point", "light side points"),
+            fmt_count(self.dark_side, "dark side point", "dark side points"),
+        ]
+        .into_iter()
+        .flatten()
+        .join(", ");
+
+        let outcome = if self.is_success() {
+            "success!"
+        } else {
+            "failure!"
+        };
+
+        if symbols.is_empty() {
+            write!(f, "{}", outcome)
+        } else {
+            write!(f, "{} ({})", outcome, symbols)
+        }
+    }
+}
+
+fn roll_narrative_dice<R: DieRoller>(pool: &NarrativePool, roller: &mut R) -> NarrativeResult {
+    let mut result = NarrativeResult::default();
+
+    for (die, amount) in &pool.dice {
+        let faces = die.faces();
+        for _ in 0..*amount {
+            let face = roller.roll_number(faces.len() as u32) as usize;
+            for symbol in faces[face - 1] {
+                result.add(*symbol);
+            }
+        }
+    }
+
+    result
+}
+
+/// Roll a pool of narrative dice, tallying up the symbols rolled.
+pub fn roll_pool(pool: &NarrativePool) -> NarrativeResult {
+    let mut roller = RngDieRoller(rand::thread_rng());
+    roll_narrative_dice(pool, &mut roller)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::SequentialDieRoller;
+
+    fn pool(dice: &[(NarrativeDie, u32)]) -> NarrativePool {
+        NarrativePool {
+            dice: dice.to_vec(),
+        }
+    }
+
+    #[test]
+    fn faces_match_die_sizes() {
+        assert_eq!(6, NarrativeDie::Boost.faces().len());
+        assert_eq!(6, NarrativeDie::Setback.faces().len());
+        assert_eq!(8, NarrativeDie::Ability.faces().len());
+        assert_eq!(8, NarrativeDie::Difficulty.faces().len());
+        assert_eq!(12, NarrativeDie::Proficiency.faces().len());
+        assert_eq!(12, NarrativeDie::Challenge.faces().len());
+        assert_eq!(12, NarrativeDie::Force.faces().len());
+    }
+
+    #[test]
+    fn symbols_cancel_out() {
+        //Ability: success + advantage. Difficulty: failure + threat.
+        let pool = pool(&[(NarrativeDie::Ability, 1), (NarrativeDie::Difficulty, 1)]);
+        let mut roller = SequentialDieRoller::new(vec![7, 8]);
+        let result = roll_narrative_dice(&pool, &mut roller);
+        assert_eq!(0, result.net_successes());
+        assert_eq!(0, result.net_advantages());
+        assert!(!result.is_success());
+    }
+
+    #[test]
+    fn triumph_counts_as_success() {
+        let pool = pool(&[(NarrativeDie::Proficiency, 1), (NarrativeDie::Setback, 1)]);
+        let mut roller = SequentialDieRoller::new(vec![12, 3]);
+        let result = roll_narrative_dice(&pool, &mut roller);
+        assert_eq!(1, result.triumphs);
+        assert_eq!(0, result.net_successes());
+        assert!(!result.is_success());
+    }
+
+    #[test]
+    fn despair_does_not_cancel_triumph() {
+        let pool = pool(&[(NarrativeDie::Proficiency, 2), (NarrativeDie::Challenge, 1)]);
+        let mut roller = SequentialDieRoller::new(vec![12, 4, 12]);
+        let result = roll_narrative_dice(&pool, &mut roller);
+        assert_eq!(1, result.triumphs);
+        assert_eq!(1, result.despairs);
+        assert_eq!(2, result.net_successes());
+        assert!(result.is_success());
+    }
+
+    #[test]
+    fn force_dice_count_light_and_dark() {
+        let pool = pool(&[(NarrativeDie::Force, 3)]);
+        let mut roller = SequentialDieRoller::new(vec![1, 7, 12]);
+        let result = roll_narrative_dice(&pool, &mut roller);
+        assert_eq!(3, result.dark_side);
+        assert_eq!(2, result.light_side);
+    }
+
+    #[test]
+    fn formats_net_result() {
+        let result = NarrativeResult {
+            successes: 3,
+            failures: 1,
+            advantages: 1,
+            threats: 2,
+            triumphs: 1,
+            ..Default::default()
+        };
+
+        assert_eq!(
+            "success! (3 successes, 1 threat, 1 triumph)",
+            result.to_string()
+        );
+
+        assert_eq!("failure!", NarrativeResult::default().to_string());
+    }
+
+    #[test]
+    fn formats_pool() {
+        let pool = pool(&[(NarrativeDie::Ability, 2), (NarrativeDie::Setback, 1)]);
+        assert_eq!("2 ability, 1 setback", pool.to_string());
+    }
+
+    #[test]
+    fn pool_merges_same_dice() {
+        let mut pool = NarrativePool::default();
+        pool.add(NarrativeDie::Ability, 1);
+        pool.add(NarrativeDie::Boost, 1);
+        pool.add(NarrativeDie::Ability, 2);
+        assert_eq!(
+            vec![(NarrativeDie::Ability, 3), (NarrativeDie::Boost, 1)],
+            pool.dice
+        );
+        assert_eq!(4, pool.total_dice());
+    }
+
+    #[test]
+    fn die_names_and_letters() {
+        assert_eq!(Some(NarrativeDie::Setback), NarrativeDie::from_letter('k'));
+        assert_eq!(
+            Some(NarrativeDie::Ability),
+            NarrativeDie::from_name("ability")
+        );
+        assert_eq!(Some(NarrativeDie::Boost), NarrativeDie::from_name("boosts"));
+        assert_eq!(None, NarrativeDie::from_name("awesome"));
+    }
+}
@@ -0,0 +1,129 @@
+use super::dice::{NarrativeDie, NarrativePool};
+use crate::parser::dice::DiceParsingError;
+use combine::parser::char::{digit, letter, spaces};
+use combine::{many1, optional, Parser};
+
+/// Upper limit on the total amount of dice in a single pool.
+const MAX_DICE: u32 = 100;
+
+/// Parse a pool of narrative dice. Dice can be given by the letter of
+/// their colour (e.g. "2g1y2p1k"), or by name (e.g. "2 ability 1
+/// difficulty"). A run of letters without a number counts each letter
+/// as one die, so "ggp" is two ability dice and a difficulty die.
+///
+/// Letters: g = ability, y = proficiency, p = difficulty, r =
+/// challenge, b = boost, k = setback, w = force.
+pub fn parse_narrative_pool(input: &str) -> Result<NarrativePool, DiceParsingError> {
+    let input = input.trim().to_lowercase();
+
+    let element = optional(many1(digit()))
+        .skip(spaces().silent())
+        .and(many1(letter()))
+        .skip(spaces().silent());
+
+    let mut parser = many1(element).map(|elements: Vec<(Option<String>, String)>| elements);
+    let (elements, rest) = parser.parse(input.as_str())?;
+
+    if !rest.is_empty() {
+        return Err(DiceParsingError::UnconsumedInput);
+    }
+
+    let mut pool = NarrativePool::default();
+    for (amount, dice) in elements {
+        let amount = match amount {
+            Some(amount) => Some(amount.parse::<u32>()?),
+            None => None,
+        };
+
+        if let Some(die) = NarrativeDie::from_name(&dice) {
+            pool.add(die, amount.unwrap_or(1));
+        } else if amount.is_some() && dice.chars().count() > 1 {
+            // A number must be followed by a single letter or a name.
+            return Err(DiceParsingError::WrongElementType);
+        } else {
+            for letter in dice.chars() {
+                let die =
+                    NarrativeDie::from_letter(letter).ok_or(DiceParsingError::WrongElementType)?;
+                pool.add(die, amount.unwrap_or(1));
+            }
+        }
+    }
+
+    let total = pool.total_dice();
+    if total == 0 || total > MAX_DICE {
+        return Err(DiceParsingError::InvalidAmount);
+    }
+
+    Ok(pool)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use NarrativeDie::*;
+
+    fn pool(dice: &[(NarrativeDie, u32)]) -> NarrativePool {
+        NarrativePool {
+            dice: dice.to_vec(),
+        }
+    }
+
+    #[test]
+    fn parses_letters() {
+        assert_eq!(
+            parse_narrative_pool("2g1y2p1k"),
+            Ok(pool(&[
+                (Ability, 2),
+                (Proficiency, 1),
+                (Difficulty, 2),
+                (Setback, 1)
+            ]))
+        );
+    }
+
+    #[test]
+    fn parses_letters_without_numbers() {
+        assert_eq!(
+            parse_narrative_pool("ggp b"),
+            Ok(pool(&[(Ability, 2), (Difficulty, 1), (Boost, 1)]))
+        );
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!(
+            parse_narrative_pool("2 ability 1 Proficiency 3 difficulty force"),
+            Ok(pool(&[
+                (Ability, 2),
+                (Proficiency, 1),
+                (Difficulty, 3),
+                (Force, 1)
+            ]))
+        );
+
+        assert_eq!(
+            parse_narrative_pool("2 boosts 1 challenge"),
+            Ok(pool(&[(Boost, 2), (Challenge, 1)]))
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_dice() {
+        assert_eq!(
+            parse_narrative_pool("2x"),
+            Err(DiceParsingError::WrongElementType)
+        );
+        assert_eq!(
+            parse_narrative_pool("2 awesome"),
+            Err(DiceParsingError::WrongElementType)
+        );
+    }
+
+    #[test]
+    fn rejects_bad_amounts() {
+        assert!(parse_narrative_pool("").is_err());
+        assert!(parse_narrative_pool("0g").is_err());
+        assert!(parse_narrative_pool("101g").is_err());
+        assert!(parse_narrative_pool("2g 3").is_err());
+    }
+}
@@ -0,0 +1,292 @@
+use crate::context::Context;
+use crate::dice::{DieRoller, RngDieRoller};
+use crate::error::{BotError, DiceRollingError};
+use crate::parser::dice::Amount;
+use itertools::Itertools;
+use std::fmt;
+
+/// Every GURPS success roll is 3d6.
+const DICE: u32 = 3;
+const DIE_SIDES: u32 = 6;
+
+/// A success roll against one skill, or a quick contest between two
+/// skills. Skills are expressions, so they can be made of variables
+/// and modifiers (e.g. "broadsword - 2").
+#[derive(Debug, PartialEq)]
+pub struct GurpsCheck {
+    pub(crate) skill: Vec<Amount>,
+    pub(crate) opponent: Option<Vec<Amount>>,
+}
+
+/// Attach a Context to a GURPS check. Needed for database access.
+pub struct GurpsCheckWithContext<'a>(pub &'a GurpsCheck, pub &'a Context<'a>);
+
+/// Outcome of a success roll.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CheckResult {
+    CriticalSuccess,
+    Success,
+    Failure,
+    CriticalFailure,
+}
+
+impl CheckResult {
+    pub fn is_success(&self) -> bool {
+        matches!(self, Self::CriticalSuccess | Self::Success)
+    }
+}
+
+impl fmt::Display for CheckResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CriticalSuccess => write!(f, "critical success"),
+            Self::Success => write!(f, "success"),
+            Self::Failure => write!(f, "failure"),
+            Self::CriticalFailure => write!(f, "critical failure"),
+        }
+    }
+}
+
+/// A success roll made against an effective skill level.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RolledCheck {
+    pub(crate) skill: i32,
+    pub(crate) rolls: Vec<u32>,
+}
+
+impl RolledCheck {
+    pub fn total(&self) -> i32 {
+        self.rolls.iter().sum::<u32>() as i32
+    }
+
+    /// Effective skill minus the roll. Positive (or zero) for a margin
+    /// of success, negative for a margin of failure.
+    pub fn margin(&self) -> i32 {
+        self.skill - self.total()
+    }
+
+    /// 3 and 4 are always critical successes, 5 is one at skill 15+,
+    /// and 6 is one at skill 16+. 18 is always a critical failure, as
+    /// is 17 at skill 15 or less, and any roll 10 or more over the
+    /// skill. Otherwise 17 and 18 always fail.
+    pub fn result(&self) -> CheckResult {
+        let roll = self.total();
+        let skill = self.skill;
+
+        if roll <= 4 || (roll == 5 && skill >= 15) || (roll == 6 && skill >= 16) {
+            CheckResult::CriticalSuccess
+        } else if roll == 18 || (roll == 17 && skill <= 15) || roll - skill >= 10 {
+            CheckResult::CriticalFailure
+        } else if roll >= 17 || roll > skill {
+            CheckResult::Failure
+        } else {
+            CheckResult::Success
+        }
+    }
+}
+
+impl fmt::Display for RolledCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} ({}) against {}: {} by {}!",
+            self.total(),
+            self.rolls.iter().join(", "),
+            self.skill,
+            self.result(),
+            self.margin().abs()
+        )
+    }
+}
+
+/// Outcome of a quick contest. The winner is whoever succeeded by
+/// more (or failed by less, when nobody succeeded).
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ContestResult {
+    /// The first skill won, by the given margin of victory.
+    FirstWins(i32),
+
+    /// The second skill won, by the given margin of victory.
+    SecondWins(i32),
+
+    /// Nobody won: both sides had the same margin.
+    Tie,
+}
+
+/// In a quick contest, each side makes a success roll. If one side
+/// succeeds and the other fails, the successful side wins. Otherwise
+/// the side that succeeded by more, or failed by less, wins. Equal
+/// margins are a tie.
+pub fn quick_contest(first: &RolledCheck, second: &RolledCheck) -> ContestResult {
+    let first_succeeded = first.result().is_success();
+    let second_succeeded = second.result().is_success();
+    let difference = first.margin() - second.margin();
+
+    match (first_succeeded, second_succeeded) {
+        (true, false) => ContestResult::FirstWins(std::cmp::max(difference, 1)),
+        (false, true) => ContestResult::SecondWins(std::cmp::max(-difference, 1)),
+        _ if difference > 0 => ContestResult::FirstWins(difference),
+        _ if difference < 0 => ContestResult::SecondWins(-difference),
+        _ => ContestResult::Tie,
+    }
+}
+
+impl fmt::Display for ContestResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FirstWins(margin) => write!(f, "first skill wins by {}!", margin),
+            Self::SecondWins(margin) => write!(f, "second skill wins by {}!", margin),
+            Self::Tie => write!(f, "tie, nobody wins!"),
+        }
+    }
+}
+
+/// Result of a GURPS check: either a single success roll, or both
+/// rolls of a quick contest.
+pub enum RolledGurpsCheck {
+    Single(RolledCheck),
+    Contest(RolledCheck, RolledCheck),
+}
+
+impl fmt::Display for RolledGurpsCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Single(check) => write!(f, "{}", check),
+            Self::Contest(first, second) => {
+                write!(f, "{}; {}; {}", first, second, quick_contest(first, second))
+            }
+        }
+    }
+}
+
+fn roll_check<R: DieRoller>(skill: i32, roller: &mut R) -> RolledCheck {
+    let rolls = (0..DICE).map(|_| roller.roll_number(DIE_SIDES)).collect();
+    RolledCheck { skill, rolls }
+}
+
+async fn resolve_skill(amounts: &[Amount], ctx: &Context<'_>) -> Result<i32, BotError> {
+    if amounts.len() > 100 {
+        return Err(DiceRollingError::ExpressionTooLarge.into());
+    }
+
+    crate::logic::calculate_dice_amount(amounts, ctx).await
+}
+
+///Roll a GURPS check, resolving any variables in the skill
+///expressions first.
+pub async fn roll_gurps_check(
+    check: &GurpsCheckWithContext<'_>,
+) -> Result<RolledGurpsCheck, BotError> {
+    let skill = resolve_skill(&check.0.skill, check.1).await?;
+    let opponent = match &check.0.opponent {
+        Some(opponent) => Some(resolve_skill(opponent, check.1).await?),
+        None => None,
+    };
+
+    let mut roller = RngDieRoller(rand::thread_rng());
+    let first = roll_check(skill, &mut roller);
+
+    Ok(match opponent {
+        Some(opponent) => RolledGurpsCheck::Contest(first, roll_check(opponent, &mut roller)),
+        None => RolledGurpsCheck::Single(first),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::SequentialDieRoller;
+
+    fn check(skill: i32, total: u32) -> RolledCheck {
+        //Spread the total over three dice; only the total matters.
+        let first = std::cmp::min(total - 2, 6);
+        let second = std::cmp::min(total - first - 1, 6);
+        let third = total - first - second;
+        RolledCheck {
+            skill,
+            rolls: vec![first, second, third],
+        }
+    }
+
+    #[test]
+    fn rolls_three_dice() {
+        let mut roller = SequentialDieRoller::new(vec![2, 5, 4]);
+        let rolled = roll_check(12, &mut roller);
+        assert_eq!(11, rolled.total());
+        assert_eq!(1, rolled.margin());
+        assert_eq!(CheckResult::Success, rolled.result());
+    }
+
+    #[test]
+    fn check_helper_spreads_total() {
+        for total in 3..=18 {
+            assert_eq!(total as i32, check(10, total).total());
+        }
+    }
+
+    #[test]
+    fn three_and_four_always_critically_succeed() {
+        assert_eq!(CheckResult::CriticalSuccess, check(3, 3).result());
+        assert_eq!(CheckResult::CriticalSuccess, check(1, 4).result());
+    }
+
+    #[test]
+    fn five_and_six_critical_depends_on_skill() {
+        assert_eq!(CheckResult::Success, check(14, 5).result());
+        assert_eq!(CheckResult::CriticalSuccess, check(15, 5).result());
+        assert_eq!(CheckResult::Success, check(15, 6).result());
+        assert_eq!(CheckResult::CriticalSuccess, check(16, 6).result());
+    }
+
+    #[test]
+    fn seventeen_and_eighteen_always_fail() {
+        assert_eq!(CheckResult::CriticalFailure, check(15, 17).result());
+        assert_eq!(CheckResult::Failure, check(16, 17).result());
+        assert_eq!(CheckResult::Failure, check(20, 17).result());
+        assert_eq!(CheckResult::CriticalFailure, check(20, 18).result());
+    }
+
+    #[test]
+    fn ten_over_skill_is_critical_failure() {
+        assert_eq!(CheckResult::Failure, check(5, 14).result());
+        assert_eq!(CheckResult::CriticalFailure, check(5, 15).result());
+    }
+
+    #[test]
+    fn margin_of_success_and_failure() {
+        assert_eq!(3, check(12, 9).margin());
+        assert_eq!(-2, check(12, 14).margin());
+        assert_eq!(CheckResult::Failure, check(12, 14).result());
+    }
+
+    #[test]
+    fn quick_contest_success_beats_failure() {
+        let result = quick_contest(&check(12, 12), &check(14, 15));
+        assert_eq!(ContestResult::FirstWins(1), result);
+    }
+
+    #[test]
+    fn quick_contest_compares_margins() {
+        let result = quick_contest(&check(12, 10), &check(14, 8));
+        assert_eq!(ContestResult::SecondWins(4), result);
+
+        let result = quick_contest(&check(12, 10), &check(14, 12));
+        assert_eq!(ContestResult::Tie, result);
+    }
+
+    #[test]
+    fn quick_contest_both_failing_compares_margins() {
+        let result = quick_contest(&check(10, 12), &check(10, 15));
+        assert_eq!(ContestResult::FirstWins(3), result);
+    }
+
+    #[test]
+    fn formats_check() {
+        let rolled = RolledCheck {
+            skill: 12,
+            rolls: vec![3, 4, 2],
+        };
+
+        assert_eq!("9 (3, 4, 2) against 12: success by 3!", rolled.to_string());
+    }
+}
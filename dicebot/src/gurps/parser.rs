@@ -0,0 +1,83 @@
+use super::dice::GurpsCheck;
+use crate::parser::dice::{parse_amounts, Amount, DiceParsingError};
+
+/// Separates the two skills of a quick contest.
+const CONTEST_SEPARATOR: &str = " vs ";
+
+fn parse_skill(input: &str) -> Result<Vec<Amount>, DiceParsingError> {
+    let (amounts, rest) = parse_amounts(input.trim())?;
+    if !rest.trim().is_empty() {
+        return Err(DiceParsingError::UnconsumedInput);
+    }
+
+    Ok(amounts)
+}
+
+/// Parse a GURPS check: an effective skill expression, optionally
+/// followed by "vs" and the opposing skill for a quick contest.
+pub fn parse_gurps_check(input: &str) -> Result<GurpsCheck, DiceParsingError> {
+    let (skill, opponent) = match input.split_once(CONTEST_SEPARATOR) {
+        Some((skill, opponent)) => (skill, Some(opponent)),
+        None => (input, None),
+    };
+
+    Ok(GurpsCheck {
+        skill: parse_skill(skill)?,
+        opponent: opponent.map(parse_skill).transpose()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::dice::{Element, Operator};
+
+    #[test]
+    fn skill_with_modifier_test() {
+        let result = parse_gurps_check("broadsword - 2");
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap(),
+            GurpsCheck {
+                skill: vec![
+                    Amount {
+                        operator: Operator::Plus,
+                        element: Element::Variable("broadsword".to_owned()),
+                    },
+                    Amount {
+                        operator: Operator::Minus,
+                        element: Element::Number(2),
+                    },
+                ],
+                opponent: None,
+            }
+        );
+    }
+
+    #[test]
+    fn quick_contest_test() {
+        let result = parse_gurps_check("12 vs 14");
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap(),
+            GurpsCheck {
+                skill: vec![Amount {
+                    operator: Operator::Plus,
+                    element: Element::Number(12),
+                }],
+                opponent: Some(vec![Amount {
+                    operator: Operator::Plus,
+                    element: Element::Number(14),
+                }]),
+            }
+        );
+    }
+
+    #[test]
+    fn trailing_input_test() {
+        assert_eq!(
+            Err(DiceParsingError::UnconsumedInput),
+            parse_gurps_check("12 14")
+        );
+    }
+}
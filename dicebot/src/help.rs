@@ -2,29 +2,96 @@ use indoc::indoc;
 
 pub fn parse_help_topic(input: &str) -> Option<HelpTopic> {
     match input {
+        "char" | "characters" => Some(HelpTopic::Characters),
         "cofd" => Some(HelpTopic::ChroniclesOfDarkness),
         "dicepool" => Some(HelpTopic::DicePool),
         "dice" => Some(HelpTopic::RollingDice),
         "cthulhu" => Some(HelpTopic::Cthulhu),
-        "" => Some(HelpTopic::General),
+        "cypher" => Some(HelpTopic::Cypher),
+        "sheet" => Some(HelpTopic::Sheet),
+        "import" | "export" => Some(HelpTopic::ImportExport),
+        "copy" | "migrate" => Some(HelpTopic::CopyVariables),
+        "undo" | "history" => Some(HelpTopic::History),
+        "templates" | "braces" => Some(HelpTopic::Templates),
+        "macro" | "macros" => Some(HelpTopic::Macros),
+        "inline" => Some(HelpTopic::InlineRolls),
+        "prefix" => Some(HelpTopic::Prefix),
+        "permission" | "permissions" | "roles" => Some(HelpTopic::Permissions),
+        "sw" | "savageworlds" => Some(HelpTopic::SavageWorlds),
+        "narr" | "genesys" => Some(HelpTopic::Genesys),
+        "gurps" => Some(HelpTopic::Gurps),
+        "l5r" | "rk" => Some(HelpTopic::L5r),
+        "sr" | "shadowrun" => Some(HelpTopic::Shadowrun),
+        "wfrp" | "warhammer" => Some(HelpTopic::Wfrp),
+        "yze" | "yearzero" => Some(HelpTopic::YearZero),
         _ => None,
     }
 }
 
 pub enum HelpTopic {
+    Characters,
     ChroniclesOfDarkness,
     DicePool,
     Cthulhu,
+    Cypher,
+    SavageWorlds,
+    Sheet,
+    ImportExport,
+    CopyVariables,
+    History,
+    Templates,
+    Macros,
+    InlineRolls,
+    Prefix,
+    Permissions,
+    Genesys,
+    Gurps,
+    L5r,
+    Shadowrun,
+    Wfrp,
+    YearZero,
     RollingDice,
-    General,
 }
 
+const CHARACTERS_HELP: &'static str = indoc! {"
+Managing characters
+
+Command: !char, !character
+
+Syntax: !char [list | clear | new <name> | switch <name> | delete <name>]
+
+While a character is active, variables are read from and stored on
+that character instead of your own variables. Each user has one
+active character per room. Creating a character makes it active.
+
+Examples:
+  !char new Anna (create Anna and switch to her)
+  !char list (list your characters in this room)
+  !char switch Anna (make Anna the active character)
+  !char clear (go back to your own variables)
+  !char delete Anna (delete Anna and all of her variables)
+"};
+
 const COFD_HELP: &'static str = indoc! {"
 Chronicles of Darkness
 
 Commands available:
  !pool, !rp: roll a dice pool
  !chance: roll a chance die
+ !system <name>: set the room's game system (e.g. mage, werewolf)
+
+Splat commands, when the room's game system matches:
+ !cast <arcanum> <level> [reach <num>] [mana <num>] [bonus <num>]:
+   Mage spellcasting with Gnosis + Arcanum, rolling Paradox for
+   Reach over the free Reach
+ !form <hishu|dalu|gauru|urshul|urhan>: change Werewolf form; form
+   modifiers apply to pools using attribute variables
+ !spend <resource> [amount]: spend Mana (Mage), Glamour (Changeling),
+   Pillar points (Mummy: ab, ba, ka, ren, sheut) or Pyros
+   (Promethean), limited by Gnosis, Wyrd, Sekhem or Azoth
+
+Deviant: The Renegades has no splat commands and uses !pool as is.
+Only a GM can change the room's game system.
 
 See also:
  !help dicepool
@@ -53,9 +120,7 @@ Rolling dice pools
 
 Command: !pool, !rp
 
-Syntax: !pool <modifiers>:<expression>
-
-Short syntax: !pool <expression>
+Syntax: !pool <expression> [modifiers]
 
 Expression Syntax: <num|variable> [+/- <expression> ...]
 
@@ -68,11 +133,11 @@ Modifiers:
 
 Examples:
  !pool 8 (roll a regular pool of 8 dice)
- !pool n:5 (roll dice pool of 5, nine-again)
- !pool rs3:6 (roll dice pool of 6, rote quality, 3 successes for exceptional)
+ !pool 5 n (roll dice pool of 5, nine-again)
+ !pool 6 rs3 (roll dice pool of 6, rote quality, 3 successes for exceptional)
  !pool 10 + 3 (roll dice pool of 10 + 3, which is 13)
  !pool myskill - 4 (roll pool of the value of myskill - 4).
- !pool n:myskill - 5 (roll pool of myskill - 5, with nine-again)
+ !pool myskill - 5 n (roll pool of myskill - 5, with nine-again)
 "};
 
 const CTHULHU_HELP: &'static str = indoc! {"
@@ -80,7 +145,7 @@ Rolling Call of Cthlhu dice
 
 Commands: !cthroll (regular rolls), !cthadv (advancement rolls)
 
-Regular roll syntax: !cthroll <modifiers>:<num|variable>
+Regular roll syntax: !cthroll <num|variable> [modifiers]
 
 Advancement roll syntax: !cthadv <num|variable>
 
@@ -93,7 +158,7 @@ Modifiers:
 Examples:
   !cthroll 60 (make a roll against a skill of 60)
   !cthroll spothidden (make a roll against variable spothidden)
-  !cthroll bb:30 (make a roll against skill of 30 with two bonus dice)
+  !cthroll 30 bb (make a roll against skill of 30 with two bonus dice)
   !cthadv 50 (make an advancement roll against a skill of 50)
   !cthadv spothidden (make an advancement roll against the number in spothidden)
 
@@ -101,23 +166,419 @@ Note: If !cthadv is given a variable, and the roll is successful, it will
 update the variable with the new skill.
 "};
 
-const GENERAL_HELP: &'static str = indoc! {"
-General Help
+const CYPHER_HELP: &'static str = indoc! {"
+Rolling Cypher System dice
+
+Command: !cypher
+
+Syntax: !cypher <difficulty> [effort <num>] [asset <num>] [skill trained|specialized] [might|speed|intellect]
+
+Each level of effort, each asset (up to 2) and being trained eases the
+task by one step; being specialized eases it by two. The target number
+is the eased difficulty times 3, and the d20 must meet or beat it. A
+task eased to difficulty 0 succeeds without a roll.
+
+A 1 is a GM intrusion. 17 and 18 add 1 or 2 damage to attacks, 19 is a
+minor effect and 20 is a major effect.
+
+Effort costs 3 points for the first level and 2 for each level after,
+minus your Edge. If you name the stat and have stored its pool in a
+variable (might, speed or intellect), the cost is deducted from it.
+Edge is read from mightedge, speededge or intellectedge.
+
+Examples:
+  !cypher 4 (difficulty 4, target 12)
+  !cypher 5 skill trained asset 1 (eased to difficulty 3)
+  !cypher 6 effort 2 speed (spend Speed on two levels of effort)
+"};
+
+const SAVAGE_WORLDS_HELP: &'static str = indoc! {"
+Rolling Savage Worlds dice
+
+Commands: !sw (trait rolls), !benny (benny tracking)
+
+Trait roll syntax: !sw d<sides> [+/-modifier] [target number]
+
+The trait die and a d6 Wild Die are rolled, both dice ace (explode)
+on their highest number, and the higher total is kept. The default
+target number is 4, and every 4 over the target is a raise. If both
+dice come up 1, the roll is a critical failure.
+
+Benny syntax: !benny [spend | gain <num> | set <num>]
+
+Examples:
+  !sw d8 (roll a d8 trait against target 4)
+  !sw d6 +2 (roll a d6 trait with a +2 modifier)
+  !sw d10 -1 6 (roll a d10 trait with a -1 modifier against target 6)
+  !benny (show your bennies)
+  !benny set 3 (start the session with 3 bennies)
+  !benny spend (spend one benny)
+"};
+
+const IMPORT_EXPORT_HELP: &'static str = indoc! {"
+Importing and exporting variables
+
+Commands: !export, !import
+
+Export syntax: !export [json|toml|csv]
+Import syntax: !import [merge|skip|overwrite] [dry-run] [json|toml|csv]
+
+!export uploads your variables in the room as a file (JSON if no
+format is given). To import, reply to an uploaded file (up to 256
+KiB) with !import. The format is taken from the file name if not
+given.
+
+Conflicts with existing variables:
+ merge: replace variables of the same name, keep the rest (default)
+ skip: keep existing variables, only import new ones
+ overwrite: replace all of your variables with the file
+A dry-run import only shows what would change.
+"};
+
+const COPY_HELP: &'static str = indoc! {"
+Copying variables between rooms
+
+Commands: !variables copy, !variables migrate
+
+Copy syntax: !variables copy <from-room> [to-room]
+
+!variables copy copies your variables and characters from one room
+to another (the active room if not given). Rooms can be given by ID
+or by name. Put names with spaces in double quotes. Variables of the
+same name in the destination room are replaced. Like !room, it only
+works in encrypted direct messages with the bot.
 
-Try these help commands:
-  !help cofd
-  !help dice
-  !help cthulhu
+!variables migrate copies all variables, characters and room
+variables from the room that the current room replaced when it was
+upgraded. It requires power level 50 or higher.
 "};
 
+const HISTORY_HELP: &'static str = indoc! {"
+Variable history and undo
+
+Commands: !undo, !variables history
+
+History syntax: !variables history <name> [user]
+
+Every change to a variable is recorded, with the old and new value,
+who made it, and the command used. !variables history shows the
+latest changes to a variable. Moderators (power level 50 or higher)
+can see the history of other players' variables by giving their
+user ID.
+
+!undo reverts your last change to your variables in the room. Use it
+again to undo earlier changes.
+"};
+
+const TEMPLATES_HELP: &'static str = indoc! {"
+Variables in commands
+
+Dice rolling commands (and !spend) can use your variables by putting
+their names in braces. They are replaced by their values before the
+command runs: numbers and formulas by their current value, and text
+variables by their text. Unknown variable names are an error.
+Commands that store text or take passwords, like !set, !macro and
+!register, never replace variables.
+
+Examples:
+ !cthroll {firearms} bb
+ !roll {weapon_dice}+{str_mod}
+ !pool {room.difficulty}
+
+Use {{ and }} for literal braces.
+"};
+
+const MACRO_HELP: &'static str = indoc! {"
+Macros
+
+Command: !macro
+
+Syntax: !macro add <name> <commands>, !macro del <name>, !macro list
+
+A macro is a name for one or more commands, one per line. Running
+!<name> runs all of its commands. Variables in braces are filled in
+when the macro runs, and text after \" # \" is a comment.
+
+Macros are your own, or shared by the room if the name starts with
+room. (which requires power level 50 or higher). Your own macros are
+used before the room's. Macros can run other macros, up to 5 deep.
+
+Example:
+ !macro add attack !roll 1d20+{atk} # attack
+ !roll 1d8+{str}
+
+Then run it with !attack.
+"};
+
+const INLINE_HELP: &'static str = indoc! {"
+Inline rolls
+
+Command: !inline
+
+Syntax: !inline [on|off], !inline markers <open> <close>
+
+Dice expressions between [[ and ]] anywhere in a normal message are
+rolled, e.g. I swing my axe [[1d12+3]] at the orc. The bot quotes
+the message with the results in place.
+
+Inline rolls are off until a moderator (power level 50 or higher)
+turns them on for the room. Moderators can also change the markers
+(up to 4 characters each). !inline shows the current settings.
+
+Examples:
+ !inline on
+ !inline markers (( ))
+"};
+
+const PREFIX_HELP: &'static str = indoc! {"
+Command prefix
+
+Command: !prefix
+
+Syntax: !prefix [<prefix>|default]
+
+Commands start with ! unless the bot is configured otherwise. A
+moderator (power level 50 or higher) can give the room its own
+prefix of up to 4 characters, which cannot be letters or digits, or
+go back to the bot's prefix with !prefix default. !prefix shows the
+room's prefix.
+
+Commands can also be sent by mentioning the bot, e.g. dicebot: roll
+1d20. In a direct message with the bot, every line is a command.
+
+Examples:
+ !prefix .
+ .roll 1d20
+"};
+
+const PERMISSIONS_HELP: &'static str = indoc! {"
+Command permissions
+
+Command: !permission, !permissions
+
+Syntax: !permission [<command> [player|gm|admin|default]]
+
+Every command needs a role in the room to run it. Your role comes from
+your power level in the room: GM at 50 or higher (moderators), and
+room admin at 100 (admins). Bot admins are listed in the bot's config
+and can run every command in every room.
+
+Players can run every command, but changing the room for everyone
+needs a GM: the game system, inline rolls, the command prefix, and
+room variables and room macros. Changing the roles needs a room admin.
+
+Room admins can change the role a command needs in the room, which
+also replaces the GM role for changing the room. !permission default
+goes back to the command's own roles. !permission shows your role and
+the changed commands.
+
+Examples:
+ !permission system gm (only GMs can run !system)
+ !permission roll (show the role needed for !roll)
+ !permission system default
+"};
+
+const SHEET_HELP: &'static str = indoc! {"
+Character sheets
+
+Command: !sheet
+
+Syntax: !sheet [init]
+
+Uses the sheet template of the room's game system (see !system).
+!sheet init creates every attribute and skill of the sheet that is
+not set yet, with its default value. !sheet shows the whole sheet.
+
+Derived stats are recalculated every time they are read, and can be
+used in rolls like any other variable:
+ Chronicles of Darkness: defense, initiative, speed, willpower, health
+ Call of Cthulhu: hp, mp, sanity, and <name>half and <name>fifth for
+   every characteristic and skill
+ D&D 5e: <ability>mod, proficiency, initiative
+
+Examples:
+  !sheet init (create the sheet)
+  !sheet (show the sheet)
+  !pool defense (roll using a derived stat)
+"};
+
+const GENESYS_HELP: &'static str = indoc! {"
+Rolling Genesys and Star Wars narrative dice
+
+Command: !narr, !genesys
+
+Syntax: !narr <dice>
+
+Dice can be given by the letter of their colour, or by name:
+ g = ability
+ y = proficiency
+ p = difficulty
+ r = challenge
+ b = boost
+ k = setback
+ w = force
+
+Successes and failures cancel each other out, as do advantages and
+threats. Triumphs also count as a success, and despairs as a
+failure, but are always shown.
+
+Examples:
+  !narr 2g1y2p1k (2 ability, 1 proficiency, 2 difficulty, 1 setback)
+  !narr ggpb (2 ability, 1 difficulty, 1 boost)
+  !narr 3 ability 2 difficulty (dice by name)
+  !narr 1w (roll a force die)
+"};
+
+const GURPS_HELP: &'static str = indoc! {"
+Rolling GURPS dice
+
+Command: !gurps
+
+Syntax: !gurps <skill> [vs <opposing skill>]
+
+Skill Syntax: <num|variable> [+/- <skill> ...]
+
+Rolls 3d6 against the effective skill; rolling the skill or lower is a
+success. The margin of success or failure is shown. 3 and 4 are always
+critical successes, as are 5 at skill 15+ and 6 at skill 16+. 18 is
+always a critical failure, as is 17 at skill 15 or less, and any roll
+10 or more over the skill.
+
+A quick contest rolls for both skills. The winner is whoever succeeded
+by more, or failed by less.
+
+Examples:
+  !gurps 12 (roll against a skill of 12)
+  !gurps broadsword - 2 (roll against broadsword with a -2 penalty)
+  !gurps stealth vs 13 (quick contest of stealth against 13)
+"};
+
+const L5R_HELP: &'static str = indoc! {"
+Rolling Legend of the Five Rings dice
+
+Command: !l5r, !rk
+
+Syntax: !l5r <X>k<Y> [+/-modifier] [tn <target>] [raises <num>]
+
+Rolls X ten-sided dice and keeps the highest Y. Tens explode. The ten
+dice rule is applied: every two rolled dice over 10 become a kept
+die, and every kept die over 10 becomes a +2 bonus. Each raise adds
+5 to the target number.
+
+Examples:
+  !l5r 6k3 (roll 6 dice, keep 3)
+  !l5r 6k3 +5 tn 20 (roll 6k3 with a +5 bonus against TN 20)
+  !l5r 7k4 tn 15 raises 2 (call 2 raises against TN 15)
+"};
+
+const SHADOWRUN_HELP: &'static str = indoc! {"
+Rolling Shadowrun dice
+
+Command: !sr
+
+Syntax: !sr <expression> [edge]
+
+Expression Syntax: <num|variable> [+/- <expression> ...]
+
+Every 5 or 6 is a hit. If more than half of the dice come up 1, the
+roll is a glitch, and a glitch with no hits is a critical glitch.
+
+Edge:
+ e = push the limit (6s explode; add your Edge to the pool yourself)
+ c = second chance (re-roll every die that is not a hit)
+
+Examples:
+  !sr 12 (roll 12 dice)
+  !sr agility + firearms (roll the value of agility + firearms)
+  !sr agility + firearms + edge e (push the limit)
+  !sr 10 c (roll 10 dice, re-rolling misses)
+"};
+
+const WFRP_HELP: &'static str = indoc! {"
+Rolling Warhammer Fantasy Roleplay 4e dice
+
+Command: !wfrp
+
+Syntax: !wfrp <target> [vs <opposing target>]
+
+Target Syntax: <num|variable> [+/- <target> ...]
+
+Rolls d100 against the target; rolling the target or lower is a
+success. 01 to 05 always succeed, and 96 to 100 always fail. Success
+Levels (SL) are the tens digit of the target minus the tens digit of
+the roll. Doubles are criticals on a success and fumbles on a failure.
+The hit location is read from the reversed roll.
+
+An opposed test rolls for both targets. The higher SL wins; if SL are
+tied, the higher target wins.
+
+Examples:
+  !wfrp 45 (test against 45)
+  !wfrp melee + 20 (test against melee with a +20 bonus)
+  !wfrp melee vs 38 (opposed test of melee against 38)
+"};
+
+const YEAR_ZERO_HELP: &'static str = indoc! {"
+Rolling Year Zero Engine dice
+
+Commands: !yze (roll dice), !push (push your last roll)
+
+Roll syntax: !yze <base> [skill] [gear] [stress <num>]
+
+Base, skill and gear dice are rolled as separate pools of d6s, and
+every 6 is a success. Your last roll in each room is remembered, so
+it can be pushed with !push: all dice that are not a 6 or a 1 are
+rolled again. After pushing, each 1 on base dice is one point of
+attribute damage, and each 1 on gear dice lowers the gear bonus by
+one.
+
+For Alien, add stress dice. Pushing adds a stress die instead of
+causing damage, and any 1 on a stress die triggers a panic check.
+
+Examples:
+  !yze 3 (roll 3 base dice)
+  !yze 3 2 1 (roll 3 base, 2 skill and 1 gear dice)
+  !yze 4 2 stress 1 (Alien roll with 1 stress die)
+  !push (push the last roll)
+"};
+
+/// Help topics that are not also the name of a command, listed in
+/// the general help.
+pub const OTHER_TOPICS: &[&str] = &[
+    "cofd",
+    "dicepool",
+    "dice",
+    "cthulhu",
+    "copy",
+    "history",
+    "templates",
+];
+
 impl HelpTopic {
     pub fn message(&self) -> &str {
         match self {
+            HelpTopic::Characters => CHARACTERS_HELP,
             HelpTopic::ChroniclesOfDarkness => COFD_HELP,
             HelpTopic::DicePool => DICEPOOL_HELP,
             HelpTopic::Cthulhu => CTHULHU_HELP,
+            HelpTopic::Cypher => CYPHER_HELP,
+            HelpTopic::SavageWorlds => SAVAGE_WORLDS_HELP,
+            HelpTopic::Sheet => SHEET_HELP,
+            HelpTopic::ImportExport => IMPORT_EXPORT_HELP,
+            HelpTopic::CopyVariables => COPY_HELP,
+            HelpTopic::History => HISTORY_HELP,
+            HelpTopic::Templates => TEMPLATES_HELP,
+            HelpTopic::Macros => MACRO_HELP,
+            HelpTopic::InlineRolls => INLINE_HELP,
+            HelpTopic::Prefix => PREFIX_HELP,
+            HelpTopic::Permissions => PERMISSIONS_HELP,
+            HelpTopic::Genesys => GENESYS_HELP,
+            HelpTopic::Gurps => GURPS_HELP,
+            HelpTopic::L5r => L5R_HELP,
+            HelpTopic::Shadowrun => SHADOWRUN_HELP,
+            HelpTopic::Wfrp => WFRP_HELP,
+            HelpTopic::YearZero => YEAR_ZERO_HELP,
             HelpTopic::RollingDice => DICE_HELP,
-            HelpTopic::General => GENERAL_HELP,
         }
     }
 }
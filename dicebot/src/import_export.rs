@@ -0,0 +1,471 @@
+//! Import and export of a user's variables in a room, as JSON, TOML
+//! or CSV files. JSON and TOML files follow the layout of
+//! VariableSet: a table each of numeric variables, formulas and
+//! strings. CSV files have a "name,type,value" header, followed by one
+//! row per variable, where type is number, formula or string.
+use crate::db::{errors::DataError, Variables};
+use crate::models::VariableSet;
+use crate::parser::formula::{parse_formula, FormulaParsingError};
+use crate::parser::variables::{room_variable_name, variable_name};
+use combine::{eof, Parser};
+use mime::Mime;
+use std::collections::BTreeMap;
+use std::fmt;
+use strum::{Display, EnumString};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum ImportError {
+    #[error("invalid json: {0}")]
+    JsonError(#[from] serde_json::Error),
+
+    #[error("invalid toml: {0}")]
+    TomlError(#[from] toml::de::Error),
+
+    #[error("could not write toml: {0}")]
+    TomlSerializationError(#[from] toml::ser::Error),
+
+    #[error("invalid csv on line {0}: {1}")]
+    CsvError(usize, String),
+
+    #[error("invalid variable name: {0}")]
+    InvalidName(String),
+
+    #[error("invalid formula in variable {0}: {1}")]
+    InvalidFormula(String, FormulaParsingError),
+
+    #[error("file is not valid utf-8 text")]
+    NotText,
+
+    #[error("unknown file format: {0}")]
+    UnknownFormat(String),
+}
+
+/// The file formats variables can be imported from and exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumString, Display)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum FileFormat {
+    Json,
+    Toml,
+    Csv,
+}
+
+impl FileFormat {
+    /// Guess the format of a file from its extension.
+    pub fn from_file_name(name: &str) -> Option<FileFormat> {
+        name.rsplit_once('.')
+            .and_then(|(_, extension)| extension.parse().ok())
+    }
+
+    pub fn mime_type(&self) -> Mime {
+        match self {
+            FileFormat::Json => mime::APPLICATION_JSON,
+            FileFormat::Toml => "application/toml".parse().unwrap_or(mime::TEXT_PLAIN),
+            FileFormat::Csv => mime::TEXT_CSV,
+        }
+    }
+}
+
+/// What to do with variables that exist both in the room and in the
+/// imported file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, EnumString, Display)]
+#[strum(serialize_all = "lowercase", ascii_case_insensitive)]
+pub enum ConflictMode {
+    /// Imported variables replace existing variables of the same name.
+    /// Other existing variables are kept.
+    #[default]
+    Merge,
+
+    /// The imported file replaces all existing variables. Existing
+    /// variables that are not in the file are deleted.
+    Overwrite,
+
+    /// Existing variables are kept, and only new variables are
+    /// imported.
+    Skip,
+}
+
+/// Names of the variables affected by an import, for the summary
+/// shown to the user (and for dry runs).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ImportSummary {
+    pub added: Vec<String>,
+    pub updated: Vec<String>,
+    pub unchanged: Vec<String>,
+    pub skipped: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl fmt::Display for ImportSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let parts = [
+            ("added", &self.added),
+            ("updated", &self.updated),
+            ("unchanged", &self.unchanged),
+            ("skipped", &self.skipped),
+            ("removed", &self.removed),
+        ];
+
+        let described: Vec<String> = parts
+            .iter()
+            .filter(|(_, names)| !names.is_empty())
+            .map(|(label, names)| format!("{} {} ({})", names.len(), label, names.join(", ")))
+            .collect();
+
+        match described.is_empty() {
+            true => write!(f, "nothing to import"),
+            false => write!(f, "{}", described.join("; ")),
+        }
+    }
+}
+
+/// The changes to make to the stored variables for an import.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ImportPlan {
+    pub store: VariableSet,
+    pub delete: Vec<String>,
+    pub summary: ImportSummary,
+}
+
+/// The type and value of a single variable in a set, as written in
+/// CSV files.
+fn find_value(set: &VariableSet, name: &str) -> Option<(&'static str, String)> {
+    if let Some(value) = set.variables.get(name) {
+        Some(("number", value.to_string()))
+    } else if let Some(formula) = set.formulas.get(name) {
+        Some(("formula", formula.clone()))
+    } else {
+        set.strings.get(name).map(|text| ("string", text.clone()))
+    }
+}
+
+/// Copy a single variable from one set to another.
+fn copy_variable(from: &VariableSet, to: &mut VariableSet, name: &str) {
+    if let Some(&value) = from.variables.get(name) {
+        to.variables.insert(name.to_owned(), value);
+    } else if let Some(formula) = from.formulas.get(name) {
+        to.formulas.insert(name.to_owned(), formula.clone());
+    } else if let Some(text) = from.strings.get(name) {
+        to.strings.insert(name.to_owned(), text.clone());
+    }
+}
+
+/// Work out which variables to store and delete to import the
+/// incoming variables on top of the existing ones.
+pub fn plan_import(
+    existing: &VariableSet,
+    incoming: &VariableSet,
+    mode: ConflictMode,
+) -> ImportPlan {
+    let mut plan = ImportPlan::default();
+
+    for name in incoming.names() {
+        let summary = &mut plan.summary;
+        let current = find_value(existing, name);
+        let names = match current {
+            None => &mut summary.added,
+            Some(_) if mode == ConflictMode::Skip => {
+                summary.skipped.push(name.to_owned());
+                continue;
+            }
+            Some(_) if current == find_value(incoming, name) => {
+                summary.unchanged.push(name.to_owned());
+                continue;
+            }
+            Some(_) => &mut summary.updated,
+        };
+
+        names.push(name.to_owned());
+        copy_variable(incoming, &mut plan.store, name);
+    }
+
+    if mode == ConflictMode::Overwrite {
+        for name in existing.names() {
+            if !incoming.contains(name) {
+                plan.summary.removed.push(name.to_owned());
+                plan.delete.push(name.to_owned());
+            }
+        }
+    }
+
+    plan
+}
+
+fn validate_name(name: &str) -> Result<(), ImportError> {
+    let parsed = variable_name().skip(eof()).parse(name).is_ok();
+
+    match parsed && room_variable_name(name).is_none() {
+        true => Ok(()),
+        false => Err(ImportError::InvalidName(name.to_owned())),
+    }
+}
+
+/// Make sure all names and formulas in an imported set are valid,
+/// and store names in lower case like all other variables.
+fn validate(set: VariableSet) -> Result<VariableSet, ImportError> {
+    for name in set.names() {
+        validate_name(name)?;
+    }
+
+    for (name, formula) in &set.formulas {
+        parse_formula(formula).map_err(|e| ImportError::InvalidFormula(name.clone(), e))?;
+    }
+
+    Ok(VariableSet {
+        variables: lower_case_names(set.variables),
+        formulas: lower_case_names(set.formulas),
+        strings: lower_case_names(set.strings),
+    })
+}
+
+fn lower_case_names<V>(values: BTreeMap<String, V>) -> BTreeMap<String, V> {
+    values
+        .into_iter()
+        .map(|(name, value)| (name.to_lowercase(), value))
+        .collect()
+}
+
+/// Quote a CSV field if needed.
+fn csv_field(value: &str) -> String {
+    if value.contains(&[',', '"', '\n'][..]) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+/// Split a line of CSV into fields, handling quoted fields.
+fn parse_csv_line(line: &str) -> Option<Vec<String>> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if quoted => quoted = false,
+            '"' if field.is_empty() => quoted = true,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+
+    if quoted {
+        return None;
+    }
+
+    fields.push(field);
+    Some(fields)
+}
+
+fn to_csv(set: &VariableSet) -> String {
+    let mut csv = String::from("name,type,value\n");
+    for name in set.names() {
+        if let Some((kind, value)) = find_value(set, name) {
+            csv.push_str(&format!(
+                "{},{},{}\n",
+                csv_field(name),
+                kind,
+                csv_field(&value)
+            ));
+        }
+    }
+
+    csv
+}
+
+fn from_csv(data: &str) -> Result<VariableSet, ImportError> {
+    let mut set = VariableSet::default();
+    let lines = data
+        .lines()
+        .enumerate()
+        .map(|(index, line)| (index + 1, line));
+
+    for (line_number, line) in lines.filter(|(_, line)| !line.trim().is_empty()) {
+        let error = |message: &str| ImportError::CsvError(line_number, message.to_owned());
+        let fields = parse_csv_line(line).ok_or_else(|| error("unclosed quote"))?;
+
+        match &fields[..] {
+            [name, kind, _] if line_number == 1 && name == "name" && kind == "type" => (),
+            [name, kind, value] => match kind.as_str() {
+                "number" => {
+                    let value = value.trim().parse().map_err(|_| error("invalid number"))?;
+                    set.variables.insert(name.clone(), value);
+                }
+                "formula" => {
+                    set.formulas.insert(name.clone(), value.clone());
+                }
+                "string" => {
+                    set.strings.insert(name.clone(), value.clone());
+                }
+                _ => return Err(error("type must be number, formula or string")),
+            },
+            _ => return Err(error("expected name, type and value")),
+        }
+    }
+
+    Ok(set)
+}
+
+/// Write variables in the given format.
+pub fn serialize(set: &VariableSet, format: FileFormat) -> Result<String, ImportError> {
+    match format {
+        FileFormat::Json => Ok(serde_json::to_string_pretty(set)?),
+        FileFormat::Toml => Ok(toml::to_string(set)?),
+        FileFormat::Csv => Ok(to_csv(set)),
+    }
+}
+
+/// Read and validate variables in the given format.
+pub fn deserialize(data: &str, format: FileFormat) -> Result<VariableSet, ImportError> {
+    let set = match format {
+        FileFormat::Json => serde_json::from_str(data)?,
+        FileFormat::Toml => toml::from_str(data)?,
+        FileFormat::Csv => from_csv(data)?,
+    };
+
+    validate(set)
+}
+
+/// All of the user's variables in the room.
+pub async fn load_variables(
+    db: &impl Variables,
+    user: &str,
+    room_id: &str,
+) -> Result<VariableSet, DataError> {
+    Ok(VariableSet {
+        variables: db
+            .get_user_variables(user, room_id)
+            .await?
+            .into_iter()
+            .collect(),
+        formulas: db
+            .get_user_formulas(user, room_id)
+            .await?
+            .into_iter()
+            .collect(),
+        strings: db
+            .get_user_strings(user, room_id)
+            .await?
+            .into_iter()
+            .collect(),
+    })
+}
+
+/// Import variables for the user in the room. Nothing is stored for
+/// dry runs, but the summary is still returned.
+pub async fn import_variables(
+    db: &impl Variables,
+    user: &str,
+    room_id: &str,
+    incoming: &VariableSet,
+    mode: ConflictMode,
+    dry_run: bool,
+) -> Result<ImportSummary, DataError> {
+    let existing = load_variables(db, user, room_id).await?;
+    let plan = plan_import(&existing, incoming, mode);
+
+    if !dry_run {
+        db.import_user_variables(user, room_id, &plan.store, &plan.delete)
+            .await?;
+    }
+
+    Ok(plan.summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variable_set() -> VariableSet {
+        let mut set = VariableSet::default();
+        set.variables.insert("strength".to_owned(), 3);
+        set.formulas.insert(
+            "defense".to_owned(),
+            "min(wits, dex) + athletics".to_owned(),
+        );
+        set.strings
+            .insert("weapon".to_owned(), "Sword, \"Longsword\"".to_owned());
+        set
+    }
+
+    #[test]
+    fn round_trips_all_formats() {
+        for format in &[FileFormat::Json, FileFormat::Toml, FileFormat::Csv] {
+            let data = serialize(&variable_set(), *format).unwrap();
+            assert_eq!(variable_set(), deserialize(&data, *format).unwrap());
+        }
+    }
+
+    #[test]
+    fn reads_csv_with_header() {
+        let data = "name,type,value\nStrength,number,3\nnotes,string,\"a, b\"\n";
+        let set = deserialize(data, FileFormat::Csv).unwrap();
+
+        assert_eq!(Some(&3), set.variables.get("strength"));
+        assert_eq!(Some("a, b"), set.strings.get("notes").map(String::as_str));
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        let bad_name = r#"{"variables": {"room.difficulty": 2}}"#;
+        assert!(matches!(
+            deserialize(bad_name, FileFormat::Json),
+            Err(ImportError::InvalidName(_))
+        ));
+
+        let bad_formula = "[formulas]\ndefense = \"wits +\"";
+        assert!(matches!(
+            deserialize(bad_formula, FileFormat::Toml),
+            Err(ImportError::InvalidFormula(_, _))
+        ));
+
+        let bad_csv = "strength,number,three";
+        assert!(matches!(
+            deserialize(bad_csv, FileFormat::Csv),
+            Err(ImportError::CsvError(1, _))
+        ));
+    }
+
+    #[test]
+    fn guesses_format_from_file_name() {
+        assert_eq!(
+            Some(FileFormat::Csv),
+            FileFormat::from_file_name("anna.CSV")
+        );
+        assert_eq!(None, FileFormat::from_file_name("anna.txt"));
+        assert_eq!(None, FileFormat::from_file_name("anna"));
+    }
+
+    #[test]
+    fn plans_imports_for_each_conflict_mode() {
+        let mut existing = VariableSet::default();
+        existing.variables.insert("strength".to_owned(), 2);
+        existing.variables.insert("wits".to_owned(), 1);
+        existing.formulas.insert(
+            "defense".to_owned(),
+            "min(wits, dex) + athletics".to_owned(),
+        );
+
+        let incoming = variable_set();
+
+        let merge = plan_import(&existing, &incoming, ConflictMode::Merge);
+        assert_eq!(vec!["weapon"], merge.summary.added);
+        assert_eq!(vec!["strength"], merge.summary.updated);
+        assert_eq!(vec!["defense"], merge.summary.unchanged);
+        assert!(merge.delete.is_empty());
+        assert_eq!(2, merge.store.len());
+
+        let skip = plan_import(&existing, &incoming, ConflictMode::Skip);
+        assert_eq!(vec!["defense", "strength"], skip.summary.skipped);
+        assert_eq!(1, skip.store.len());
+
+        let overwrite = plan_import(&existing, &incoming, ConflictMode::Overwrite);
+        assert_eq!(vec!["wits"], overwrite.summary.removed);
+        assert_eq!(vec!["wits".to_owned()], overwrite.delete);
+    }
+}
@@ -0,0 +1,152 @@
+use crate::basic::dice::ElementExpression;
+use crate::basic::parser::parse_element_expression;
+use crate::basic::roll::Roll;
+use crate::models::RoomSettings;
+
+/// Most inline rolls rolled in one message. Markers after these are
+/// left as they are.
+pub const MAX_INLINE_ROLLS: usize = 10;
+
+/// A piece of a chat message: text, or what is between a pair of
+/// inline roll markers.
+#[derive(Debug, PartialEq, Eq)]
+enum Segment<'a> {
+    Text(&'a str),
+    Roll(&'a str),
+}
+
+/// Split a message into text and the contents of inline roll markers.
+/// A marker without a closing marker is left as text.
+fn segments<'a>(message: &'a str, open: &str, close: &str) -> Vec<Segment<'a>> {
+    let mut segments = vec![];
+    let mut rest = message;
+    let mut rolls = 0;
+
+    while let Some(start) = rest.find(open).filter(|_| rolls < MAX_INLINE_ROLLS) {
+        let after_open = &rest[start + open.len()..];
+        let end = match after_open.find(close) {
+            Some(end) => end,
+            None => break,
+        };
+
+        segments.push(Segment::Text(&rest[..start]));
+        segments.push(Segment::Roll(&after_open[..end]));
+        rest = &after_open[end + close.len()..];
+        rolls += 1;
+    }
+
+    segments.push(Segment::Text(rest));
+    segments
+}
+
+fn parse_expression(input: &str) -> Option<ElementExpression> {
+    match parse_element_expression(input.trim()) {
+        Ok(("", expression)) => Some(expression),
+        _ => None,
+    }
+}
+
+/// Escape text that is put into an HTML message as it is.
+pub(crate) fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Roll every dice expression between inline roll markers in a chat
+/// message, and quote the message with the results in place of the
+/// markers. Returns None if inline rolls are turned off in the room,
+/// or if the message has nothing to roll. Markers that do not contain
+/// a dice expression are quoted as they are.
+pub fn roll_inline(message: &str, settings: &RoomSettings) -> Option<String> {
+    let (open, close) = (&settings.inline_roll_open, &settings.inline_roll_close);
+    if !settings.inline_rolls || open.is_empty() || close.is_empty() {
+        return None;
+    }
+
+    let mut quoted = String::with_capacity(message.len());
+    let mut rolled = false;
+
+    for segment in segments(message, open, close) {
+        match segment {
+            Segment::Text(text) => quoted.push_str(&escape_html(text)),
+            Segment::Roll(input) => match parse_expression(input) {
+                Some(expression) => {
+                    let roll = expression.roll();
+                    quoted.push_str(&format!("<strong>[{}: {}]</strong>", expression, roll));
+                    rolled = true;
+                }
+                None => quoted.push_str(&escape_html(&format!("{}{}{}", open, input, close))),
+            },
+        }
+    }
+
+    match rolled {
+        true => Some(format!("<blockquote>{}</blockquote>", quoted)),
+        false => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_rolls_from_text() {
+        assert_eq!(
+            vec![
+                Segment::Text("I swing my axe "),
+                Segment::Roll("1d12+3"),
+                Segment::Text(" at the orc"),
+            ],
+            segments("I swing my axe [[1d12+3]] at the orc", "[[", "]]")
+        );
+    }
+
+    #[test]
+    fn unclosed_markers_are_text() {
+        assert_eq!(
+            vec![
+                Segment::Text("a "),
+                Segment::Roll("1d4"),
+                Segment::Text(" [[1d6")
+            ],
+            segments("a [[1d4]] [[1d6", "[[", "]]")
+        );
+    }
+
+    #[test]
+    fn rolls_only_dice_expressions() {
+        let settings = RoomSettings {
+            inline_rolls: true,
+            ..Default::default()
+        };
+        let result = roll_inline("see [[the wiki]] for <rules>", &settings);
+        assert_eq!(None, result);
+
+        let result = roll_inline("I hit for [[3]] and [[oops]]", &settings);
+        assert_eq!(
+            Some("<blockquote>I hit for <strong>[3: 3]</strong> and [[oops]]</blockquote>"),
+            result.as_deref()
+        );
+    }
+
+    #[test]
+    fn turned_off_by_default() {
+        let settings = RoomSettings::default();
+        assert_eq!(None, roll_inline("[[1d4]]", &settings));
+    }
+
+    #[test]
+    fn uses_room_markers() {
+        let settings = RoomSettings {
+            inline_rolls: true,
+            inline_roll_open: "((".to_owned(),
+            inline_roll_close: "))".to_owned(),
+            ..Default::default()
+        };
+
+        assert_eq!(None, roll_inline("[[1d4]]", &settings));
+        assert!(roll_inline("((1d4))", &settings).is_some());
+    }
+}
@@ -0,0 +1,284 @@
+use crate::dice::{roll_exploding_die, DieRoller, RngDieRoller};
+use itertools::Itertools;
+use std::fmt;
+
+/// Legend of the Five Rings only uses ten-sided dice.
+const DIE_SIDES: u32 = 10;
+
+/// Tens explode: a 10 is rolled again and added to the die.
+const EXPLODE_ON: u32 = 10;
+
+/// The ten dice rule: no roll may roll or keep more than 10 dice.
+const MAX_DICE: u32 = 10;
+
+/// Every kept die over 10 is converted into a flat bonus of +2.
+const EXCESS_KEPT_BONUS: i32 = 2;
+
+/// Each raise increases the target number by 5.
+const RAISE_SIZE: i32 = 5;
+
+/// A roll-and-keep roll (XkY): roll X exploding d10s, and add up the
+/// highest Y of them. An optional target number can be given, along
+/// with the number of raises called for the roll.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RollAndKeep {
+    pub rolled: u32,
+    pub kept: u32,
+    pub modifier: i32,
+    pub target: Option<i32>,
+    pub raises: u32,
+}
+
+impl RollAndKeep {
+    /// Apply the ten dice rule. Every two rolled dice over 10 become
+    /// one kept die, and then every kept die over 10 becomes a bonus
+    /// of +2. A roll can never keep more dice than it rolls.
+    pub fn apply_ten_dice_rule(&self) -> RollAndKeep {
+        let mut rolled = self.rolled;
+        let mut kept = self.kept;
+        let mut modifier = self.modifier;
+
+        if rolled > MAX_DICE {
+            kept += (rolled - MAX_DICE) / 2;
+            rolled = MAX_DICE;
+        }
+
+        if kept > MAX_DICE {
+            modifier += (kept - MAX_DICE) as i32 * EXCESS_KEPT_BONUS;
+            kept = MAX_DICE;
+        }
+
+        RollAndKeep {
+            rolled,
+            kept: std::cmp::min(kept, rolled),
+            modifier,
+            target: self.target,
+            raises: self.raises,
+        }
+    }
+
+    /// The target number, increased by any called raises.
+    pub fn effective_target(&self) -> Option<i32> {
+        self.target
+            .map(|target| target + self.raises as i32 * RAISE_SIZE)
+    }
+}
+
+impl fmt::Display for RollAndKeep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}k{}", self.rolled, self.kept)?;
+
+        if self.modifier != 0 {
+            write!(f, "{:+}", self.modifier)?;
+        }
+
+        if let Some(target) = self.target {
+            write!(f, ", TN {}", target)?;
+        }
+
+        match self.raises {
+            0 => (),
+            1 => write!(f, " with 1 raise")?,
+            raises => write!(f, " with {} raises", raises)?,
+        }
+
+        Ok(())
+    }
+}
+
+/// A completed roll-and-keep roll. Each die keeps every roll made
+/// for it, so exploded tens can be displayed. Dice are sorted from
+/// highest to lowest, and the first ones are kept.
+pub struct RolledRollAndKeep {
+    pub(crate) roll: RollAndKeep,
+    pub(crate) dice: Vec<Vec<u32>>,
+}
+
+fn die_total(die: &[u32]) -> u32 {
+    die.iter().sum()
+}
+
+fn fmt_die(die: &[u32]) -> String {
+    if die.len() > 1 {
+        format!("{} ({})", die_total(die), die.iter().join("+"))
+    } else {
+        die.iter().join("")
+    }
+}
+
+impl RolledRollAndKeep {
+    pub fn kept(&self) -> &[Vec<u32>] {
+        &self.dice[..self.roll.kept as usize]
+    }
+
+    pub fn dropped(&self) -> &[Vec<u32>] {
+        &self.dice[self.roll.kept as usize..]
+    }
+
+    /// Sum of the kept dice plus the modifier.
+    pub fn total(&self) -> i32 {
+        let kept: u32 = self.kept().iter().map(|die| die_total(die)).sum();
+        kept as i32 + self.roll.modifier
+    }
+
+    /// Whether the roll met the target number (including raises), if
+    /// there is one.
+    pub fn is_success(&self) -> Option<bool> {
+        self.roll
+            .effective_target()
+            .map(|target| self.total() >= target)
+    }
+}
+
+impl fmt::Display for RolledRollAndKeep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "kept: {}",
+            self.kept().iter().map(|die| fmt_die(die)).join(", ")
+        )?;
+
+        if !self.dropped().is_empty() {
+            let dropped = self.dropped().iter().map(|die| fmt_die(die)).join(", ");
+            write!(f, "; dropped: {}", dropped)?;
+        }
+
+        write!(f, "; total: {}", self.total())?;
+
+        match (self.is_success(), self.roll.effective_target()) {
+            (Some(true), Some(target)) => write!(f, " against {}, success!", target),
+            (Some(false), Some(target)) => write!(f, " against {}, failure!", target),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn roll_and_keep_dice<R: DieRoller>(roll: &RollAndKeep, roller: &mut R) -> RolledRollAndKeep {
+    let roll = roll.apply_ten_dice_rule();
+
+    let dice: Vec<Vec<u32>> = (0..roll.rolled)
+        .map(|_| roll_exploding_die(roller, DIE_SIDES, EXPLODE_ON))
+        .sorted_by_key(|die| std::cmp::Reverse(die_total(die)))
+        .collect();
+
+    RolledRollAndKeep { roll, dice }
+}
+
+/// Make a roll-and-keep roll, after applying the ten dice rule.
+pub fn roll_and_keep(roll: &RollAndKeep) -> RolledRollAndKeep {
+    let mut roller = RngDieRoller(rand::thread_rng());
+    roll_and_keep_dice(roll, &mut roller)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::SequentialDieRoller;
+
+    fn xky(rolled: u32, kept: u32) -> RollAndKeep {
+        RollAndKeep {
+            rolled,
+            kept,
+            modifier: 0,
+            target: None,
+            raises: 0,
+        }
+    }
+
+    #[test]
+    fn ten_dice_rule_leaves_small_rolls_alone() {
+        assert_eq!(xky(6, 3), xky(6, 3).apply_ten_dice_rule());
+        assert_eq!(xky(10, 10), xky(10, 10).apply_ten_dice_rule());
+    }
+
+    #[test]
+    fn ten_dice_rule_converts_rolled_to_kept() {
+        //Two extra rolled dice become one kept die; the odd one is lost.
+        assert_eq!(xky(10, 5), xky(13, 4).apply_ten_dice_rule());
+        assert_eq!(xky(10, 6), xky(14, 4).apply_ten_dice_rule());
+    }
+
+    #[test]
+    fn ten_dice_rule_converts_kept_to_bonus() {
+        let result = xky(14, 9).apply_ten_dice_rule();
+        assert_eq!(10, result.rolled);
+        assert_eq!(10, result.kept);
+        assert_eq!(2, result.modifier);
+
+        let result = xky(10, 12).apply_ten_dice_rule();
+        assert_eq!(10, result.kept);
+        assert_eq!(4, result.modifier);
+    }
+
+    #[test]
+    fn cannot_keep_more_than_rolled() {
+        assert_eq!(xky(3, 3), xky(3, 5).apply_ten_dice_rule());
+    }
+
+    #[test]
+    fn keeps_highest_dice() {
+        let mut roller = SequentialDieRoller::new(vec![3, 10, 4, 7, 1]);
+        let rolled = roll_and_keep_dice(&xky(4, 2), &mut roller);
+        assert_eq!(vec![vec![10, 4], vec![7]], rolled.kept());
+        assert_eq!(vec![vec![3], vec![1]], rolled.dropped());
+        assert_eq!(21, rolled.total());
+    }
+
+    #[test]
+    fn raises_increase_target() {
+        let roll = RollAndKeep {
+            rolled: 2,
+            kept: 2,
+            modifier: 1,
+            target: Some(10),
+            raises: 1,
+        };
+
+        //Total of 14 against 15.
+        let mut roller = SequentialDieRoller::new(vec![6, 7]);
+        let rolled = roll_and_keep_dice(&roll, &mut roller);
+        assert_eq!(Some(15), roll.effective_target());
+        assert_eq!(Some(false), rolled.is_success());
+
+        //Total of 15 against 15.
+        let mut roller = SequentialDieRoller::new(vec![6, 8]);
+        let rolled = roll_and_keep_dice(&roll, &mut roller);
+        assert_eq!(Some(true), rolled.is_success());
+    }
+
+    #[test]
+    fn no_target_has_no_success() {
+        let mut roller = SequentialDieRoller::new(vec![5]);
+        let rolled = roll_and_keep_dice(&xky(1, 1), &mut roller);
+        assert_eq!(None, rolled.is_success());
+    }
+
+    #[test]
+    fn formats_roll() {
+        let roll = RollAndKeep {
+            rolled: 6,
+            kept: 3,
+            modifier: 5,
+            target: Some(20),
+            raises: 2,
+        };
+
+        assert_eq!("6k3+5, TN 20 with 2 raises", roll.to_string());
+        assert_eq!("4k2", xky(4, 2).to_string());
+    }
+
+    #[test]
+    fn formats_result() {
+        let roll = RollAndKeep {
+            target: Some(15),
+            ..xky(3, 2)
+        };
+
+        let mut roller = SequentialDieRoller::new(vec![10, 2, 5, 1]);
+        let rolled = roll_and_keep_dice(&roll, &mut roller);
+        assert_eq!(
+            "kept: 12 (10+2), 5; dropped: 1; total: 17 against 15, success!",
+            rolled.to_string()
+        );
+    }
+}
@@ -0,0 +1,189 @@
+use super::dice::RollAndKeep;
+use crate::parser::dice::DiceParsingError;
+use combine::parser::char::{digit, spaces, string};
+use combine::{many, many1, one_of, optional, Parser};
+
+/// Most dice that can be rolled or kept before the ten dice rule is
+/// applied. Anything bigger is a typo, not a roll.
+const MAX_DICE: u32 = 100;
+
+/// Most raises that can be called on a single roll.
+const MAX_RAISES: u32 = 20;
+
+/// Largest total modifier, and largest target number, of a roll.
+/// Keeps the totals of the dice from overflowing.
+const MAX_MODIFIER: i32 = 1000;
+
+fn parse_limited<T>(amount: &str, max: T) -> Result<T, DiceParsingError>
+where
+    T: std::str::FromStr<Err = std::num::ParseIntError> + PartialOrd,
+{
+    match amount.parse::<T>()? {
+        amount if amount > max => Err(DiceParsingError::InvalidAmount),
+        amount => Ok(amount),
+    }
+}
+
+/// Parse a roll-and-keep roll, in the form of `<X>k<Y> [+/-modifier
+/// ...] [tn <target>] [raises <amount>]`, e.g. "6k3", "6k3 +5 tn 20"
+/// or "7k4 tn 15 raises 2". Raises can only be called against a
+/// target number.
+pub fn parse_roll_and_keep(input: &str) -> Result<RollAndKeep, DiceParsingError> {
+    let input = input.trim();
+
+    let dice = many1(digit())
+        .skip(one_of("kK".chars()))
+        .and(many1(digit()))
+        .skip(spaces().silent());
+
+    let modifier = one_of("+-".chars())
+        .skip(spaces().silent())
+        .and(many1(digit()))
+        .skip(spaces().silent());
+
+    let target = string("tn")
+        .skip(spaces().silent())
+        .with(many1(digit()))
+        .skip(spaces().silent());
+
+    let raises = string("raises")
+        .skip(spaces().silent())
+        .with(many1(digit()))
+        .skip(spaces().silent());
+
+    let mut parser = (
+        dice,
+        many::<Vec<(char, String)>, _, _>(modifier),
+        optional(target),
+        optional(raises),
+    );
+
+    let (((rolled, kept), modifiers, target, raises), rest) = parser.parse(input)?;
+
+    if !rest.is_empty() {
+        return Err(DiceParsingError::UnconsumedInput);
+    }
+
+    let rolled: String = rolled;
+    let kept: String = kept;
+    let rolled = parse_limited(&rolled, MAX_DICE)?;
+    let kept = parse_limited(&kept, MAX_DICE)?;
+    if rolled == 0 || kept == 0 {
+        return Err(DiceParsingError::InvalidAmount);
+    }
+
+    let mut modifier: i32 = 0;
+    for (sign, amount) in modifiers {
+        let amount = parse_limited(&amount, MAX_MODIFIER)?;
+        modifier += if sign == '-' { -amount } else { amount };
+
+        if modifier.abs() > MAX_MODIFIER {
+            return Err(DiceParsingError::InvalidAmount);
+        }
+    }
+
+    let target = target
+        .map(|tn: String| parse_limited(&tn, MAX_MODIFIER))
+        .transpose()?;
+
+    let raises = raises
+        .map(|r: String| parse_limited(&r, MAX_RAISES))
+        .transpose()?;
+
+    if target.is_none() && raises.is_some() {
+        return Err(DiceParsingError::InvalidModifiers);
+    }
+
+    Ok(RollAndKeep {
+        rolled,
+        kept,
+        modifier,
+        target,
+        raises: raises.unwrap_or(0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn xky(rolled: u32, kept: u32, modifier: i32) -> RollAndKeep {
+        RollAndKeep {
+            rolled,
+            kept,
+            modifier,
+            target: None,
+            raises: 0,
+        }
+    }
+
+    #[test]
+    fn parses_dice() {
+        assert_eq!(parse_roll_and_keep("6k3"), Ok(xky(6, 3, 0)));
+        assert_eq!(parse_roll_and_keep(" 12K5 "), Ok(xky(12, 5, 0)));
+    }
+
+    #[test]
+    fn parses_modifiers() {
+        assert_eq!(parse_roll_and_keep("6k3+5"), Ok(xky(6, 3, 5)));
+        assert_eq!(parse_roll_and_keep("6k3 + 5 - 1"), Ok(xky(6, 3, 4)));
+    }
+
+    #[test]
+    fn parses_target_and_raises() {
+        assert_eq!(
+            parse_roll_and_keep("6k3 +2 tn 20"),
+            Ok(RollAndKeep {
+                target: Some(20),
+                ..xky(6, 3, 2)
+            })
+        );
+
+        assert_eq!(
+            parse_roll_and_keep("7k4 tn 15 raises 2"),
+            Ok(RollAndKeep {
+                target: Some(15),
+                raises: 2,
+                ..xky(7, 4, 0)
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_raises_without_target() {
+        assert_eq!(
+            parse_roll_and_keep("6k3 raises 1"),
+            Err(DiceParsingError::InvalidModifiers)
+        );
+    }
+
+    #[test]
+    fn rejects_bad_input() {
+        assert!(parse_roll_and_keep("").is_err());
+        assert!(parse_roll_and_keep("6").is_err());
+        assert!(parse_roll_and_keep("0k1").is_err());
+        assert!(parse_roll_and_keep("6k0").is_err());
+        assert_eq!(
+            parse_roll_and_keep("6k3 tn 20 abc"),
+            Err(DiceParsingError::UnconsumedInput)
+        );
+    }
+
+    #[test]
+    fn rejects_huge_amounts() {
+        let too_big = |input: &str| {
+            assert_eq!(
+                parse_roll_and_keep(input),
+                Err(DiceParsingError::InvalidAmount)
+            )
+        };
+
+        too_big("5k4000000000");
+        too_big("4000000000k5");
+        too_big("101k5");
+        too_big("6k3 tn 20 raises 4000000000");
+        too_big("6k3 tn 2147483647 raises 20");
+        too_big("6k3 +1000 +1");
+        assert!(parse_roll_and_keep("100k100 +1000 tn 1000 raises 20").is_ok());
+    }
+}
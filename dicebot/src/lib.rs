@@ -5,13 +5,26 @@ pub mod commands;
 pub mod config;
 pub mod context;
 pub mod cthulhu;
+pub mod cypher;
 pub mod db;
+mod dice;
 pub mod error;
+pub mod genesys;
+pub mod gurps;
 mod help;
+pub mod import_export;
+pub mod inline_rolls;
+pub mod l5r;
 pub mod logic;
 pub mod matrix;
 pub mod models;
 mod parser;
 pub mod rpc;
+pub mod savage_worlds;
+pub mod shadowrun;
 pub mod state;
+pub mod suggestions;
 pub mod systems;
+pub mod templating;
+pub mod wfrp;
+pub mod year_zero;
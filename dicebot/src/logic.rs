@@ -1,15 +1,245 @@
 use crate::error::{BotError, DiceRollingError};
 use crate::parser::dice::{Amount, Element};
+use crate::parser::formula::{parse_formula, Formula};
+use crate::parser::variables::{room_variable_name, ROOM_PREFIX};
+use crate::suggestions::closest_match;
 use crate::{context::Context, models::Account};
 use crate::{
-    db::{sqlite::Database, Users, Variables},
+    db::{sqlite::Database, RoomVariables, Rooms, Users, Variables},
     models::TransientUser,
 };
 use argon2::{self, Config, Error as ArgonError};
-use futures::stream::{self, StreamExt, TryStreamExt};
 use rand::Rng;
+use std::collections::HashMap;
 use std::slice;
 
+/// Resolves variable names to numbers for the user in the active
+/// room. Variables are either stored numbers, formula variables, or
+/// the derived values of the room's character sheet template (if the
+/// room has a game system with a template). Formulas and derived
+/// values are recalculated on every read. Names are
+/// case-insensitive. Room variables are found under their prefixed
+/// name ("room.difficulty"), or under their plain name if the user
+/// has no variable of that name.
+pub struct VariableResolver {
+    numbers: HashMap<String, i32>,
+    formulas: HashMap<String, String>,
+    strings: HashMap<String, String>,
+}
+
+impl VariableResolver {
+    pub async fn load(ctx: &Context<'_>) -> Result<VariableResolver, BotError> {
+        let room_id = ctx.active_room_id();
+        let mut numbers = ctx
+            .db
+            .get_user_variables(ctx.username, room_id.as_str())
+            .await?;
+
+        let formulas = ctx
+            .db
+            .get_user_formulas(ctx.username, room_id.as_str())
+            .await?;
+
+        let strings = ctx
+            .db
+            .get_user_strings(ctx.username, room_id.as_str())
+            .await?;
+
+        let system = ctx.db.get_game_system(room_id.as_str()).await?;
+        if let Some(template) = system.and_then(|system| system.sheet_template()) {
+            let derived = template.derived(&numbers);
+            numbers.extend(derived);
+        }
+
+        let mut variables = VariableResolver {
+            numbers,
+            formulas,
+            strings,
+        };
+
+        let room_numbers = ctx.db.get_room_variables(room_id.as_str()).await?;
+        let room_formulas = ctx.db.get_room_formulas(room_id.as_str()).await?;
+        let room_strings = ctx.db.get_room_strings(room_id.as_str()).await?;
+        variables.add_room_variables(room_numbers, room_formulas, room_strings);
+
+        Ok(variables)
+    }
+
+    fn add_room_variables(
+        &mut self,
+        numbers: HashMap<String, i32>,
+        formulas: HashMap<String, String>,
+        strings: HashMap<String, String>,
+    ) {
+        let prefixed = |name: String| format!("{}{}", ROOM_PREFIX, name);
+        self.numbers
+            .extend(numbers.into_iter().map(|(k, v)| (prefixed(k), v)));
+        self.formulas
+            .extend(formulas.into_iter().map(|(k, v)| (prefixed(k), v)));
+        self.strings
+            .extend(strings.into_iter().map(|(k, v)| (prefixed(k), v)));
+    }
+
+    fn contains(&self, name: &str) -> bool {
+        self.numbers.contains_key(name)
+            || self.formulas.contains_key(name)
+            || self.strings.contains_key(name)
+    }
+
+    /// The stored name of a variable: the user's (or character's)
+    /// variable comes first, then the room variable of the same name.
+    fn lookup(&self, name: &str) -> String {
+        let name = name.to_lowercase();
+        let room_name = format!("{}{}", ROOM_PREFIX, name);
+        if room_variable_name(&name).is_none() && !self.contains(&name) && self.contains(&room_name)
+        {
+            room_name
+        } else {
+            name
+        }
+    }
+
+    /// The numeric or formula variable with the name closest to a
+    /// (probably misspelled) name that does not exist.
+    fn suggest(&self, name: &str) -> Option<String> {
+        let known: Vec<&str> = self
+            .numbers
+            .keys()
+            .chain(self.formulas.keys())
+            .map(String::as_str)
+            .collect();
+
+        closest_match(name, &known).map(str::to_owned)
+    }
+
+    /// Whether a variable of any kind exists under the name.
+    pub fn is_defined(&self, name: &str) -> bool {
+        self.contains(&self.lookup(name))
+    }
+
+    /// The unevaluated expression of a formula variable.
+    pub fn formula(&self, name: &str) -> Option<&str> {
+        self.formulas.get(&self.lookup(name)).map(String::as_str)
+    }
+
+    /// The value of a string variable.
+    pub fn text(&self, name: &str) -> Option<&str> {
+        self.strings.get(&self.lookup(name)).map(String::as_str)
+    }
+
+    /// Resolve a variable to a number, evaluating formulas as needed.
+    /// Errors out if a variable is not defined, or if formulas refer
+    /// to each other in a cycle.
+    pub fn resolve(&self, name: &str) -> Result<i32, DiceRollingError> {
+        self.resolve_with_path(name, &mut Resolution::default())
+    }
+
+    /// Add up the dice amounts, resolving their variables.
+    pub fn dice_amount(&self, amounts: &[Amount]) -> Result<i32, DiceRollingError> {
+        let mut resolution = Resolution::default();
+        amounts.iter().try_fold(0i32, |total, amount| {
+            let value = match &amount.element {
+                Element::Number(num_dice) => *num_dice,
+                Element::Variable(variable) => self.resolve_with_path(variable, &mut resolution)?,
+            };
+
+            value
+                .checked_mul(amount.operator.mult())
+                .and_then(|value| total.checked_add(value))
+                .ok_or(DiceRollingError::InvalidAmount)
+        })
+    }
+
+    fn resolve_with_path(
+        &self,
+        name: &str,
+        resolution: &mut Resolution,
+    ) -> Result<i32, DiceRollingError> {
+        let name = &self.lookup(name);
+        let formula = match self.formulas.get(name) {
+            Some(formula) => formula,
+            None if self.strings.contains_key(name) => {
+                return Err(DiceRollingError::VariableNotNumeric(name.to_owned()))
+            }
+            None => {
+                return self.numbers.get(name).copied().ok_or_else(|| {
+                    DiceRollingError::VariableNotFound(name.to_owned(), self.suggest(name))
+                })
+            }
+        };
+
+        if let Some(value) = resolution.values.get(name) {
+            return Ok(*value);
+        }
+
+        let path = &mut resolution.path;
+        let in_cycle = path.iter().any(|visited| visited == name);
+        path.push(name.to_owned());
+
+        if in_cycle {
+            return Err(DiceRollingError::VariableCycle(path.join(" -> ")));
+        }
+
+        let formula = parse_formula(formula)
+            .map_err(|e| DiceRollingError::InvalidFormula(name.to_owned(), e))?;
+
+        let value = self.evaluate(&formula, resolution)?;
+        resolution.path.pop();
+        resolution.values.insert(name.to_owned(), value);
+        Ok(value)
+    }
+
+    fn evaluate(
+        &self,
+        formula: &Formula,
+        resolution: &mut Resolution,
+    ) -> Result<i32, DiceRollingError> {
+        use Formula::*;
+        let value = match formula {
+            Number(num) => Some(*num),
+            Variable(name) => Some(self.resolve_with_path(name, resolution)?),
+            Negate(inner) => self.evaluate(inner, resolution)?.checked_neg(),
+            Add(lhs, rhs) => self
+                .evaluate(lhs, resolution)?
+                .checked_add(self.evaluate(rhs, resolution)?),
+            Subtract(lhs, rhs) => self
+                .evaluate(lhs, resolution)?
+                .checked_sub(self.evaluate(rhs, resolution)?),
+            Multiply(lhs, rhs) => self
+                .evaluate(lhs, resolution)?
+                .checked_mul(self.evaluate(rhs, resolution)?),
+            Divide(lhs, rhs) => self
+                .evaluate(lhs, resolution)?
+                .checked_div(self.evaluate(rhs, resolution)?),
+            Min(args) | Max(args) => {
+                let values = args
+                    .iter()
+                    .map(|arg| self.evaluate(arg, resolution))
+                    .collect::<Result<Vec<_>, _>>()?
+                    .into_iter();
+
+                match formula {
+                    Min(_) => values.min(),
+                    _ => values.max(),
+                }
+            }
+        };
+
+        //Overflow, or division by zero.
+        value.ok_or(DiceRollingError::InvalidAmount)
+    }
+}
+
+/// State of resolving one variable (or dice amount). The path holds
+/// the formula variables currently being evaluated, to detect
+/// cycles, and the values of formulas already evaluated are kept, so
+/// that formulas used many times are only evaluated once.
+#[derive(Default)]
+struct Resolution {
+    path: Vec<String>,
+    values: HashMap<String, i32>,
+}
+
 /// Calculate the amount of dice to roll by consulting the database
 /// and replacing variables with corresponding the amount. Errors out
 /// if it cannot find a variable defined, or if the database errors.
@@ -24,27 +254,8 @@ pub async fn calculate_single_die_amount(
 /// and replacing variables with corresponding amounts. Errors out if
 /// it cannot find a variable defined, or if the database errors.
 pub async fn calculate_dice_amount(amounts: &[Amount], ctx: &Context<'_>) -> Result<i32, BotError> {
-    let stream = stream::iter(amounts);
-    let variables = &ctx
-        .db
-        .get_user_variables(&ctx.username, ctx.active_room_id().as_str())
-        .await?;
-
-    use DiceRollingError::VariableNotFound;
-    let dice_amount: i32 = stream
-        .then(|amount| async move {
-            match &amount.element {
-                Element::Number(num_dice) => Ok(num_dice * amount.operator.mult()),
-                Element::Variable(variable) => variables
-                    .get(variable)
-                    .ok_or_else(|| VariableNotFound(variable.clone()))
-                    .map(|i| *i),
-            }
-        })
-        .try_fold(0, |total, num_dice| async move { Ok(total + num_dice) })
-        .await?;
-
-    Ok(dice_amount)
+    let variables = VariableResolver::load(ctx).await?;
+    Ok(variables.dice_amount(amounts)?)
 }
 
 /// Hash a password using the argon2 algorithm with a 16 byte salt.
@@ -128,4 +339,148 @@ mod tests {
         })
         .await;
     }
+
+    fn resolver(numbers: &[(&str, i32)], formulas: &[(&str, &str)]) -> VariableResolver {
+        VariableResolver {
+            numbers: numbers
+                .iter()
+                .map(|(name, value)| (name.to_string(), *value))
+                .collect(),
+            formulas: formulas
+                .iter()
+                .map(|(name, formula)| (name.to_string(), formula.to_string()))
+                .collect(),
+            strings: HashMap::new(),
+        }
+    }
+
+    #[test]
+    fn resolves_formula_variables() {
+        let variables = resolver(
+            &[("wits", 3), ("dex", 2), ("athletics", 2), ("composure", 3)],
+            &[
+                ("defense", "min(wits, dex) + athletics"),
+                ("initmod", "dex + composure"),
+                ("double", "initmod * 2"),
+            ],
+        );
+
+        assert_eq!(4, variables.resolve("defense").unwrap());
+        assert_eq!(5, variables.resolve("initmod").unwrap());
+        assert_eq!(10, variables.resolve("double").unwrap());
+        assert_eq!(3, variables.resolve("wits").unwrap());
+    }
+
+    #[test]
+    fn resolves_names_case_insensitively() {
+        let variables = resolver(&[("melee-weapons", 3)], &[("attack", "Melee-Weapons + 1")]);
+        assert_eq!(3, variables.resolve("MELEE-WEAPONS").unwrap());
+        assert_eq!(4, variables.resolve("Attack").unwrap());
+    }
+
+    #[test]
+    fn string_variables_are_not_numbers() {
+        let mut variables = resolver(&[], &[]);
+        variables
+            .strings
+            .insert("weapon".to_string(), "Longsword".to_string());
+
+        assert!(matches!(
+            variables.resolve("weapon"),
+            Err(DiceRollingError::VariableNotNumeric(_))
+        ));
+        assert_eq!(Some("Longsword"), variables.text("Weapon"));
+    }
+
+    #[test]
+    fn user_variables_come_before_room_variables() {
+        let mut variables = resolver(&[("difficulty", 2)], &[("pool", "dex + modifier")]);
+        variables.add_room_variables(
+            vec![("difficulty".to_string(), 5), ("dex".to_string(), 1)]
+                .into_iter()
+                .collect(),
+            vec![("modifier".to_string(), "difficulty - 3".to_string())]
+                .into_iter()
+                .collect(),
+            HashMap::new(),
+        );
+
+        assert_eq!(2, variables.resolve("difficulty").unwrap());
+        assert_eq!(5, variables.resolve("room.difficulty").unwrap());
+        assert_eq!(Some("difficulty - 3"), variables.formula("modifier"));
+
+        //The room formula refers to the user's difficulty, because it
+        //is evaluated for the user.
+        assert_eq!(0, variables.resolve("pool").unwrap());
+    }
+
+    #[test]
+    fn detects_formula_cycles() {
+        let variables = resolver(&[], &[("a", "b + 1"), ("b", "c"), ("c", "a - 1")]);
+        let result = variables.resolve("a");
+        assert!(matches!(
+            result,
+            Err(DiceRollingError::VariableCycle(ref path)) if path == "a -> b -> c -> a"
+        ));
+    }
+
+    #[test]
+    fn missing_variable_suggests_closest_name() {
+        let variables = resolver(&[("strength", 3), ("brawl", 2)], &[("defense", "dex")]);
+        let result = variables.resolve("strenght");
+        assert!(matches!(
+            result,
+            Err(DiceRollingError::VariableNotFound(ref name, Some(ref suggestion)))
+                if name == "strenght" && suggestion == "strength"
+        ));
+
+        let message = variables.resolve("firearms").unwrap_err().to_string();
+        assert_eq!("variable not found: firearms", message);
+    }
+
+    #[test]
+    fn formula_with_missing_variable() {
+        let variables = resolver(&[], &[("defense", "wits + athletics")]);
+        let result = variables.resolve("defense");
+        assert!(
+            matches!(result, Err(DiceRollingError::VariableNotFound(ref name, _)) if name == "wits")
+        );
+    }
+
+    #[test]
+    fn formula_division_by_zero() {
+        let variables = resolver(&[("zero", 0)], &[("bad", "10 / zero")]);
+        assert!(matches!(
+            variables.resolve("bad"),
+            Err(DiceRollingError::InvalidAmount)
+        ));
+    }
+
+    #[test]
+    fn formulas_used_many_times_are_evaluated_once() {
+        let formulas: Vec<(String, String)> = (1..=40)
+            .map(|n| (format!("a{}", n), format!("a{0} + a{0}", n - 1)))
+            .collect();
+
+        let formulas: Vec<(&str, &str)> = formulas
+            .iter()
+            .map(|(name, formula)| (name.as_str(), formula.as_str()))
+            .collect();
+
+        let variables = resolver(&[("a0", 0)], &formulas);
+        assert_eq!(0, variables.resolve("a40").unwrap());
+
+        let amounts = crate::parser::dice::parse_amounts("a40 + a40").unwrap().0;
+        assert_eq!(0, variables.dice_amount(&amounts).unwrap());
+    }
+
+    #[test]
+    fn dice_amount_subtracts_variables() {
+        let variables = resolver(&[("dex", 3), ("wounds", 2)], &[("pool", "dex + 4")]);
+        let amounts = crate::parser::dice::parse_amounts("pool - wounds - 1")
+            .unwrap()
+            .0;
+
+        assert_eq!(4, variables.dice_amount(&amounts).unwrap());
+    }
 }
@@ -1,15 +1,24 @@
+use std::io::{Cursor, Read};
 use std::path::PathBuf;
 
 use futures::stream::{self, StreamExt, TryStreamExt};
 use log::error;
-use matrix_sdk::ruma::events::room::message::{InReplyTo, MessageEventContent, Relation};
-use matrix_sdk::ruma::events::AnyMessageEventContent;
-use matrix_sdk::ruma::{EventId, RoomId, UserId};
+use matrix_sdk::ruma::api::client::r0::room::get_room_event;
+use matrix_sdk::ruma::events::room::message::{
+    InReplyTo, MessageEventContent, MessageType, Relation,
+};
+use matrix_sdk::ruma::events::{
+    AnyMessageEvent, AnyMessageEventContent, AnyRoomEvent, MessageEvent,
+};
+use matrix_sdk::ruma::{EventId, MxcUri, RoomId, UserId};
 use matrix_sdk::Client;
 use matrix_sdk::Error as MatrixError;
+use matrix_sdk::{reqwest, HttpError};
 use matrix_sdk::{room::Joined, ClientConfig};
+use matrix_sdk_crypto::AttachmentDecryptor;
 use url::Url;
 
+use crate::commands::Attachment;
 use crate::{config::Config, error::BotError};
 
 fn cache_dir() -> Result<PathBuf, BotError> {
@@ -54,6 +63,31 @@ pub async fn get_users_in_room(
     }
 }
 
+/// The power level of a user in a room. Users who are not members of
+/// the room, and rooms the bot is not in, have power level 0.
+pub async fn get_power_level(
+    client: &Client,
+    room_id: &RoomId,
+    user: &UserId,
+) -> Result<i64, MatrixError> {
+    let member = match client.get_joined_room(room_id) {
+        Some(room) => room.get_member(user).await?,
+        None => None,
+    };
+
+    Ok(member.map(|member| member.power_level()).unwrap_or(0))
+}
+
+/// The room that the given room replaced when it was upgraded, if
+/// any.
+pub fn get_room_predecessor(client: &Client, room_id: &RoomId) -> Option<RoomId> {
+    client
+        .get_joined_room(room_id)
+        .and_then(|room| room.create_content())
+        .and_then(|create| create.predecessor)
+        .map(|predecessor| predecessor.room_id)
+}
+
 pub async fn get_rooms_for_user(
     client: &Client,
     user: &UserId,
@@ -107,3 +141,130 @@ pub async fn send_message(
         error!("Error sending html: {}", html);
     };
 }
+
+/// Upload a file to the room, as a file message.
+pub async fn send_file(client: &Client, room_id: &RoomId, attachment: &Attachment) {
+    let room = match client.get_joined_room(room_id) {
+        Some(room) => room,
+        _ => return,
+    };
+
+    let mut data = attachment.data.as_slice();
+    let result = room
+        .send_attachment(
+            &attachment.file_name,
+            &attachment.content_type,
+            &mut data,
+            None,
+        )
+        .await;
+
+    if let Err(e) = result {
+        let message = extract_error_message(e);
+        error!("Error sending file: {}", message);
+    };
+}
+
+/// Largest file that will be downloaded, in bytes. Only small files
+/// like variable exports are ever downloaded.
+const MAX_DOWNLOAD_SIZE: u64 = 256 * 1024;
+
+/// Download media from the homeserver, and give up as soon as it is
+/// larger than MAX_DOWNLOAD_SIZE, whatever size the sender stated.
+async fn download_limited(client: &Client, uri: &MxcUri) -> Result<Vec<u8>, BotError> {
+    let (server_name, media_id) = uri.parts().ok_or(BotError::NotAFileMessage)?;
+    let mut url = client.homeserver().await;
+    if let Ok(mut path) = url.path_segments_mut() {
+        path.pop_if_empty()
+            .extend(&["_matrix", "media", "r0", "download"])
+            .extend(&[server_name.as_str(), media_id]);
+    }
+
+    let http_error = |e| BotError::from(MatrixError::from(HttpError::from(e)));
+    let mut response = reqwest::get(url)
+        .await
+        .and_then(reqwest::Response::error_for_status)
+        .map_err(http_error)?;
+
+    let too_large = BotError::FileTooLarge(MAX_DOWNLOAD_SIZE / 1024);
+    if matches!(response.content_length(), Some(length) if length > MAX_DOWNLOAD_SIZE) {
+        return Err(too_large);
+    }
+
+    let mut data = Vec::new();
+    while let Some(chunk) = response.chunk().await.map_err(http_error)? {
+        if (data.len() + chunk.len()) as u64 > MAX_DOWNLOAD_SIZE {
+            return Err(too_large);
+        }
+
+        data.extend_from_slice(&chunk);
+    }
+
+    Ok(data)
+}
+
+/// Download the file of a file message in a room. Returns the file
+/// name and contents, or None if the event is not a file message.
+/// Files that do not state their size, or are larger than
+/// MAX_DOWNLOAD_SIZE, are refused before downloading them, and the
+/// download stops as soon as it goes over MAX_DOWNLOAD_SIZE.
+pub async fn download_file(
+    client: &Client,
+    room_id: &RoomId,
+    event_id: &EventId,
+) -> Result<Option<(String, Vec<u8>)>, BotError> {
+    let room = client
+        .get_joined_room(room_id)
+        .ok_or(BotError::RoomDoesNotExist)?;
+
+    let request = get_room_event::Request::new(room_id, event_id);
+    let event = room
+        .event(request)
+        .await?
+        .event
+        .deserialize()
+        .map_err(|_| BotError::NotAFileMessage)?;
+
+    let file = match event {
+        AnyRoomEvent::Message(AnyMessageEvent::RoomMessage(MessageEvent {
+            content:
+                MessageEventContent {
+                    msgtype: MessageType::File(file),
+                    ..
+                },
+            ..
+        })) => file,
+        _ => return Ok(None),
+    };
+
+    let size = file.info.as_ref().and_then(|info| info.size).map(u64::from);
+    match size {
+        Some(size) if size <= MAX_DOWNLOAD_SIZE => (),
+        _ => return Err(BotError::FileTooLarge(MAX_DOWNLOAD_SIZE / 1024)),
+    }
+
+    let data = match (&file.file, &file.url) {
+        (Some(encrypted), _) => {
+            let data = download_limited(client, &encrypted.url).await?;
+            let info = encrypted.as_ref().clone().into();
+            let mut cursor = Cursor::new(data);
+            let mut decryptor =
+                AttachmentDecryptor::new(&mut cursor, info).map_err(MatrixError::from)?;
+
+            let mut decrypted = Vec::new();
+            decryptor.read_to_end(&mut decrypted)?;
+            decrypted
+        }
+        (None, Some(url)) => download_limited(client, url).await?,
+        (None, None) => return Ok(None),
+    };
+
+    //Decryption does not change the size, but check the final data
+    //anyway.
+    if data.len() as u64 > MAX_DOWNLOAD_SIZE {
+        return Err(BotError::FileTooLarge(MAX_DOWNLOAD_SIZE / 1024));
+    }
+
+    let file_name = file.filename.unwrap_or(file.body);
+    Ok(Some((file_name, data)))
+}
@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
 
 /// RoomInfo has basic metadata about a room: its name, ID, etc.
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
@@ -7,6 +9,208 @@ pub struct RoomInfo {
     pub room_name: String,
 }
 
+/// Optional bounds on a variable's value, enforced when the variable
+/// is incremented or decremented.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, Default)]
+pub struct VariableBounds {
+    pub min: Option<i32>,
+    pub max: Option<i32>,
+
+    /// Refuse updates that would go out of bounds, instead of
+    /// clamping the value.
+    pub refuse: bool,
+}
+
+impl VariableBounds {
+    /// Clamp a value to the bounds.
+    pub fn clamp(&self, value: i32) -> i32 {
+        let value = self.max.map_or(value, |max| std::cmp::min(value, max));
+        self.min.map_or(value, |min| std::cmp::max(value, min))
+    }
+}
+
+/// All of a user's variables in a room, by name: numbers, formulas
+/// and strings. This is also the schema of imported and exported
+/// variable files.
+#[derive(Serialize, Deserialize, Eq, PartialEq, Clone, Debug, Default)]
+#[serde(default)]
+pub struct VariableSet {
+    pub variables: BTreeMap<String, i32>,
+    pub formulas: BTreeMap<String, String>,
+    pub strings: BTreeMap<String, String>,
+}
+
+impl VariableSet {
+    pub fn contains(&self, name: &str) -> bool {
+        self.variables.contains_key(name)
+            || self.formulas.contains_key(name)
+            || self.strings.contains_key(name)
+    }
+
+    pub fn len(&self) -> usize {
+        self.variables.len() + self.formulas.len() + self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Names of all variables, in alphabetical order.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self
+            .variables
+            .keys()
+            .chain(self.formulas.keys())
+            .chain(self.strings.keys())
+            .map(String::as_str)
+            .collect();
+
+        names.sort_unstable();
+        names
+    }
+}
+
+/// A value stored in a variable: a number, a formula or a string.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub enum StoredValue {
+    Number(i32),
+    Formula(String),
+    Text(String),
+}
+
+/// Shown the same way the value is given to !set.
+impl fmt::Display for StoredValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StoredValue::Number(number) => write!(f, "{}", number),
+            StoredValue::Formula(formula) => write!(f, "= {}", formula),
+            StoredValue::Text(text) => write!(f, "\"{}\"", text),
+        }
+    }
+}
+
+/// Who is changing variables, and through which command or RPC
+/// method. Recorded in the variable history.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct ChangeSource {
+    /// The user making the change. If not known, the owner of the
+    /// variable is recorded.
+    pub changed_by: Option<String>,
+    pub via: String,
+}
+
+impl Default for ChangeSource {
+    fn default() -> Self {
+        ChangeSource {
+            changed_by: None,
+            via: "bot".to_string(),
+        }
+    }
+}
+
+/// A recorded change to a variable. No old value means the variable
+/// was created, and no new value means it was deleted.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct VariableChange {
+    pub key: String,
+    pub old_value: Option<StoredValue>,
+    pub new_value: Option<StoredValue>,
+    pub changed_by: String,
+    pub via: String,
+
+    /// Seconds since the UNIX epoch.
+    pub changed_at: i64,
+}
+
+/// Settings of the bot in a room.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct RoomSettings {
+    /// Whether dice expressions between the inline roll markers in
+    /// normal messages are rolled. Off unless a moderator turns it
+    /// on, so normal chat is never rolled by surprise.
+    pub inline_rolls: bool,
+    pub inline_roll_open: String,
+    pub inline_roll_close: String,
+
+    /// What commands start with in the room, instead of the prefix
+    /// in the config.
+    pub command_prefix: Option<String>,
+}
+
+impl Default for RoomSettings {
+    fn default() -> Self {
+        RoomSettings {
+            inline_rolls: false,
+            inline_roll_open: "[[".to_string(),
+            inline_roll_close: "]]".to_string(),
+            command_prefix: None,
+        }
+    }
+}
+
+/// What a user is allowed to do in a room, from least to most
+/// allowed. Each role can also do everything that the roles before it
+/// can do.
+#[derive(Eq, PartialEq, Ord, PartialOrd, Clone, Copy, Debug, sqlx::Type)]
+#[sqlx(rename_all = "snake_case")]
+pub enum Role {
+    Player,
+
+    /// The game master of the room, i.e. a room moderator.
+    GameMaster,
+    RoomAdmin,
+
+    /// Listed as a bot admin in the config, in every room.
+    BotAdmin,
+}
+
+/// Power level needed in a room to be its game master. This is the
+/// default power level of moderators.
+pub const GAME_MASTER_POWER_LEVEL: i64 = 50;
+
+/// Power level needed in a room to be a room admin. This is the
+/// default power level of admins.
+pub const ROOM_ADMIN_POWER_LEVEL: i64 = 100;
+
+impl Role {
+    /// The role of a user with the given power level in a room.
+    pub fn from_power_level(power_level: i64) -> Role {
+        match power_level {
+            level if level >= ROOM_ADMIN_POWER_LEVEL => Role::RoomAdmin,
+            level if level >= GAME_MASTER_POWER_LEVEL => Role::GameMaster,
+            _ => Role::Player,
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Role::Player => write!(f, "player"),
+            Role::GameMaster => write!(f, "GM"),
+            Role::RoomAdmin => write!(f, "room admin"),
+            Role::BotAdmin => write!(f, "bot admin"),
+        }
+    }
+}
+
+/// Whether a macro belongs to a user, or is shared by everyone in a
+/// room.
+#[derive(Eq, PartialEq, Clone, Copy, Debug, sqlx::Type)]
+#[sqlx(rename_all = "snake_case")]
+pub enum MacroScope {
+    User,
+    Room,
+}
+
+/// A named list of commands, run one after the other.
+#[derive(Eq, PartialEq, Clone, Debug)]
+pub struct Macro {
+    pub name: String,
+    pub scope: MacroScope,
+    pub commands: Vec<String>,
+}
+
 #[derive(Eq, PartialEq, Clone, Copy, Debug, sqlx::Type)]
 #[sqlx(rename_all = "snake_case")]
 pub enum AccountStatus {
@@ -1,5 +1,6 @@
+use super::variables::variable_name;
 use combine::error::ParseError;
-use combine::parser::char::{digit, letter, spaces};
+use combine::parser::char::{digit, spaces};
 use combine::stream::Stream;
 use combine::{many, many1, one_of, Parser};
 use thiserror::Error;
@@ -100,7 +101,7 @@ where
         }
     };
 
-    many1(letter())
+    variable_name()
         .or(many1(digit()))
         .skip(spaces().silent()) //Consume any space after first amount
         .map(map_first_amount)
@@ -130,7 +131,7 @@ where
         }
     };
 
-    let parse_element = many1(letter()).or(many1(digit())).map(map_element);
+    let parse_element = variable_name().or(many1(digit())).map(map_element);
 
     let element_parser = parse_operator
         .skip(spaces().silent())
@@ -315,4 +316,34 @@ mod parse_many_amounts_tests {
     fn parse_complex_amount_expression() {
         assert!(parse_amounts("1 + myvariable - 2").is_ok());
     }
+
+    #[test]
+    fn parse_variable_names_with_dashes_test() {
+        let variable = |name: &str, operator| Amount {
+            operator,
+            element: Element::Variable(name.to_string()),
+        };
+
+        let (amounts, rest) = parse_amounts("melee-weapons + str_mod2").unwrap();
+        assert_eq!("", rest);
+        assert_eq!(
+            vec![
+                variable("melee-weapons", Operator::Plus),
+                variable("str_mod2", Operator::Plus)
+            ],
+            amounts
+        );
+
+        let (amounts, _) = parse_amounts("str-2").unwrap();
+        assert_eq!(
+            vec![
+                variable("str", Operator::Plus),
+                Amount {
+                    operator: Operator::Minus,
+                    element: Element::Number(2)
+                }
+            ],
+            amounts
+        );
+    }
 }
@@ -0,0 +1,372 @@
+use std::iter::Peekable;
+use std::str::Chars;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum FormulaParsingError {
+    #[error("formula ended unexpectedly")]
+    UnexpectedEnd,
+
+    #[error("unexpected character in formula: {0}")]
+    UnexpectedCharacter(char),
+
+    #[error("unknown function in formula: {0}")]
+    UnknownFunction(String),
+
+    #[error("number in formula is too large")]
+    ConversionError,
+
+    #[error("formula is longer than {0} characters")]
+    TooLong(usize),
+
+    #[error("formula is nested more than {0} levels deep")]
+    TooDeep(usize),
+}
+
+/// Longest formula that can be parsed.
+pub const MAX_FORMULA_LENGTH: usize = 256;
+
+/// How deeply parentheses, function calls and negations can be
+/// nested in a formula, which keeps the parser from running out of
+/// stack.
+pub const MAX_FORMULA_DEPTH: usize = 32;
+
+type ParseResult<T> = Result<T, FormulaParsingError>;
+
+/// A formula variable's expression, e.g. "min(wits, dex) + athletics".
+/// Formulas are parsed when they are stored, but only evaluated when
+/// the variable is used, so they always see the latest values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Formula {
+    Number(i32),
+    Variable(String),
+    Negate(Box<Formula>),
+    Add(Box<Formula>, Box<Formula>),
+    Subtract(Box<Formula>, Box<Formula>),
+    Multiply(Box<Formula>, Box<Formula>),
+    Divide(Box<Formula>, Box<Formula>),
+    Min(Vec<Formula>),
+    Max(Vec<Formula>),
+}
+
+/// Simple recursive descent parser over the formula text.
+///   expr   := term (("+" | "-") term)*
+///   term   := factor (("*" | "/") factor)*
+///   factor := number | name | name "(" expr ("," expr)* ")"
+///             | "(" expr ")" | "-" factor
+struct FormulaParser<'a> {
+    input: Peekable<Chars<'a>>,
+    depth: usize,
+}
+
+impl FormulaParser<'_> {
+    fn skip_spaces(&mut self) {
+        while matches!(self.input.peek(), Some(c) if c.is_whitespace()) {
+            self.input.next();
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_spaces();
+        self.input.peek().copied()
+    }
+
+    fn expect(&mut self, expected: char) -> ParseResult<()> {
+        match self.peek() {
+            Some(c) if c == expected => {
+                self.input.next();
+                Ok(())
+            }
+            Some(c) => Err(FormulaParsingError::UnexpectedCharacter(c)),
+            None => Err(FormulaParsingError::UnexpectedEnd),
+        }
+    }
+
+    fn take_while(&mut self, pred: impl Fn(char) -> bool) -> String {
+        let mut value = String::new();
+        while let Some(&c) = self.input.peek() {
+            if !pred(c) {
+                break;
+            }
+
+            value.push(c);
+            self.input.next();
+        }
+
+        value
+    }
+
+    /// Names follow the same rules as variable names elsewhere: a
+    /// dash is only part of the name if a letter follows it, and
+    /// names can have the room variable prefix.
+    fn name(&mut self) -> String {
+        let mut name = String::new();
+        while let Some(&c) = self.input.peek() {
+            let letter_follows = || {
+                let mut ahead = self.input.clone();
+                ahead.next();
+                matches!(ahead.peek(), Some(next) if next.is_alphabetic())
+            };
+
+            let continues = match c {
+                '-' => letter_follows(),
+                '.' => name.eq_ignore_ascii_case("room") && letter_follows(),
+                _ => c.is_alphanumeric() || c == '_',
+            };
+
+            if !continues {
+                break;
+            }
+
+            name.push(c);
+            self.input.next();
+        }
+
+        name
+    }
+
+    /// Parse a part of the formula one level deeper, e.g. between
+    /// parentheses.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> ParseResult<T>) -> ParseResult<T> {
+        if self.depth >= MAX_FORMULA_DEPTH {
+            return Err(FormulaParsingError::TooDeep(MAX_FORMULA_DEPTH));
+        }
+
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn expr(&mut self) -> ParseResult<Formula> {
+        let mut formula = self.term()?;
+        loop {
+            formula = match self.peek() {
+                Some('+') => {
+                    self.input.next();
+                    Formula::Add(Box::new(formula), Box::new(self.term()?))
+                }
+                Some('-') => {
+                    self.input.next();
+                    Formula::Subtract(Box::new(formula), Box::new(self.term()?))
+                }
+                _ => return Ok(formula),
+            };
+        }
+    }
+
+    fn term(&mut self) -> ParseResult<Formula> {
+        let mut formula = self.factor()?;
+        loop {
+            formula = match self.peek() {
+                Some('*') => {
+                    self.input.next();
+                    Formula::Multiply(Box::new(formula), Box::new(self.factor()?))
+                }
+                Some('/') => {
+                    self.input.next();
+                    Formula::Divide(Box::new(formula), Box::new(self.factor()?))
+                }
+                _ => return Ok(formula),
+            };
+        }
+    }
+
+    fn arguments(&mut self) -> ParseResult<Vec<Formula>> {
+        self.expect('(')?;
+        let mut args = vec![self.nested(Self::expr)?];
+        while self.peek() == Some(',') {
+            self.input.next();
+            args.push(self.nested(Self::expr)?);
+        }
+
+        self.expect(')')?;
+        Ok(args)
+    }
+
+    fn factor(&mut self) -> ParseResult<Formula> {
+        match self.peek() {
+            Some('-') => {
+                self.input.next();
+                Ok(Formula::Negate(Box::new(self.nested(Self::factor)?)))
+            }
+            Some('(') => {
+                self.input.next();
+                let formula = self.nested(Self::expr)?;
+                self.expect(')')?;
+                Ok(formula)
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let number = self.take_while(|c| c.is_ascii_digit());
+                number
+                    .parse()
+                    .map(Formula::Number)
+                    .map_err(|_| FormulaParsingError::ConversionError)
+            }
+            Some(c) if c.is_alphabetic() => {
+                let name = self.name();
+                if self.peek() != Some('(') {
+                    return Ok(Formula::Variable(name));
+                }
+
+                match name.to_lowercase().as_ref() {
+                    "min" => Ok(Formula::Min(self.arguments()?)),
+                    "max" => Ok(Formula::Max(self.arguments()?)),
+                    _ => Err(FormulaParsingError::UnknownFunction(name)),
+                }
+            }
+            Some(c) => Err(FormulaParsingError::UnexpectedCharacter(c)),
+            None => Err(FormulaParsingError::UnexpectedEnd),
+        }
+    }
+}
+
+/// Parse a formula such as "min(wits, dex) + athletics". Supports
+/// numbers, variables, + - * / (integer division), parentheses, and
+/// the min and max functions.
+pub fn parse_formula(input: &str) -> Result<Formula, FormulaParsingError> {
+    if input.chars().count() > MAX_FORMULA_LENGTH {
+        return Err(FormulaParsingError::TooLong(MAX_FORMULA_LENGTH));
+    }
+
+    let mut parser = FormulaParser {
+        input: input.chars().peekable(),
+        depth: 0,
+    };
+
+    let formula = parser.expr()?;
+    match parser.peek() {
+        Some(c) => Err(FormulaParsingError::UnexpectedCharacter(c)),
+        None => Ok(formula),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Formula::*;
+
+    fn var(name: &str) -> Box<Formula> {
+        Box::new(Variable(name.to_owned()))
+    }
+
+    #[test]
+    fn parses_sum_of_variables() {
+        assert_eq!(
+            Ok(Add(var("dex"), var("composure"))),
+            parse_formula("dex + composure")
+        );
+    }
+
+    #[test]
+    fn parses_min_function() {
+        let expected = Add(
+            Box::new(Min(vec![*var("wits"), *var("dex")])),
+            var("athletics"),
+        );
+
+        assert_eq!(Ok(expected), parse_formula("min(wits, dex) + athletics"));
+    }
+
+    #[test]
+    fn multiplication_binds_tighter_than_addition() {
+        let expected = Subtract(
+            Box::new(Number(1)),
+            Box::new(Divide(var("pow"), Box::new(Number(5)))),
+        );
+
+        assert_eq!(Ok(expected), parse_formula("1 - pow / 5"));
+    }
+
+    #[test]
+    fn parses_parentheses_and_negation() {
+        let expected = Multiply(
+            Box::new(Negate(Box::new(Number(2)))),
+            Box::new(Add(var("a"), var("b"))),
+        );
+
+        assert_eq!(Ok(expected), parse_formula("-2 * (a + b)"));
+    }
+
+    #[test]
+    fn parses_names_with_dashes_and_digits() {
+        assert_eq!(
+            Ok(Subtract(var("melee-weapons"), var("str_mod2"))),
+            parse_formula("melee-weapons - str_mod2")
+        );
+
+        assert_eq!(
+            Ok(Subtract(var("str"), Box::new(Number(2)))),
+            parse_formula("str-2")
+        );
+    }
+
+    #[test]
+    fn parses_room_variables() {
+        assert_eq!(
+            Ok(Add(var("room.difficulty"), var("scene"))),
+            parse_formula("room.difficulty + scene")
+        );
+
+        assert_eq!(
+            Err(FormulaParsingError::UnexpectedCharacter('.')),
+            parse_formula("hall.difficulty")
+        );
+    }
+
+    #[test]
+    fn rejects_bad_formulas() {
+        assert_eq!(
+            Err(FormulaParsingError::UnknownFunction("avg".to_owned())),
+            parse_formula("avg(a, b)")
+        );
+
+        assert_eq!(
+            Err(FormulaParsingError::UnexpectedEnd),
+            parse_formula("a +")
+        );
+
+        assert_eq!(
+            Err(FormulaParsingError::UnexpectedCharacter(')')),
+            parse_formula("a + b)")
+        );
+    }
+
+    #[test]
+    fn rejects_deeply_nested_formulas() {
+        let nested = |open: &str, close: &str, depth: usize| {
+            format!("{}1{}", open.repeat(depth), close.repeat(depth))
+        };
+
+        assert_eq!(Ok(Number(1)), parse_formula(&nested("(", ")", 32)));
+        assert_eq!(
+            Err(FormulaParsingError::TooDeep(MAX_FORMULA_DEPTH)),
+            parse_formula(&nested("(", ")", 33))
+        );
+
+        assert_eq!(
+            Err(FormulaParsingError::TooDeep(MAX_FORMULA_DEPTH)),
+            parse_formula(&nested("-", "", 100))
+        );
+
+        assert_eq!(
+            Err(FormulaParsingError::TooDeep(MAX_FORMULA_DEPTH)),
+            parse_formula(&nested("max(", ")", 40))
+        );
+    }
+
+    #[test]
+    fn rejects_long_formulas() {
+        let formula = vec!["a"; 129].join("+");
+        assert_eq!(
+            Err(FormulaParsingError::TooLong(MAX_FORMULA_LENGTH)),
+            parse_formula(&formula)
+        );
+
+        let stack_buster = "(".repeat(100_000);
+        assert_eq!(
+            Err(FormulaParsingError::TooLong(MAX_FORMULA_LENGTH)),
+            parse_formula(&stack_buster)
+        );
+    }
+}
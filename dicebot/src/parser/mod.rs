@@ -1,2 +1,3 @@
 pub mod dice;
+pub mod formula;
 pub mod variables;
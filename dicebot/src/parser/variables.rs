@@ -1,10 +1,22 @@
-use combine::parser::char::{char, digit, letter, spaces};
-use combine::{many1, optional, Parser};
+use super::formula::{parse_formula, FormulaParsingError};
+use combine::error::ParseError;
+use combine::parser::char::{alpha_num, char, letter, spaces, string_cmp};
+use combine::stream::Stream;
+use combine::{attempt, look_ahead, many, optional, Parser};
 use thiserror::Error;
 
-enum ParsedValue {
-    Valid(i32),
-    Invalid,
+/// The value of a variable being set: either a plain number, a
+/// formula that is evaluated whenever the variable is used, or a
+/// string (e.g. a weapon name or a condition).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum VariableValue {
+    Number(i32),
+    Formula(String),
+    Text(String),
+
+    /// Add to the current value (negative to subtract), as in
+    /// "hp -= 3".
+    Adjust(i32),
 }
 
 #[derive(Error, Debug)]
@@ -12,6 +24,9 @@ pub enum VariableParsingError {
     #[error("invalid variable value, must be a number")]
     InvalidValue,
 
+    #[error("invalid formula: {0}")]
+    InvalidFormula(#[from] FormulaParsingError),
+
     #[error("unconsumed input")]
     UnconsumedInput,
 
@@ -19,35 +34,85 @@ pub enum VariableParsingError {
     InternalParseError(#[from] combine::error::StringStreamError),
 }
 
-pub fn parse_set_variable(input: &str) -> Result<(String, i32), VariableParsingError> {
-    let name = many1(letter()).map(|value: String| value);
-
-    let maybe_minus = optional(char('-')).map(|value: Option<char>| match value {
-        Some(minus_sign) => String::from(minus_sign),
-        _ => "".to_owned(),
-    });
-
-    let value = maybe_minus
-        .and(many1(digit()))
-        .map(|value: (String, String)| {
-            let number = format!("{}{}", value.0, value.1);
-            match number.parse::<i32>() {
-                Ok(num) => ParsedValue::Valid(num),
-                _ => ParsedValue::Invalid,
-            }
-        });
-
-    let mut parser = name.skip(spaces().silent()).and(value);
-    let (result, rest) = parser.parse(input)?;
-
-    if rest.len() == 0 {
-        match result {
-            (variable_name, ParsedValue::Valid(value)) => Ok((variable_name, value)),
-            _ => Err(VariableParsingError::InvalidValue),
+/// Prefix of variable names that refer to room variables, instead of
+/// the user's own variables.
+pub const ROOM_PREFIX: &str = "room.";
+
+/// If the variable name refers to a room variable ("room.difficulty"),
+/// the name of the room variable without the prefix.
+pub fn room_variable_name(name: &str) -> Option<&str> {
+    match name.get(..ROOM_PREFIX.len()) {
+        Some(prefix) if prefix.eq_ignore_ascii_case(ROOM_PREFIX) => {
+            Some(&name[ROOM_PREFIX.len()..])
         }
-    } else {
-        Err(VariableParsingError::UnconsumedInput)
+        _ => None,
+    }
+}
+
+/// Parser for variable names. Names start with a letter, followed by
+/// any letters, digits, underscores, and dashes. A dash is only part
+/// of the name if a letter follows it, so "str-2" is still "str"
+/// minus 2, while "melee-weapons" is a single name. Names can have
+/// the room prefix to refer to room variables.
+pub fn variable_name<Input>() -> impl Parser<Input, Output = String>
+where
+    Input: Stream<Token = char>,
+    Input::Error: ParseError<Input::Token, Input::Range, Input::Position>,
+{
+    let prefix = attempt(string_cmp(ROOM_PREFIX, |l, r| l.eq_ignore_ascii_case(&r)));
+    let dash = attempt(char('-').skip(look_ahead(letter())));
+    let rest = many(alpha_num().or(char('_')).or(dash));
+
+    optional(prefix).and(letter()).and(rest).map(
+        |((prefix, first), rest): ((Option<&str>, char), String)| {
+            format!("{}{}{}", prefix.unwrap_or_default(), first, rest)
+        },
+    )
+}
+
+/// Parse the name and value of a variable to set. The value is either
+/// a number ("myvar 5"), or a formula, optionally after an equals
+/// sign ("defense = min(wits, dex) + athletics"). The value can also
+/// be adjusted with += or -= ("hp -= 3"). Quoted values are strings
+/// ("weapon \"Longsword\"").
+pub fn parse_set_variable(input: &str) -> Result<(String, VariableValue), VariableParsingError> {
+    let (variable_name, rest) = variable_name().skip(spaces().silent()).parse(input)?;
+
+    let adjustment = match rest.get(..2) {
+        Some("+=") => Some(1),
+        Some("-=") => Some(-1),
+        _ => None,
+    };
+
+    if let Some(sign) = adjustment {
+        return match rest[2..].trim().parse::<i32>() {
+            Ok(amount) if amount >= 0 => Ok((variable_name, VariableValue::Adjust(sign * amount))),
+            _ => Err(VariableParsingError::InvalidValue),
+        };
+    }
+
+    let rest = rest.strip_prefix('=').unwrap_or(rest).trim();
+    if rest.is_empty() {
+        return Err(VariableParsingError::InvalidValue);
+    }
+
+    let text = rest
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'));
+    if let Some(text) = text {
+        return Ok((variable_name, VariableValue::Text(text.to_owned())));
+    }
+
+    let number = rest.strip_prefix('-').unwrap_or(rest);
+    if number.chars().all(|c| c.is_ascii_digit()) {
+        return match rest.parse::<i32>() {
+            Ok(value) => Ok((variable_name, VariableValue::Number(value))),
+            _ => Err(VariableParsingError::InvalidValue),
+        };
     }
+
+    parse_formula(rest)?;
+    Ok((variable_name, VariableValue::Formula(rest.to_owned())))
 }
 
 #[cfg(test)]
@@ -58,13 +123,114 @@ mod tests {
     fn parse_postive_number() {
         let result = parse_set_variable("myvar 5");
         assert!(result.is_ok());
-        assert_eq!(("myvar".to_string(), 5), result.unwrap());
+        assert_eq!(
+            ("myvar".to_string(), VariableValue::Number(5)),
+            result.unwrap()
+        );
     }
 
     #[test]
     fn parse_negative_number() {
         let result = parse_set_variable("myvar -5");
         assert!(result.is_ok());
-        assert_eq!(("myvar".to_string(), -5), result.unwrap());
+        assert_eq!(
+            ("myvar".to_string(), VariableValue::Number(-5)),
+            result.unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_number_with_equals() {
+        let result = parse_set_variable("myvar = 3");
+        assert_eq!(
+            ("myvar".to_string(), VariableValue::Number(3)),
+            result.unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_formula_value() {
+        let result = parse_set_variable("defense = min(wits, dex) + athletics");
+        assert_eq!(
+            (
+                "defense".to_string(),
+                VariableValue::Formula("min(wits, dex) + athletics".to_string())
+            ),
+            result.unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_adjustments() {
+        let result = parse_set_variable("hp -= 3");
+        assert_eq!(
+            ("hp".to_string(), VariableValue::Adjust(-3)),
+            result.unwrap()
+        );
+
+        let result = parse_set_variable("ammo += 12");
+        assert_eq!(
+            ("ammo".to_string(), VariableValue::Adjust(12)),
+            result.unwrap()
+        );
+
+        assert!(parse_set_variable("hp -= -3").is_err());
+        assert!(parse_set_variable("hp += x").is_err());
+    }
+
+    #[test]
+    fn parse_names_with_digits_underscores_and_dashes() {
+        let parse_name = |input: &str| parse_set_variable(input).map(|(name, _)| name).ok();
+
+        assert_eq!(Some("str_mod".to_string()), parse_name("str_mod 2"));
+        assert_eq!(Some("firearms2".to_string()), parse_name("firearms2 3"));
+        assert_eq!(
+            Some("Melee-Weapons".to_string()),
+            parse_name("Melee-Weapons 4")
+        );
+        assert_eq!(None, parse_name("2fast 4"));
+    }
+
+    #[test]
+    fn parse_room_variable_names() {
+        let parse_name = |input: &str| parse_set_variable(input).map(|(name, _)| name).ok();
+
+        assert_eq!(
+            Some("room.difficulty".to_string()),
+            parse_name("Room.difficulty 3")
+        );
+        assert_eq!(Some("roomy".to_string()), parse_name("roomy 3"));
+        assert_eq!(None, parse_name("room. 3"));
+
+        assert_eq!(Some("difficulty"), room_variable_name("ROOM.difficulty"));
+        assert_eq!(None, room_variable_name("difficulty"));
+    }
+
+    #[test]
+    fn parse_string_value() {
+        let result = parse_set_variable("weapon \"Longsword +1\"");
+        assert_eq!(
+            (
+                "weapon".to_string(),
+                VariableValue::Text("Longsword +1".to_string())
+            ),
+            result.unwrap()
+        );
+
+        let result = parse_set_variable("condition = \"prone\"");
+        assert_eq!(
+            (
+                "condition".to_string(),
+                VariableValue::Text("prone".to_string())
+            ),
+            result.unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_invalid_values() {
+        assert!(parse_set_variable("myvar").is_err());
+        assert!(parse_set_variable("myvar = 99999999999").is_err());
+        assert!(parse_set_variable("myvar = wits +").is_err());
     }
 }
@@ -1,5 +1,6 @@
 use crate::db::{errors::DataError, Variables};
 use crate::error::BotError;
+use crate::import_export::{self, ConflictMode, FileFormat, ImportError};
 use crate::matrix;
 use crate::{config::Config, db::sqlite::Database};
 use futures::stream;
@@ -12,6 +13,10 @@ use tenebrous_rpc::protos::dicebot::{
     dicebot_server::Dicebot, rooms_list_reply::Room, GetAllVariablesReply, GetAllVariablesRequest,
     RoomsListReply, SetVariableReply, SetVariableRequest, UserIdRequest,
 };
+use tenebrous_rpc::protos::dicebot::{
+    ConflictMode as RpcConflictMode, ExportVariablesReply, ExportVariablesRequest,
+    ImportVariablesReply, ImportVariablesRequest, VariableFormat,
+};
 use tenebrous_rpc::protos::dicebot::{GetVariableReply, GetVariableRequest};
 use tonic::{Code, Request, Response, Status};
 
@@ -27,6 +32,28 @@ impl From<DataError> for Status {
     }
 }
 
+impl From<ImportError> for Status {
+    fn from(error: ImportError) -> Status {
+        Status::new(Code::InvalidArgument, error.to_string())
+    }
+}
+
+fn file_format(format: i32) -> Option<FileFormat> {
+    match VariableFormat::from_i32(format)? {
+        VariableFormat::Json => Some(FileFormat::Json),
+        VariableFormat::Toml => Some(FileFormat::Toml),
+        VariableFormat::Csv => Some(FileFormat::Csv),
+    }
+}
+
+fn conflict_mode(mode: i32) -> Option<ConflictMode> {
+    match RpcConflictMode::from_i32(mode)? {
+        RpcConflictMode::Merge => Some(ConflictMode::Merge),
+        RpcConflictMode::Overwrite => Some(ConflictMode::Overwrite),
+        RpcConflictMode::Skip => Some(ConflictMode::Skip),
+    }
+}
+
 #[derive(Clone)]
 pub(super) struct DicebotRpcService {
     pub(super) config: Arc<Config>,
@@ -48,6 +75,7 @@ impl Dicebot for DicebotRpcService {
         } = request.into_inner();
 
         self.db
+            .with_change_source(&user_id, "rpc SetVariable")
             .set_user_variable(&user_id, &room_id, &variable_name, value)
             .await?;
 
@@ -77,7 +105,67 @@ impl Dicebot for DicebotRpcService {
             .get_user_variables(&request.user_id, &request.room_id)
             .await?;
 
-        Ok(Response::new(GetAllVariablesReply { variables }))
+        let string_variables = self
+            .db
+            .get_user_strings(&request.user_id, &request.room_id)
+            .await?;
+
+        let formulas = self
+            .db
+            .get_user_formulas(&request.user_id, &request.room_id)
+            .await?;
+
+        Ok(Response::new(GetAllVariablesReply {
+            variables,
+            string_variables,
+            formulas,
+        }))
+    }
+
+    async fn export_variables(
+        &self,
+        request: Request<ExportVariablesRequest>,
+    ) -> Result<Response<ExportVariablesReply>, Status> {
+        let request = request.into_inner();
+        let format = file_format(request.format)
+            .ok_or_else(|| Status::invalid_argument("unknown variable format"))?;
+        let variables =
+            import_export::load_variables(&self.db, &request.user_id, &request.room_id).await?;
+
+        let data = import_export::serialize(&variables, format)?;
+        Ok(Response::new(ExportVariablesReply { data }))
+    }
+
+    async fn import_variables(
+        &self,
+        request: Request<ImportVariablesRequest>,
+    ) -> Result<Response<ImportVariablesReply>, Status> {
+        let request = request.into_inner();
+        let format = file_format(request.format)
+            .ok_or_else(|| Status::invalid_argument("unknown variable format"))?;
+        let mode = conflict_mode(request.conflict_mode)
+            .ok_or_else(|| Status::invalid_argument("unknown conflict mode"))?;
+        let variables = import_export::deserialize(&request.data, format)?;
+
+        let summary = import_export::import_variables(
+            &self
+                .db
+                .with_change_source(&request.user_id, "rpc ImportVariables"),
+            &request.user_id,
+            &request.room_id,
+            &variables,
+            mode,
+            request.dry_run,
+        )
+        .await?;
+
+        Ok(Response::new(ImportVariablesReply {
+            added: summary.added,
+            updated: summary.updated,
+            unchanged: summary.unchanged,
+            skipped: summary.skipped,
+            removed: summary.removed,
+        }))
     }
 
     async fn rooms_for_user(
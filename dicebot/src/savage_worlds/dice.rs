@@ -0,0 +1,278 @@
+use crate::dice::{roll_exploding_die, DieRoller, RngDieRoller};
+use itertools::Itertools;
+use std::fmt;
+
+/// The Wild Die rolled alongside Wild Card trait rolls is always a
+/// d6.
+const WILD_DIE_SIDES: u32 = 6;
+
+/// Target number for trait rolls when none is given.
+pub const DEFAULT_TARGET_NUMBER: i32 = 4;
+
+/// Every full 4 points over the target number counts as a raise.
+const RAISE_SIZE: i32 = 4;
+
+/// The die types that a trait can be rated at.
+pub const TRAIT_DICE: [u32; 5] = [4, 6, 8, 10, 12];
+
+/// Largest total modifier, up or down, that a trait roll can have.
+/// Keeps the totals of the dice from overflowing.
+pub const MAX_MODIFIER: i32 = 1000;
+
+/// A planned trait roll: the trait die, a flat modifier applied to
+/// both the trait die and the Wild Die, and the target number.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TraitRoll {
+    pub trait_die: u32,
+    pub modifier: i32,
+    pub target: i32,
+}
+
+impl fmt::Display for TraitRoll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "d{}", self.trait_die)?;
+
+        if self.modifier != 0 {
+            write!(f, "{:+}", self.modifier)?;
+        }
+
+        write!(f, " with Wild Die, target: {}", self.target)
+    }
+}
+
+/// The outcome of a trait roll.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TraitRollResult {
+    /// Both the trait die and the Wild Die came up 1 ("snake eyes").
+    CriticalFailure,
+
+    /// The best total was below the target number.
+    Failure,
+
+    /// The best total met the target number, with the given number of
+    /// raises (every 4 over the target).
+    Success(u32),
+}
+
+impl fmt::Display for TraitRollResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::CriticalFailure => write!(f, "critical failure (snake eyes)!"),
+            Self::Failure => write!(f, "failure!"),
+            Self::Success(0) => write!(f, "success!"),
+            Self::Success(1) => write!(f, "success with 1 raise!"),
+            Self::Success(raises) => write!(f, "success with {} raises!", raises),
+        }
+    }
+}
+
+/// A completed trait roll. Keeps every die rolled for both the trait
+/// die and the Wild Die, so aces can be displayed.
+pub struct RolledTrait {
+    trait_rolls: Vec<u32>,
+    wild_rolls: Vec<u32>,
+    modifier: i32,
+    target: i32,
+}
+
+fn sum_rolls(rolls: &[u32]) -> i32 {
+    rolls.iter().map(|&roll| roll as i32).sum()
+}
+
+fn fmt_rolls(rolls: &[u32]) -> String {
+    if rolls.len() > 1 {
+        format!("{} = {}", rolls.iter().join(" + "), sum_rolls(rolls))
+    } else {
+        rolls.iter().join("")
+    }
+}
+
+impl RolledTrait {
+    /// Total of the trait die (including aces) plus the modifier.
+    pub fn trait_total(&self) -> i32 {
+        sum_rolls(&self.trait_rolls) + self.modifier
+    }
+
+    /// Total of the Wild Die (including aces) plus the modifier.
+    pub fn wild_total(&self) -> i32 {
+        sum_rolls(&self.wild_rolls) + self.modifier
+    }
+
+    /// The higher of the trait die and Wild Die totals.
+    pub fn total(&self) -> i32 {
+        std::cmp::max(self.trait_total(), self.wild_total())
+    }
+
+    /// Snake eyes: the first roll of both dice was a 1. A 1 can never
+    /// ace, so both dice will only have a single roll.
+    pub fn is_snake_eyes(&self) -> bool {
+        self.trait_rolls.first() == Some(&1) && self.wild_rolls.first() == Some(&1)
+    }
+
+    /// Calculate the result of the roll. Consult the TraitRollResult
+    /// enum for what each result means.
+    pub fn result(&self) -> TraitRollResult {
+        let total = self.total();
+        if self.is_snake_eyes() {
+            TraitRollResult::CriticalFailure
+        } else if total < self.target {
+            TraitRollResult::Failure
+        } else {
+            let raises = (total - self.target) / RAISE_SIZE;
+            TraitRollResult::Success(raises as u32)
+        }
+    }
+}
+
+impl fmt::Display for RolledTrait {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "trait die: {}, wild die: {}; ",
+            fmt_rolls(&self.trait_rolls),
+            fmt_rolls(&self.wild_rolls)
+        )?;
+
+        write!(
+            f,
+            "{} against {}: {}",
+            self.total(),
+            self.target,
+            self.result()
+        )
+    }
+}
+
+///Roll a die that "aces": whenever the maximum value is rolled, the
+///die is rolled again and added to the total. All rolls are returned.
+fn roll_acing_die<R: DieRoller>(roller: &mut R, sides: u32) -> Vec<u32> {
+    roll_exploding_die(roller, sides, sides)
+}
+
+fn roll_trait_dice<R: DieRoller>(roll: &TraitRoll, roller: &mut R) -> RolledTrait {
+    let trait_rolls = roll_acing_die(roller, roll.trait_die);
+    let wild_rolls = roll_acing_die(roller, WILD_DIE_SIDES);
+
+    RolledTrait {
+        trait_rolls,
+        wild_rolls,
+        modifier: roll.modifier,
+        target: roll.target,
+    }
+}
+
+/// Make a trait roll: the trait die and the Wild Die are both rolled,
+/// both can ace, and the higher total is kept.
+pub fn roll_trait(roll: &TraitRoll) -> RolledTrait {
+    let mut roller = RngDieRoller(rand::thread_rng());
+    roll_trait_dice(roll, &mut roller)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::SequentialDieRoller;
+
+    fn d8_roll(modifier: i32, target: i32) -> TraitRoll {
+        TraitRoll {
+            trait_die: 8,
+            modifier,
+            target,
+        }
+    }
+
+    #[test]
+    fn acing_die_rerolls_on_max() {
+        let mut roller = SequentialDieRoller::new(vec![8, 8, 3, 1]);
+        let rolls = roll_acing_die(&mut roller, 8);
+        assert_eq!(vec![8, 8, 3], rolls);
+    }
+
+    #[test]
+    fn acing_die_stops_below_max() {
+        let mut roller = SequentialDieRoller::new(vec![7, 8]);
+        let rolls = roll_acing_die(&mut roller, 8);
+        assert_eq!(vec![7], rolls);
+    }
+
+    #[test]
+    fn keeps_higher_of_trait_and_wild_die() {
+        //Trait die rolls 3, wild die rolls 5.
+        let mut roller = SequentialDieRoller::new(vec![3, 5]);
+        let rolled = roll_trait_dice(&d8_roll(0, 4), &mut roller);
+        assert_eq!(5, rolled.total());
+        assert_eq!(TraitRollResult::Success(0), rolled.result());
+    }
+
+    #[test]
+    fn wild_die_aces() {
+        //Trait die rolls 2, wild die rolls 6 then 4.
+        let mut roller = SequentialDieRoller::new(vec![2, 6, 4]);
+        let rolled = roll_trait_dice(&d8_roll(0, 4), &mut roller);
+        assert_eq!(10, rolled.total());
+        assert_eq!(TraitRollResult::Success(1), rolled.result());
+    }
+
+    #[test]
+    fn modifier_applies_to_both_dice() {
+        let mut roller = SequentialDieRoller::new(vec![2, 3]);
+        let rolled = roll_trait_dice(&d8_roll(-2, 4), &mut roller);
+        assert_eq!(0, rolled.trait_total());
+        assert_eq!(1, rolled.wild_total());
+        assert_eq!(TraitRollResult::Failure, rolled.result());
+    }
+
+    #[test]
+    fn raise_for_every_four_over_target() {
+        //Trait die aces: 8 + 8 + 4 = 20 against 4 is 4 raises.
+        let mut roller = SequentialDieRoller::new(vec![8, 8, 4, 1]);
+        let rolled = roll_trait_dice(&d8_roll(0, 4), &mut roller);
+        assert_eq!(TraitRollResult::Success(4), rolled.result());
+
+        //7 against 4 is not a raise.
+        let mut roller = SequentialDieRoller::new(vec![7, 1]);
+        let rolled = roll_trait_dice(&d8_roll(0, 4), &mut roller);
+        assert_eq!(TraitRollResult::Success(0), rolled.result());
+    }
+
+    #[test]
+    fn snake_eyes_is_critical_failure() {
+        let mut roller = SequentialDieRoller::new(vec![1, 1]);
+        let rolled = roll_trait_dice(&d8_roll(0, 4), &mut roller);
+        assert!(rolled.is_snake_eyes());
+        assert_eq!(TraitRollResult::CriticalFailure, rolled.result());
+    }
+
+    #[test]
+    fn snake_eyes_ignores_modifier() {
+        //Even a huge modifier cannot save snake eyes.
+        let mut roller = SequentialDieRoller::new(vec![1, 1]);
+        let rolled = roll_trait_dice(&d8_roll(10, 4), &mut roller);
+        assert_eq!(TraitRollResult::CriticalFailure, rolled.result());
+    }
+
+    #[test]
+    fn single_one_is_not_snake_eyes() {
+        let mut roller = SequentialDieRoller::new(vec![1, 4]);
+        let rolled = roll_trait_dice(&d8_roll(0, 4), &mut roller);
+        assert!(!rolled.is_snake_eyes());
+        assert_eq!(TraitRollResult::Success(0), rolled.result());
+    }
+
+    #[test]
+    fn formats_trait_roll() {
+        assert_eq!("d8 with Wild Die, target: 4", d8_roll(0, 4).to_string());
+        assert_eq!("d8+2 with Wild Die, target: 6", d8_roll(2, 6).to_string());
+        assert_eq!("d8-1 with Wild Die, target: 4", d8_roll(-1, 4).to_string());
+    }
+
+    #[test]
+    fn formats_aced_rolls() {
+        let mut roller = SequentialDieRoller::new(vec![8, 3, 2]);
+        let rolled = roll_trait_dice(&d8_roll(0, 4), &mut roller);
+        assert_eq!(
+            "trait die: 8 + 3 = 11, wild die: 2; 11 against 4: success with 1 raise!",
+            rolled.to_string()
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use super::dice::{TraitRoll, DEFAULT_TARGET_NUMBER, MAX_MODIFIER, TRAIT_DICE};
+use crate::parser::dice::DiceParsingError;
+use combine::parser::char::{digit, spaces};
+use combine::{many, many1, one_of, optional, Parser};
+
+/// Parse a Savage Worlds trait roll, in the form of `d<sides>
+/// [+/-modifier ...] [target number]`, e.g. "d8", "d8 +1", "d6 -2 6".
+/// The trait die must be one of the standard trait dice (d4 to d12).
+pub fn parse_trait_roll(input: &str) -> Result<TraitRoll, DiceParsingError> {
+    let input = input.trim();
+
+    let trait_die = one_of("dD".chars())
+        .with(many1(digit()))
+        .skip(spaces().silent())
+        .map(|sides: String| sides);
+
+    let modifier = one_of("+-".chars())
+        .skip(spaces().silent())
+        .and(many1(digit()))
+        .skip(spaces().silent())
+        .map(|(sign, amount): (char, String)| (sign, amount));
+
+    let target = many1(digit()).skip(spaces().silent()).map(|tn: String| tn);
+
+    let mut parser = (trait_die, many::<Vec<_>, _, _>(modifier), optional(target));
+    let ((die, modifiers, target), rest) = parser.parse(input)?;
+
+    if !rest.is_empty() {
+        return Err(DiceParsingError::UnconsumedInput);
+    }
+
+    let trait_die = die.parse::<u32>()?;
+    if !TRAIT_DICE.contains(&trait_die) {
+        return Err(DiceParsingError::InvalidAmount);
+    }
+
+    let mut modifier: i32 = 0;
+    for (sign, amount) in modifiers {
+        let amount = amount.parse::<i32>()?;
+        let sum = if sign == '-' {
+            modifier.checked_sub(amount)
+        } else {
+            modifier.checked_add(amount)
+        };
+
+        modifier = sum
+            .filter(|sum| sum.abs() <= MAX_MODIFIER)
+            .ok_or(DiceParsingError::InvalidAmount)?;
+    }
+
+    let target = match target {
+        Some(target) => target.parse::<i32>()?,
+        None => DEFAULT_TARGET_NUMBER,
+    };
+
+    Ok(TraitRoll {
+        trait_die,
+        modifier,
+        target,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_die_only() {
+        let result = parse_trait_roll("d8");
+        assert_eq!(
+            result,
+            Ok(TraitRoll {
+                trait_die: 8,
+                modifier: 0,
+                target: 4
+            })
+        );
+    }
+
+    #[test]
+    fn parses_modifier() {
+        let result = parse_trait_roll("d8 +2");
+        assert_eq!(
+            result,
+            Ok(TraitRoll {
+                trait_die: 8,
+                modifier: 2,
+                target: 4
+            })
+        );
+
+        let result = parse_trait_roll("d8-2");
+        assert_eq!(
+            result,
+            Ok(TraitRoll {
+                trait_die: 8,
+                modifier: -2,
+                target: 4
+            })
+        );
+    }
+
+    #[test]
+    fn parses_multiple_modifiers() {
+        let result = parse_trait_roll("d10 +2 -1");
+        assert_eq!(
+            result,
+            Ok(TraitRoll {
+                trait_die: 10,
+                modifier: 1,
+                target: 4
+            })
+        );
+    }
+
+    #[test]
+    fn parses_target_number() {
+        let result = parse_trait_roll("d6 6");
+        assert_eq!(
+            result,
+            Ok(TraitRoll {
+                trait_die: 6,
+                modifier: 0,
+                target: 6
+            })
+        );
+
+        let result = parse_trait_roll("   d12 + 1   8  ");
+        assert_eq!(
+            result,
+            Ok(TraitRoll {
+                trait_die: 12,
+                modifier: 1,
+                target: 8
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_non_trait_dice() {
+        assert_eq!(
+            parse_trait_roll("d20"),
+            Err(DiceParsingError::InvalidAmount)
+        );
+        assert_eq!(parse_trait_roll("d7"), Err(DiceParsingError::InvalidAmount));
+    }
+
+    #[test]
+    fn rejects_huge_modifiers() {
+        assert_eq!(
+            parse_trait_roll("d8 +2147483647 +1"),
+            Err(DiceParsingError::InvalidAmount)
+        );
+        assert_eq!(
+            parse_trait_roll("d8 -1001"),
+            Err(DiceParsingError::InvalidAmount)
+        );
+        assert!(parse_trait_roll("d8 +1000 -1000").is_ok());
+    }
+
+    #[test]
+    fn rejects_extraneous_input() {
+        assert_eq!(
+            parse_trait_roll("d8 +1 4 abc"),
+            Err(DiceParsingError::UnconsumedInput)
+        );
+    }
+
+    #[test]
+    fn rejects_missing_die() {
+        assert!(parse_trait_roll("8").is_err());
+        assert!(parse_trait_roll("").is_err());
+    }
+}
@@ -0,0 +1,367 @@
+use crate::context::Context;
+use crate::dice::{roll_exploding_die, DieRoller, RngDieRoller};
+use crate::error::{BotError, DiceRollingError};
+use crate::parser::dice::Amount;
+use itertools::Itertools;
+use std::fmt;
+
+/// Shadowrun only uses six-sided dice.
+const DIE_SIDES: u32 = 6;
+
+/// A 5 or 6 on a die is a hit.
+const HIT_ON: u32 = 5;
+
+/// With Push the Limit, 6s explode.
+const EXPLODE_ON: u32 = 6;
+
+/// Upper limit on the amount of dice in a single pool.
+const MAX_DICE: i32 = 100;
+
+/// Amount of dice to display before cutting off and showing "and X
+/// more", so we don't spam the room with huge messages.
+const MAX_DISPLAYED_ROLLS: usize = 15;
+
+/// Ways that Edge can be spent on a test.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EdgeUse {
+    /// Push the Limit: 6s explode, adding another die for each.
+    PushTheLimit,
+
+    /// Second Chance: re-roll every die that was not a hit.
+    SecondChance,
+}
+
+impl fmt::Display for EdgeUse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EdgeUse::PushTheLimit => write!(f, "push the limit"),
+            EdgeUse::SecondChance => write!(f, "second chance"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ShadowrunPool {
+    pub(crate) amounts: Vec<Amount>,
+    pub(crate) edge: Option<EdgeUse>,
+}
+
+impl ShadowrunPool {
+    pub fn new(amounts: Vec<Amount>, edge: Option<EdgeUse>) -> ShadowrunPool {
+        ShadowrunPool { amounts, edge }
+    }
+}
+
+/// Attach a Context to a Shadowrun pool. Needed for database access.
+pub struct ShadowrunPoolWithContext<'a>(pub &'a ShadowrunPool, pub &'a Context<'a>);
+
+/// Glitch status of a roll.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Glitch {
+    None,
+
+    /// More than half of the dice came up as 1s.
+    Glitch,
+
+    /// A glitch with no hits at all.
+    CriticalGlitch,
+}
+
+/// The result of a rolled Shadowrun pool. If Second Chance was used,
+/// the rolls from before the re-roll are kept for display.
+#[derive(Debug, PartialEq)]
+pub struct RolledShadowrunPool {
+    pub(crate) num_dice: i32,
+    pub(crate) edge: Option<EdgeUse>,
+    pub(crate) rolls: Vec<u32>,
+    pub(crate) first_rolls: Option<Vec<u32>>,
+}
+
+fn count_hits(rolls: &[u32]) -> usize {
+    rolls.iter().filter(|&&roll| roll >= HIT_ON).count()
+}
+
+fn glitch_of(rolls: &[u32]) -> Glitch {
+    let ones = rolls.iter().filter(|&&roll| roll == 1).count();
+    if ones * 2 <= rolls.len() {
+        Glitch::None
+    } else if count_hits(rolls) == 0 {
+        Glitch::CriticalGlitch
+    } else {
+        Glitch::Glitch
+    }
+}
+
+fn fmt_rolls(rolls: &[u32]) -> String {
+    if rolls.len() > MAX_DISPLAYED_ROLLS {
+        let shown_amount = rolls.iter().take(MAX_DISPLAYED_ROLLS).join(", ");
+        format!(
+            "{}, and {} more",
+            shown_amount,
+            rolls.len() - MAX_DISPLAYED_ROLLS
+        )
+    } else {
+        rolls.iter().join(", ")
+    }
+}
+
+impl RolledShadowrunPool {
+    pub fn rolls(&self) -> &[u32] {
+        &self.rolls
+    }
+
+    pub fn hits(&self) -> usize {
+        count_hits(&self.rolls)
+    }
+
+    /// Glitches are checked against every die rolled, including the
+    /// extra dice from Push the Limit.
+    pub fn glitch(&self) -> Glitch {
+        glitch_of(&self.rolls)
+    }
+}
+
+impl fmt::Display for RolledShadowrunPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let dice_plural = if self.num_dice == 1 { "die" } else { "dice" };
+        write!(f, "{} {}", self.num_dice, dice_plural)?;
+
+        if let Some(edge) = self.edge {
+            write!(f, " ({})", edge)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Display of the outcome of the roll: hits, glitches and the dice.
+pub struct ShadowrunResult<'a>(pub &'a RolledShadowrunPool);
+
+impl fmt::Display for ShadowrunResult<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pool = self.0;
+        match pool.hits() {
+            1 => write!(f, "1 hit")?,
+            hits => write!(f, "{} hits", hits)?,
+        }
+
+        match pool.glitch() {
+            Glitch::None => write!(f, "!")?,
+            Glitch::Glitch => write!(f, ", glitch!")?,
+            Glitch::CriticalGlitch => write!(f, ", critical glitch!")?,
+        }
+
+        write!(f, " ({})", fmt_rolls(&pool.rolls))?;
+
+        if let Some(first_rolls) = &pool.first_rolls {
+            write!(f, " re-rolled from ({})", fmt_rolls(first_rolls))?;
+        }
+
+        Ok(())
+    }
+}
+
+fn roll_dice<R: DieRoller>(num_dice: i32, edge: Option<EdgeUse>, roller: &mut R) -> Vec<u32> {
+    match edge {
+        Some(EdgeUse::PushTheLimit) => (0..num_dice)
+            .flat_map(|_| roll_exploding_die(roller, DIE_SIDES, EXPLODE_ON))
+            .collect(),
+        _ => (0..num_dice)
+            .map(|_| roller.roll_number(DIE_SIDES))
+            .collect(),
+    }
+}
+
+///Re-roll every die that was not a hit, keeping the hits.
+fn reroll_misses<R: DieRoller>(rolls: &[u32], roller: &mut R) -> Vec<u32> {
+    rolls
+        .iter()
+        .map(|&roll| {
+            if roll >= HIT_ON {
+                roll
+            } else {
+                roller.roll_number(DIE_SIDES)
+            }
+        })
+        .collect()
+}
+
+fn roll_shadowrun_dice<R: DieRoller>(
+    num_dice: i32,
+    edge: Option<EdgeUse>,
+    roller: &mut R,
+) -> RolledShadowrunPool {
+    let rolls = roll_dice(num_dice, edge, roller);
+
+    // Second Chance cannot be used to negate a critical glitch.
+    let can_reroll = glitch_of(&rolls) != Glitch::CriticalGlitch;
+    let (rolls, first_rolls) = match edge {
+        Some(EdgeUse::SecondChance) if can_reroll => (reroll_misses(&rolls, roller), Some(rolls)),
+        _ => (rolls, None),
+    };
+
+    RolledShadowrunPool {
+        num_dice,
+        edge,
+        rolls,
+        first_rolls,
+    }
+}
+
+///Roll the dice in a Shadowrun pool, resolving any variables in the
+///pool expression first.
+pub async fn roll_pool(
+    pool: &ShadowrunPoolWithContext<'_>,
+) -> Result<RolledShadowrunPool, BotError> {
+    if pool.0.amounts.len() > 100 {
+        return Err(DiceRollingError::ExpressionTooLarge.into());
+    }
+
+    let num_dice = crate::logic::calculate_dice_amount(&pool.0.amounts, pool.1).await?;
+
+    if num_dice <= 0 {
+        return Err(DiceRollingError::InvalidAmount.into());
+    }
+
+    if num_dice > MAX_DICE {
+        return Err(DiceRollingError::ExpressionTooLarge.into());
+    }
+
+    let mut roller = RngDieRoller(rand::thread_rng());
+    Ok(roll_shadowrun_dice(num_dice, pool.0.edge, &mut roller))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::sqlite::Database;
+    use crate::db::Variables;
+    use crate::dice::SequentialDieRoller;
+    use crate::parser::dice::{Element, Operator};
+    use url::Url;
+
+    #[test]
+    fn fives_and_sixes_are_hits() {
+        let mut roller = SequentialDieRoller::new(vec![5, 6, 4, 3, 6]);
+        let rolled = roll_shadowrun_dice(5, None, &mut roller);
+        assert_eq!(3, rolled.hits());
+        assert_eq!(Glitch::None, rolled.glitch());
+    }
+
+    #[test]
+    fn half_ones_is_not_a_glitch() {
+        let mut roller = SequentialDieRoller::new(vec![1, 1, 2, 3]);
+        let rolled = roll_shadowrun_dice(4, None, &mut roller);
+        assert_eq!(Glitch::None, rolled.glitch());
+    }
+
+    #[test]
+    fn more_than_half_ones_is_glitch() {
+        let mut roller = SequentialDieRoller::new(vec![1, 1, 1, 5]);
+        let rolled = roll_shadowrun_dice(4, None, &mut roller);
+        assert_eq!(1, rolled.hits());
+        assert_eq!(Glitch::Glitch, rolled.glitch());
+    }
+
+    #[test]
+    fn glitch_without_hits_is_critical() {
+        let mut roller = SequentialDieRoller::new(vec![1, 1, 4]);
+        let rolled = roll_shadowrun_dice(3, None, &mut roller);
+        assert_eq!(Glitch::CriticalGlitch, rolled.glitch());
+    }
+
+    #[test]
+    fn push_the_limit_explodes_sixes() {
+        let mut roller = SequentialDieRoller::new(vec![6, 6, 2, 5]);
+        let rolled = roll_shadowrun_dice(2, Some(EdgeUse::PushTheLimit), &mut roller);
+        assert_eq!(vec![6, 6, 2, 5], rolled.rolls);
+        assert_eq!(3, rolled.hits());
+    }
+
+    #[test]
+    fn exploded_dice_count_towards_glitch() {
+        //Three 1s out of 5 rolled dice is a glitch.
+        let mut roller = SequentialDieRoller::new(vec![6, 1, 1, 1, 3]);
+        let rolled = roll_shadowrun_dice(4, Some(EdgeUse::PushTheLimit), &mut roller);
+        assert_eq!(5, rolled.rolls.len());
+        assert_eq!(Glitch::Glitch, rolled.glitch());
+
+        //Two 1s out of 5 rolled dice is not.
+        let mut roller = SequentialDieRoller::new(vec![6, 2, 1, 1, 3]);
+        let rolled = roll_shadowrun_dice(4, Some(EdgeUse::PushTheLimit), &mut roller);
+        assert_eq!(Glitch::None, rolled.glitch());
+    }
+
+    #[test]
+    fn second_chance_rerolls_misses() {
+        let mut roller = SequentialDieRoller::new(vec![5, 2, 1, 6, 3]);
+        let rolled = roll_shadowrun_dice(3, Some(EdgeUse::SecondChance), &mut roller);
+        assert_eq!(Some(vec![5, 2, 1]), rolled.first_rolls);
+        assert_eq!(vec![5, 6, 3], rolled.rolls);
+        assert_eq!(2, rolled.hits());
+    }
+
+    #[test]
+    fn second_chance_cannot_negate_critical_glitch() {
+        let mut roller = SequentialDieRoller::new(vec![1, 1, 2]);
+        let rolled = roll_shadowrun_dice(3, Some(EdgeUse::SecondChance), &mut roller);
+        assert_eq!(None, rolled.first_rolls);
+        assert_eq!(Glitch::CriticalGlitch, rolled.glitch());
+    }
+
+    #[test]
+    fn formats_result() {
+        let mut roller = SequentialDieRoller::new(vec![5, 1, 1]);
+        let rolled = roll_shadowrun_dice(3, None, &mut roller);
+        assert_eq!("3 dice", rolled.to_string());
+        assert_eq!(
+            "1 hit, glitch! (5, 1, 1)",
+            ShadowrunResult(&rolled).to_string()
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+    async fn can_resolve_variables_test() {
+        let db_path = tempfile::NamedTempFile::new_in(".").unwrap();
+        crate::db::sqlite::migrator::migrate(db_path.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        let db = Database::new(db_path.path().to_str().unwrap())
+            .await
+            .unwrap();
+
+        let room = crate::context::RoomContext {
+            id: &matrix_sdk::ruma::identifiers::room_id!("!fakeroomid:example.com"),
+            display_name: "displayname".to_owned(),
+            secure: false,
+        };
+
+        let homeserver = Url::parse("http://example.com").unwrap();
+        let ctx = Context {
+            account: crate::models::Account::default(),
+            db: db.clone(),
+            matrix_client: matrix_sdk::Client::new(homeserver).unwrap(),
+            origin_room: room.clone(),
+            active_room: room,
+            username: "username",
+            message_body: "message",
+            in_reply_to: None,
+            bot_admin: false,
+        };
+
+        db.set_user_variable(ctx.username, ctx.origin_room.id.as_str(), "myvariable", 10)
+            .await
+            .expect("could not set myvariable to 10");
+
+        let amounts = vec![Amount {
+            operator: Operator::Plus,
+            element: Element::Variable("myvariable".to_owned()),
+        }];
+
+        let pool = ShadowrunPool::new(amounts, None);
+        let pool_with_ctx = ShadowrunPoolWithContext(&pool, &ctx);
+        let rolled = roll_pool(&pool_with_ctx).await.expect("could not roll");
+        assert_eq!(10, rolled.num_dice);
+    }
+}
@@ -0,0 +1,67 @@
+use super::dice::{EdgeUse, ShadowrunPool};
+use crate::parser::dice::{parse_amounts, DiceParsingError};
+
+/// Parse the Edge modifier that may follow the pool expression: "e"
+/// for Push the Limit, or "c" for Second Chance. Only one use of Edge
+/// is allowed per test.
+pub fn parse_edge(input: &str) -> Result<Option<EdgeUse>, DiceParsingError> {
+    match input.trim() {
+        "" => Ok(None),
+        "e" => Ok(Some(EdgeUse::PushTheLimit)),
+        "c" => Ok(Some(EdgeUse::SecondChance)),
+        _ => Err(DiceParsingError::InvalidModifiers),
+    }
+}
+
+pub fn parse_shadowrun_pool(input: &str) -> Result<ShadowrunPool, DiceParsingError> {
+    let (amounts, edge_str) = parse_amounts(input)?;
+    let edge = parse_edge(edge_str)?;
+    Ok(ShadowrunPool::new(amounts, edge))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::dice::{Amount, Element, Operator};
+
+    #[test]
+    fn edge_test() {
+        assert_eq!(Ok(None), parse_edge(""));
+        assert_eq!(Ok(Some(EdgeUse::PushTheLimit)), parse_edge("e"));
+        assert_eq!(Ok(Some(EdgeUse::SecondChance)), parse_edge(" c "));
+    }
+
+    #[test]
+    fn only_one_edge_use_test() {
+        assert_eq!(Err(DiceParsingError::InvalidModifiers), parse_edge("ec"));
+        assert_eq!(Err(DiceParsingError::InvalidModifiers), parse_edge("x"));
+    }
+
+    #[test]
+    fn pool_with_edge_test() {
+        let expected = ShadowrunPool::new(
+            vec![
+                Amount {
+                    operator: Operator::Plus,
+                    element: Element::Variable("agility".to_owned()),
+                },
+                Amount {
+                    operator: Operator::Plus,
+                    element: Element::Number(4),
+                },
+            ],
+            Some(EdgeUse::PushTheLimit),
+        );
+
+        let result = parse_shadowrun_pool("agility + 4 e");
+        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), expected);
+    }
+
+    #[test]
+    fn plain_pool_test() {
+        let result = parse_shadowrun_pool("12");
+        assert!(result.is_ok());
+        assert_eq!(None, result.unwrap().edge);
+    }
+}
@@ -0,0 +1,49 @@
+use fuse_rust::Fuse;
+
+/// How different a name can be from a known name and still be
+/// suggested in its place. 0 is an exact match and 1 matches anything.
+const SUGGESTION_THRESHOLD: f64 = 0.4;
+
+/// The known name closest to the (probably misspelled) given name, if
+/// any is close enough.
+pub fn closest_match<'a>(name: &str, known: &[&'a str]) -> Option<&'a str> {
+    let fuse = Fuse {
+        threshold: SUGGESTION_THRESHOLD,
+        ..Default::default()
+    };
+
+    //Lowest score is the best match.
+    fuse.search_text_in_iterable(name, known.iter())
+        .into_iter()
+        .min_by(|r1, r2| r1.score.partial_cmp(&r2.score).unwrap())
+        .and_then(|result| known.get(result.index).copied())
+}
+
+/// A " (did you mean ...?)" hint to add to an error message, or
+/// nothing if there is no suggestion. The prefix goes before the
+/// suggested name, e.g. "!" for commands.
+pub fn did_you_mean<S: AsRef<str>>(prefix: &str, suggestion: &Option<S>) -> String {
+    match suggestion {
+        Some(name) => format!(" (did you mean {}{}?)", prefix, name.as_ref()),
+        None => "".to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suggests_closest_name() {
+        let known = ["strength", "brawl", "stamina"];
+        assert_eq!(Some("strength"), closest_match("strenght", &known));
+        assert_eq!(Some("brawl"), closest_match("brawll", &known));
+        assert_eq!(None, closest_match("firearms", &known));
+    }
+
+    #[test]
+    fn hint_only_with_suggestion() {
+        assert_eq!(" (did you mean !roll?)", did_you_mean("!", &Some("roll")));
+        assert_eq!("", did_you_mean::<&str>("", &None));
+    }
+}
@@ -1,6 +1,9 @@
 use strum::{AsRefStr, Display, EnumIter, EnumString};
 
-#[derive(EnumString, EnumIter, AsRefStr, Display)]
+pub mod sheet;
+
+#[derive(EnumString, EnumIter, AsRefStr, Display, Debug, Clone, Copy, PartialEq, Eq)]
+#[strum(ascii_case_insensitive)]
 pub(crate) enum GameSystem {
     ChroniclesOfDarkness,
     Changeling,
@@ -18,4 +21,80 @@ pub(crate) enum GameSystem {
     None,
 }
 
-impl GameSystem {}
+impl GameSystem {
+    /// Parse a game system from either its full name (e.g.
+    /// "MageTheAwakening", in any case) or a common short name (e.g.
+    /// "mage").
+    pub fn parse(input: &str) -> Option<GameSystem> {
+        use std::str::FromStr;
+        let system = match input.trim().to_lowercase().as_ref() {
+            "cofd" => GameSystem::ChroniclesOfDarkness,
+            "mage" | "mta" => GameSystem::MageTheAwakening,
+            "werewolf" | "wtf" => GameSystem::WerewolfTheForsaken,
+            "deviant" | "dtr" => GameSystem::DeviantTheRenegades,
+            "mummy" | "mtc" => GameSystem::MummyTheCurse,
+            "promethean" | "ptc" => GameSystem::PrometheanTheCreated,
+            "cthulhu" | "coc" => GameSystem::CallOfCthulhu,
+            "dnd5e" | "5e" => GameSystem::DungeonsAndDragons5e,
+            other => return GameSystem::from_str(other).ok(),
+        };
+
+        Some(system)
+    }
+
+    /// Human-readable name of the game system.
+    pub fn name(&self) -> &'static str {
+        match self {
+            GameSystem::ChroniclesOfDarkness => "Chronicles of Darkness",
+            GameSystem::Changeling => "Changeling: The Lost",
+            GameSystem::MageTheAwakening => "Mage: The Awakening",
+            GameSystem::WerewolfTheForsaken => "Werewolf: The Forsaken",
+            GameSystem::DeviantTheRenegades => "Deviant: The Renegades",
+            GameSystem::MummyTheCurse => "Mummy: The Curse",
+            GameSystem::PrometheanTheCreated => "Promethean: The Created",
+            GameSystem::CallOfCthulhu => "Call of Cthulhu",
+            GameSystem::DungeonsAndDragons5e => "Dungeons & Dragons 5e",
+            GameSystem::DungeonsAndDragons4e => "Dungeons & Dragons 4e",
+            GameSystem::DungeonsAndDragons35e => "Dungeons & Dragons 3.5e",
+            GameSystem::DungeonsAndDragons2e => "Dungeons & Dragons 2e",
+            GameSystem::DungeonsAndDragons1e => "Dungeons & Dragons 1e",
+            GameSystem::None => "no game system",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_full_names_in_any_case() {
+        assert_eq!(
+            Some(GameSystem::MageTheAwakening),
+            GameSystem::parse("MageTheAwakening")
+        );
+
+        assert_eq!(
+            Some(GameSystem::MummyTheCurse),
+            GameSystem::parse("mummythecurse")
+        );
+    }
+
+    #[test]
+    fn parses_short_names() {
+        assert_eq!(
+            Some(GameSystem::WerewolfTheForsaken),
+            GameSystem::parse("werewolf")
+        );
+
+        assert_eq!(
+            Some(GameSystem::PrometheanTheCreated),
+            GameSystem::parse(" Promethean ")
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_systems() {
+        assert_eq!(None, GameSystem::parse("vampire"));
+    }
+}
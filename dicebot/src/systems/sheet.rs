@@ -0,0 +1,488 @@
+use super::GameSystem;
+use std::collections::HashMap;
+
+/// A single stored value on a character sheet, such as an attribute
+/// or a skill. Fields are stored as regular user variables.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Field {
+    pub name: &'static str,
+    pub default: i32,
+}
+
+/// A named group of fields, rendered together (e.g. "Attributes").
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Section {
+    pub name: &'static str,
+    pub fields: &'static [Field],
+}
+
+/// The fields a game system expects on a character sheet, and how to
+/// calculate its derived values. Derived values are never stored:
+/// they are recalculated from the stored fields whenever they are
+/// read, so they can never go stale.
+pub(crate) struct SheetTemplate {
+    pub sections: &'static [Section],
+    derive: fn(&SheetValues) -> Vec<(String, i32)>,
+}
+
+/// Values of the fields on a sheet. Fields that have not been stored
+/// yet fall back to the template's default.
+pub(crate) struct SheetValues<'a> {
+    template: &'a SheetTemplate,
+    variables: &'a HashMap<String, i32>,
+}
+
+impl SheetValues<'_> {
+    pub fn get(&self, name: &str) -> i32 {
+        self.variables
+            .get(name)
+            .copied()
+            .or_else(|| {
+                self.template
+                    .fields()
+                    .find(|field| field.name == name)
+                    .map(|field| field.default)
+            })
+            .unwrap_or(0)
+    }
+}
+
+impl SheetTemplate {
+    pub fn fields(&self) -> impl Iterator<Item = &Field> {
+        self.sections
+            .iter()
+            .flat_map(|section| section.fields.iter())
+    }
+
+    /// Calculate the derived values of a sheet from the user's stored
+    /// variables, in the order they should be displayed.
+    pub fn derived(&self, variables: &HashMap<String, i32>) -> Vec<(String, i32)> {
+        let values = SheetValues {
+            template: self,
+            variables,
+        };
+
+        (self.derive)(&values)
+    }
+
+    /// Render the sheet as HTML: each section of stored fields, then
+    /// the derived values.
+    pub fn render(&self, variables: &HashMap<String, i32>) -> String {
+        let values = SheetValues {
+            template: self,
+            variables,
+        };
+
+        let render_line = |name: &str, value: i32| format!(" - {} = {}", name, value);
+
+        let mut sections: Vec<String> = self
+            .sections
+            .iter()
+            .map(|section| {
+                let lines: Vec<String> = section
+                    .fields
+                    .iter()
+                    .map(|field| render_line(field.name, values.get(field.name)))
+                    .collect();
+
+                format!(
+                    "<strong>{}:</strong><br/>{}",
+                    section.name,
+                    lines.join("<br/>")
+                )
+            })
+            .collect();
+
+        let derived: Vec<String> = self
+            .derived(variables)
+            .into_iter()
+            .map(|(name, value)| render_line(&name, value))
+            .collect();
+
+        if !derived.is_empty() {
+            sections.push(format!(
+                "<strong>Derived:</strong><br/>{}",
+                derived.join("<br/>")
+            ));
+        }
+
+        sections.join("</p><p>")
+    }
+}
+
+impl GameSystem {
+    /// The character sheet template for this game system, if it has
+    /// one.
+    pub(crate) fn sheet_template(&self) -> Option<&'static SheetTemplate> {
+        match self {
+            GameSystem::ChroniclesOfDarkness | GameSystem::DeviantTheRenegades => Some(&COFD_SHEET),
+            GameSystem::Changeling => Some(&CHANGELING_SHEET),
+            GameSystem::MageTheAwakening => Some(&MAGE_SHEET),
+            GameSystem::WerewolfTheForsaken => Some(&WEREWOLF_SHEET),
+            GameSystem::MummyTheCurse => Some(&MUMMY_SHEET),
+            GameSystem::PrometheanTheCreated => Some(&PROMETHEAN_SHEET),
+            GameSystem::CallOfCthulhu => Some(&CTHULHU_SHEET),
+            GameSystem::DungeonsAndDragons5e => Some(&DND5E_SHEET),
+            _ => None,
+        }
+    }
+}
+
+/// Shorthand for declaring a field.
+const fn field(name: &'static str, default: i32) -> Field {
+    Field { name, default }
+}
+
+const COFD_ATTRIBUTES: Section = Section {
+    name: "Attributes",
+    fields: &[
+        field("intelligence", 1),
+        field("wits", 1),
+        field("resolve", 1),
+        field("strength", 1),
+        field("dexterity", 1),
+        field("stamina", 1),
+        field("presence", 1),
+        field("manipulation", 1),
+        field("composure", 1),
+    ],
+};
+
+const COFD_SKILLS: Section = Section {
+    name: "Skills",
+    fields: &[
+        field("academics", 0),
+        field("computer", 0),
+        field("crafts", 0),
+        field("investigation", 0),
+        field("medicine", 0),
+        field("occult", 0),
+        field("politics", 0),
+        field("science", 0),
+        field("athletics", 0),
+        field("brawl", 0),
+        field("drive", 0),
+        field("firearms", 0),
+        field("larceny", 0),
+        field("stealth", 0),
+        field("survival", 0),
+        field("weaponry", 0),
+        field("animalken", 0),
+        field("empathy", 0),
+        field("expression", 0),
+        field("intimidation", 0),
+        field("persuasion", 0),
+        field("socialize", 0),
+        field("streetwise", 0),
+        field("subterfuge", 0),
+    ],
+};
+
+/// Defense, Initiative, Speed, Willpower and Health, shared by every
+/// Chronicles of Darkness splat.
+fn cofd_derived(values: &SheetValues) -> Vec<(String, i32)> {
+    let defense =
+        std::cmp::min(values.get("wits"), values.get("dexterity")) + values.get("athletics");
+
+    vec![
+        ("defense".to_string(), defense),
+        (
+            "initiative".to_string(),
+            values.get("dexterity") + values.get("composure"),
+        ),
+        (
+            "speed".to_string(),
+            values.get("strength") + values.get("dexterity") + 5,
+        ),
+        (
+            "willpower".to_string(),
+            values.get("resolve") + values.get("composure"),
+        ),
+        (
+            "health".to_string(),
+            values.get("stamina") + values.get("size"),
+        ),
+    ]
+}
+
+const COFD_SHEET: SheetTemplate = SheetTemplate {
+    sections: &[
+        COFD_ATTRIBUTES,
+        COFD_SKILLS,
+        Section {
+            name: "Traits",
+            fields: &[field("size", 5)],
+        },
+    ],
+    derive: cofd_derived,
+};
+
+const CHANGELING_SHEET: SheetTemplate = SheetTemplate {
+    sections: &[
+        COFD_ATTRIBUTES,
+        COFD_SKILLS,
+        Section {
+            name: "Traits",
+            fields: &[field("size", 5), field("wyrd", 1), field("glamour", 0)],
+        },
+    ],
+    derive: cofd_derived,
+};
+
+const MAGE_SHEET: SheetTemplate = SheetTemplate {
+    sections: &[
+        COFD_ATTRIBUTES,
+        COFD_SKILLS,
+        Section {
+            name: "Traits",
+            fields: &[field("size", 5), field("gnosis", 1), field("mana", 0)],
+        },
+    ],
+    derive: cofd_derived,
+};
+
+const WEREWOLF_SHEET: SheetTemplate = SheetTemplate {
+    sections: &[
+        COFD_ATTRIBUTES,
+        COFD_SKILLS,
+        Section {
+            name: "Traits",
+            fields: &[
+                field("size", 5),
+                field("primalurge", 1),
+                field("essence", 0),
+            ],
+        },
+    ],
+    derive: cofd_derived,
+};
+
+const MUMMY_SHEET: SheetTemplate = SheetTemplate {
+    sections: &[
+        COFD_ATTRIBUTES,
+        COFD_SKILLS,
+        Section {
+            name: "Traits",
+            fields: &[
+                field("size", 5),
+                field("sekhem", 10),
+                field("ab", 0),
+                field("ba", 0),
+                field("ka", 0),
+                field("ren", 0),
+                field("sheut", 0),
+            ],
+        },
+    ],
+    derive: cofd_derived,
+};
+
+const PROMETHEAN_SHEET: SheetTemplate = SheetTemplate {
+    sections: &[
+        COFD_ATTRIBUTES,
+        COFD_SKILLS,
+        Section {
+            name: "Traits",
+            fields: &[field("size", 5), field("azoth", 1), field("pyros", 0)],
+        },
+    ],
+    derive: cofd_derived,
+};
+
+const CTHULHU_CHARACTERISTICS: Section = Section {
+    name: "Characteristics",
+    fields: &[
+        field("str", 50),
+        field("con", 50),
+        field("siz", 50),
+        field("dex", 50),
+        field("app", 50),
+        field("int", 50),
+        field("pow", 50),
+        field("edu", 50),
+        field("luck", 50),
+    ],
+};
+
+/// Skills with their base chances, from the investigator sheet.
+const CTHULHU_SKILLS: Section = Section {
+    name: "Skills",
+    fields: &[
+        field("accounting", 5),
+        field("anthropology", 1),
+        field("appraise", 5),
+        field("archaeology", 1),
+        field("charm", 15),
+        field("climb", 20),
+        field("creditrating", 0),
+        field("cthulhumythos", 0),
+        field("disguise", 5),
+        field("dodge", 25),
+        field("drive", 20),
+        field("electricalrepair", 10),
+        field("fasttalk", 5),
+        field("fighting", 25),
+        field("firearms", 20),
+        field("firstaid", 30),
+        field("history", 5),
+        field("intimidate", 15),
+        field("jump", 20),
+        field("law", 5),
+        field("libraryuse", 20),
+        field("listen", 20),
+        field("locksmith", 1),
+        field("mechanicalrepair", 10),
+        field("medicine", 1),
+        field("naturalworld", 10),
+        field("navigate", 10),
+        field("occult", 5),
+        field("persuade", 10),
+        field("psychology", 10),
+        field("ride", 5),
+        field("sleightofhand", 10),
+        field("spothidden", 25),
+        field("stealth", 20),
+        field("swim", 20),
+        field("throw", 20),
+        field("track", 10),
+    ],
+};
+
+/// Hit points, magic points and starting sanity, followed by the half
+/// and fifth values of every characteristic and skill, for hard and
+/// extreme successes.
+fn cthulhu_derived(values: &SheetValues) -> Vec<(String, i32)> {
+    let mut derived = vec![
+        (
+            "hp".to_string(),
+            (values.get("con") + values.get("siz")) / 10,
+        ),
+        ("mp".to_string(), values.get("pow") / 5),
+        ("sanity".to_string(), values.get("pow")),
+    ];
+
+    for field in CTHULHU_CHARACTERISTICS
+        .fields
+        .iter()
+        .chain(CTHULHU_SKILLS.fields.iter())
+    {
+        let value = values.get(field.name);
+        derived.push((format!("{}half", field.name), value / 2));
+        derived.push((format!("{}fifth", field.name), value / 5));
+    }
+
+    derived
+}
+
+const CTHULHU_SHEET: SheetTemplate = SheetTemplate {
+    sections: &[CTHULHU_CHARACTERISTICS, CTHULHU_SKILLS],
+    derive: cthulhu_derived,
+};
+
+const DND5E_ABILITIES: Section = Section {
+    name: "Abilities",
+    fields: &[
+        field("strength", 10),
+        field("dexterity", 10),
+        field("constitution", 10),
+        field("intelligence", 10),
+        field("wisdom", 10),
+        field("charisma", 10),
+    ],
+};
+
+/// Ability modifiers, the proficiency bonus by level, and initiative.
+fn dnd5e_derived(values: &SheetValues) -> Vec<(String, i32)> {
+    let modifier = |name: &str| (values.get(name) - 10).div_euclid(2);
+
+    let mut derived: Vec<(String, i32)> = DND5E_ABILITIES
+        .fields
+        .iter()
+        .map(|field| (format!("{}mod", field.name), modifier(field.name)))
+        .collect();
+
+    let level = std::cmp::max(values.get("level"), 1);
+    derived.push(("proficiency".to_string(), 2 + (level - 1) / 4));
+    derived.push(("initiative".to_string(), modifier("dexterity")));
+    derived
+}
+
+const DND5E_SHEET: SheetTemplate = SheetTemplate {
+    sections: &[
+        DND5E_ABILITIES,
+        Section {
+            name: "Traits",
+            fields: &[field("level", 1)],
+        },
+    ],
+    derive: dnd5e_derived,
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn variables(values: &[(&str, i32)]) -> HashMap<String, i32> {
+        values
+            .iter()
+            .map(|(name, value)| (name.to_string(), *value))
+            .collect()
+    }
+
+    fn derived_value(template: &SheetTemplate, vars: &HashMap<String, i32>, name: &str) -> i32 {
+        template
+            .derived(vars)
+            .into_iter()
+            .find(|(derived, _)| derived == name)
+            .map(|(_, value)| value)
+            .expect("derived value missing")
+    }
+
+    #[test]
+    fn cofd_defense_uses_lower_of_wits_and_dexterity() {
+        let vars = variables(&[("wits", 2), ("dexterity", 4), ("athletics", 3)]);
+        assert_eq!(5, derived_value(&COFD_SHEET, &vars, "defense"));
+
+        let vars = variables(&[("wits", 3), ("dexterity", 1)]);
+        assert_eq!(1, derived_value(&COFD_SHEET, &vars, "defense"));
+    }
+
+    #[test]
+    fn cofd_derived_values_use_defaults() {
+        let vars = variables(&[("stamina", 3)]);
+        assert_eq!(8, derived_value(&MAGE_SHEET, &vars, "health"));
+        assert_eq!(7, derived_value(&MAGE_SHEET, &vars, "speed"));
+    }
+
+    #[test]
+    fn cthulhu_half_and_fifth_values() {
+        let vars = variables(&[("spothidden", 63), ("con", 60), ("siz", 45)]);
+        assert_eq!(31, derived_value(&CTHULHU_SHEET, &vars, "spothiddenhalf"));
+        assert_eq!(12, derived_value(&CTHULHU_SHEET, &vars, "spothiddenfifth"));
+        assert_eq!(10, derived_value(&CTHULHU_SHEET, &vars, "hp"));
+    }
+
+    #[test]
+    fn dnd5e_ability_modifiers_round_down() {
+        let vars = variables(&[("strength", 15), ("dexterity", 7), ("level", 5)]);
+        assert_eq!(2, derived_value(&DND5E_SHEET, &vars, "strengthmod"));
+        assert_eq!(-2, derived_value(&DND5E_SHEET, &vars, "dexteritymod"));
+        assert_eq!(0, derived_value(&DND5E_SHEET, &vars, "wisdommod"));
+        assert_eq!(3, derived_value(&DND5E_SHEET, &vars, "proficiency"));
+    }
+
+    #[test]
+    fn systems_without_templates() {
+        assert!(GameSystem::DungeonsAndDragons2e.sheet_template().is_none());
+        assert!(GameSystem::None.sheet_template().is_none());
+        assert!(GameSystem::WerewolfTheForsaken.sheet_template().is_some());
+    }
+
+    #[test]
+    fn renders_sections_and_derived_values() {
+        let html = DND5E_SHEET.render(&variables(&[("strength", 14)]));
+        assert!(html.starts_with("<strong>Abilities:</strong><br/> - strength = 14"));
+        assert!(html.contains("<strong>Traits:</strong><br/> - level = 1"));
+        assert!(html.contains(" - strengthmod = 2"));
+    }
+}
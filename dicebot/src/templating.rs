@@ -0,0 +1,162 @@
+use crate::commands::parser::command_name;
+use crate::commands::registry::find_command;
+use crate::context::Context;
+use crate::error::BotError;
+use crate::logic::VariableResolver;
+use crate::parser::variables::variable_name;
+use combine::{eof, Parser};
+use std::borrow::Cow;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("unknown variable in {{{0}}}; set it with !set {0} <value>")]
+    UnknownVariable(String),
+}
+
+/// A piece of a command: literal text, or the name of a variable
+/// between braces.
+#[derive(Debug, PartialEq, Eq)]
+enum Segment<'a> {
+    Text(String),
+    Variable(&'a str),
+}
+
+fn is_variable_name(name: &str) -> bool {
+    variable_name().skip(eof()).parse(name).is_ok()
+}
+
+/// Split a command into text and {variable} references. Doubled
+/// braces ("{{" and "}}") are literal braces, and braces that do not
+/// surround a valid variable name are left alone.
+fn segments(input: &str) -> Vec<Segment<'_>> {
+    let mut segments = vec![];
+    let mut text = String::new();
+    let mut rest = input;
+
+    while let Some(start) = rest.find(&['{', '}'][..]) {
+        let (before, from_brace) = rest.split_at(start);
+        text.push_str(before);
+
+        if from_brace.starts_with("{{") || from_brace.starts_with("}}") {
+            text.push_str(&from_brace[..1]);
+            rest = &from_brace[2..];
+            continue;
+        }
+
+        let name = from_brace[1..]
+            .find('}')
+            .map(|end| &from_brace[1..end + 1])
+            .filter(|name| from_brace.starts_with('{') && is_variable_name(name));
+
+        match name {
+            Some(name) => {
+                if !text.is_empty() {
+                    segments.push(Segment::Text(std::mem::take(&mut text)));
+                }
+
+                segments.push(Segment::Variable(name));
+                rest = &from_brace[name.len() + 2..];
+            }
+            None => {
+                text.push_str(&from_brace[..1]);
+                rest = &from_brace[1..];
+            }
+        }
+    }
+
+    text.push_str(rest);
+    if !text.is_empty() {
+        segments.push(Segment::Text(text));
+    }
+
+    segments
+}
+
+/// Replace every {variable} in the command with the variable's
+/// value. Text variables are inserted as they are, and numbers and
+/// formulas as their current value.
+pub fn expand(input: &str, variables: &VariableResolver) -> Result<String, BotError> {
+    let mut expanded = String::with_capacity(input.len());
+    for segment in segments(input) {
+        match segment {
+            Segment::Text(text) => expanded.push_str(&text),
+            Segment::Variable(name) => match variables.text(name) {
+                Some(text) => expanded.push_str(text),
+                None if variables.is_defined(name) => {
+                    expanded.push_str(&variables.resolve(name)?.to_string())
+                }
+                None => return Err(TemplateError::UnknownVariable(name.to_owned()).into()),
+            },
+        }
+    }
+
+    Ok(expanded)
+}
+
+/// Whether the command expands variables, according to the command
+/// registry. Unknown commands (e.g. macros) do not.
+fn expands_variables(input: &str) -> bool {
+    let spec = command_name(input).and_then(|name| find_command(&name));
+    matches!(spec, Some(spec) if spec.expands_variables)
+}
+
+/// Expand the variables in the command of the context, before it is
+/// parsed, if the command expands variables. Variables are only
+/// loaded if the command uses braces.
+pub async fn expand_variables<'a>(ctx: &Context<'a>) -> Result<Cow<'a, str>, BotError> {
+    let input = ctx.message_body;
+    if !expands_variables(input) || !input.contains(&['{', '}'][..]) {
+        return Ok(Cow::Borrowed(input));
+    }
+
+    let variables = VariableResolver::load(ctx).await?;
+    expand(input, &variables).map(Cow::Owned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(value: &str) -> Segment<'static> {
+        Segment::Text(value.to_owned())
+    }
+
+    #[test]
+    fn splits_variables_from_text() {
+        assert_eq!(
+            vec![
+                text("!roll "),
+                Segment::Variable("weapon_dice"),
+                text("+"),
+                Segment::Variable("room.str-mod"),
+            ],
+            segments("!roll {weapon_dice}+{room.str-mod}")
+        );
+    }
+
+    #[test]
+    fn doubled_braces_are_literal() {
+        assert_eq!(
+            vec![text("!register {hunter2}")],
+            segments("!register {{hunter2}}")
+        );
+    }
+
+    #[test]
+    fn braces_without_variable_names_are_left_alone() {
+        assert_eq!(vec![text("{1d4} { x } {")], segments("{1d4} { x } {"));
+        assert_eq!(vec![text("no variables")], segments("no variables"));
+    }
+
+    #[test]
+    fn only_marked_commands_expand_variables() {
+        assert!(expands_variables("!roll {weapon_dice}"));
+        assert!(expands_variables("!CTHROLL {firearms} bb"));
+        assert!(!expands_variables("!set motto \"{name} was here\""));
+        assert!(!expands_variables("!register {hunter2}"));
+        assert!(!expands_variables("!link {hunter2}"));
+        assert!(!expands_variables("!macro attack !roll {weapon_dice}"));
+        assert!(!expands_variables("!mymacro {x}"));
+    }
+}
@@ -0,0 +1,350 @@
+use crate::context::Context;
+use crate::cthulhu::dice::{roll_d100, DieRoller, RngDieRoller};
+use crate::error::{BotError, DiceRollingError};
+use crate::parser::dice::Amount;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::convert::TryFrom;
+use std::fmt;
+
+/// Rolls of 01 to 05 always succeed.
+const AUTOMATIC_SUCCESS: u32 = 5;
+
+/// Rolls of 96 to 100 always fail.
+const AUTOMATIC_FAILURE: u32 = 96;
+
+/// A test against a characteristic or skill, or an opposed test
+/// between two of them. Targets are expressions, so they can be made
+/// of variables and modifiers (e.g. "melee + 20").
+#[derive(Debug, PartialEq)]
+pub struct WfrpTest {
+    pub(crate) target: Vec<Amount>,
+    pub(crate) opponent: Option<Vec<Amount>>,
+}
+
+/// Attach a Context to a WFRP test. Needed for database access.
+pub struct WfrpTestWithContext<'a>(pub &'a WfrpTest, pub &'a Context<'a>);
+
+/// Where an attack lands, read from the reversed test roll.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum HitLocation {
+    Head,
+    LeftArm,
+    RightArm,
+    Body,
+    LeftLeg,
+    RightLeg,
+}
+
+impl HitLocation {
+    /// Look up the location for a (reversed) d100 roll, from 1 to 100.
+    pub fn from_roll(roll: u32) -> HitLocation {
+        match roll {
+            1..=9 => HitLocation::Head,
+            10..=24 => HitLocation::LeftArm,
+            25..=44 => HitLocation::RightArm,
+            45..=79 => HitLocation::Body,
+            80..=89 => HitLocation::LeftLeg,
+            _ => HitLocation::RightLeg,
+        }
+    }
+}
+
+impl fmt::Display for HitLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let message = match self {
+            Self::Head => "head",
+            Self::LeftArm => "left arm",
+            Self::RightArm => "right arm",
+            Self::Body => "body",
+            Self::LeftLeg => "left leg",
+            Self::RightLeg => "right leg",
+        };
+
+        write!(f, "{}", message)
+    }
+}
+
+/// Tens digit of a roll or target. A roll of 100 counts as 10.
+fn tens(number: u32) -> i32 {
+    (number / 10) as i32
+}
+
+/// A completed test: the d100 result and the target it was rolled
+/// against.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RolledTest {
+    pub(crate) num_rolled: u32,
+    pub(crate) target: u32,
+}
+
+impl RolledTest {
+    /// Rolling the target or lower succeeds, except that 01 to 05
+    /// always succeed and 96 to 100 always fail.
+    pub fn is_success(&self) -> bool {
+        if self.num_rolled <= AUTOMATIC_SUCCESS {
+            true
+        } else if self.num_rolled >= AUTOMATIC_FAILURE {
+            false
+        } else {
+            self.num_rolled <= self.target
+        }
+    }
+
+    /// Success Levels: the tens digit of the target minus the tens
+    /// digit of the roll. A success is never worse than +0, and a
+    /// failure is never better than -0.
+    pub fn success_levels(&self) -> i32 {
+        let sl = tens(self.target) - tens(self.num_rolled);
+        if self.is_success() {
+            std::cmp::max(sl, 0)
+        } else {
+            std::cmp::min(sl, 0)
+        }
+    }
+
+    /// Both digits of the roll are the same. 100 counts as 00.
+    pub fn is_double(&self) -> bool {
+        let roll = self.num_rolled % 100;
+        roll / 10 == roll % 10
+    }
+
+    /// A double on a successful test.
+    pub fn is_critical(&self) -> bool {
+        self.is_double() && self.is_success()
+    }
+
+    /// A double on a failed test.
+    pub fn is_fumble(&self) -> bool {
+        self.is_double() && !self.is_success()
+    }
+
+    /// The roll with its digits swapped (e.g. 34 becomes 43), used to
+    /// find the hit location.
+    pub fn reversed(&self) -> u32 {
+        let roll = self.num_rolled % 100;
+        match (roll % 10) * 10 + roll / 10 {
+            0 => 100,
+            reversed => reversed,
+        }
+    }
+
+    pub fn hit_location(&self) -> HitLocation {
+        HitLocation::from_roll(self.reversed())
+    }
+}
+
+/// Display SL with an explicit sign, including -0 for failures.
+struct SuccessLevels<'a>(&'a RolledTest);
+
+impl fmt::Display for SuccessLevels<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let sign = if self.0.is_success() { '+' } else { '-' };
+        write!(f, "{}{} SL", sign, self.0.success_levels().abs())
+    }
+}
+
+impl fmt::Display for RolledTest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let result = if self.is_critical() {
+            "critical!"
+        } else if self.is_fumble() {
+            "fumble!"
+        } else if self.is_success() {
+            "success!"
+        } else {
+            "failure!"
+        };
+
+        write!(
+            f,
+            "{:02} against {}: {} ({}), hit location: {} ({:02})",
+            self.num_rolled % 100,
+            self.target,
+            SuccessLevels(self),
+            result,
+            self.hit_location(),
+            self.reversed() % 100
+        )
+    }
+}
+
+/// Outcome of an opposed test.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OpposedResult {
+    /// The first test won, by the given number of SL.
+    FirstWins(i32),
+
+    /// The second test won, by the given number of SL.
+    SecondWins(i32),
+
+    /// Same SL and same target: nobody wins.
+    Stalemate,
+}
+
+/// In an opposed test, the side with the higher SL wins, and the
+/// difference in SL is the margin of victory. If the SL are tied, the
+/// higher target (characteristic or skill) wins.
+pub fn opposed_test(first: &RolledTest, second: &RolledTest) -> OpposedResult {
+    let difference = first.success_levels() - second.success_levels();
+    let by_target = first.target.cmp(&second.target);
+
+    match difference {
+        d if d > 0 => OpposedResult::FirstWins(d),
+        d if d < 0 => OpposedResult::SecondWins(-d),
+        _ => match by_target {
+            std::cmp::Ordering::Greater => OpposedResult::FirstWins(0),
+            std::cmp::Ordering::Less => OpposedResult::SecondWins(0),
+            std::cmp::Ordering::Equal => OpposedResult::Stalemate,
+        },
+    }
+}
+
+impl fmt::Display for OpposedResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::FirstWins(sl) => write!(f, "first test wins by {} SL!", sl),
+            Self::SecondWins(sl) => write!(f, "second test wins by {} SL!", sl),
+            Self::Stalemate => write!(f, "stalemate!"),
+        }
+    }
+}
+
+/// Result of a WFRP test: either a single test, or both tests of an
+/// opposed test.
+pub enum RolledWfrpTest {
+    Single(RolledTest),
+    Opposed(RolledTest, RolledTest),
+}
+
+impl fmt::Display for RolledWfrpTest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Single(test) => write!(f, "{}", test),
+            Self::Opposed(first, second) => {
+                write!(f, "{}; {}; {}", first, second, opposed_test(first, second))
+            }
+        }
+    }
+}
+
+fn roll_test<R: DieRoller>(target: u32, roller: &mut R) -> RolledTest {
+    RolledTest {
+        num_rolled: roll_d100(roller),
+        target,
+    }
+}
+
+async fn resolve_target(amounts: &[Amount], ctx: &Context<'_>) -> Result<u32, BotError> {
+    if amounts.len() > 100 {
+        return Err(DiceRollingError::ExpressionTooLarge.into());
+    }
+
+    let target = crate::logic::calculate_dice_amount(amounts, ctx).await?;
+    u32::try_from(target).map_err(|_| DiceRollingError::InvalidAmount.into())
+}
+
+///Roll a WFRP test, resolving any variables in the target
+///expressions first.
+pub async fn roll_wfrp_test(test: &WfrpTestWithContext<'_>) -> Result<RolledWfrpTest, BotError> {
+    let target = resolve_target(&test.0.target, test.1).await?;
+    let opponent = match &test.0.opponent {
+        Some(opponent) => Some(resolve_target(opponent, test.1).await?),
+        None => None,
+    };
+
+    let mut roller = RngDieRoller::<StdRng>(SeedableRng::from_entropy());
+    let first = roll_test(target, &mut roller);
+
+    Ok(match opponent {
+        Some(opponent) => RolledWfrpTest::Opposed(first, roll_test(opponent, &mut roller)),
+        None => RolledWfrpTest::Single(first),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cthulhu::dice::SequentialDieRoller;
+
+    fn test(num_rolled: u32, target: u32) -> RolledTest {
+        RolledTest { num_rolled, target }
+    }
+
+    #[test]
+    fn rolls_unit_then_tens() {
+        let mut roller = SequentialDieRoller::new(vec![4, 3]);
+        let rolled = roll_test(45, &mut roller);
+        assert_eq!(34, rolled.num_rolled);
+        assert_eq!(1, rolled.success_levels());
+    }
+
+    #[test]
+    fn success_levels_from_tens_digits() {
+        assert_eq!(3, test(12, 45).success_levels());
+        assert_eq!(-2, test(67, 45).success_levels());
+        assert_eq!(-6, test(100, 45).success_levels());
+    }
+
+    #[test]
+    fn success_levels_never_cross_zero() {
+        //Automatic success, even though the roll is over the target.
+        assert!(test(4, 3).is_success());
+        assert_eq!(0, test(4, 3).success_levels());
+
+        //Automatic failure with a higher target.
+        assert!(!test(97, 110).is_success());
+        assert_eq!(0, test(97, 110).success_levels());
+    }
+
+    #[test]
+    fn doubles_are_crits_or_fumbles() {
+        assert!(test(33, 45).is_critical());
+        assert!(!test(33, 45).is_fumble());
+        assert!(test(55, 45).is_fumble());
+        assert!(test(100, 45).is_fumble());
+        assert!(!test(34, 45).is_critical());
+    }
+
+    #[test]
+    fn hit_location_from_reversed_roll() {
+        assert_eq!(43, test(34, 45).reversed());
+        assert_eq!(HitLocation::RightArm, test(34, 45).hit_location());
+        assert_eq!(70, test(7, 45).reversed());
+        assert_eq!(1, test(10, 45).reversed());
+        assert_eq!(HitLocation::Head, test(10, 45).hit_location());
+        assert_eq!(100, test(100, 45).reversed());
+        assert_eq!(HitLocation::RightLeg, test(100, 45).hit_location());
+    }
+
+    #[test]
+    fn opposed_test_compares_success_levels() {
+        let result = opposed_test(&test(12, 45), &test(30, 50));
+        assert_eq!(OpposedResult::FirstWins(1), result);
+
+        let result = opposed_test(&test(67, 45), &test(30, 50));
+        assert_eq!(OpposedResult::SecondWins(4), result);
+    }
+
+    #[test]
+    fn opposed_test_tie_goes_to_higher_target() {
+        let result = opposed_test(&test(32, 45), &test(21, 35));
+        assert_eq!(OpposedResult::FirstWins(0), result);
+
+        let result = opposed_test(&test(32, 45), &test(35, 45));
+        assert_eq!(OpposedResult::Stalemate, result);
+    }
+
+    #[test]
+    fn formats_test() {
+        assert_eq!(
+            "34 against 45: +1 SL (success!), hit location: right arm (43)",
+            test(34, 45).to_string()
+        );
+
+        assert_eq!(
+            "00 against 45: -6 SL (fumble!), hit location: right leg (00)",
+            test(100, 45).to_string()
+        );
+    }
+}
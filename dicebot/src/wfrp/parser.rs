@@ -0,0 +1,71 @@
+use super::dice::WfrpTest;
+use crate::parser::dice::{parse_amounts, Amount, DiceParsingError};
+
+/// Separates the two targets of an opposed test.
+const OPPOSED_SEPARATOR: &str = " vs ";
+
+fn parse_target(input: &str) -> Result<Vec<Amount>, DiceParsingError> {
+    let (amounts, rest) = parse_amounts(input.trim())?;
+    if !rest.trim().is_empty() {
+        return Err(DiceParsingError::UnconsumedInput);
+    }
+
+    Ok(amounts)
+}
+
+/// Parse a WFRP test: a target expression, optionally followed by
+/// "vs" and the opposing target for an opposed test.
+pub fn parse_wfrp_test(input: &str) -> Result<WfrpTest, DiceParsingError> {
+    let (target, opponent) = match input.split_once(OPPOSED_SEPARATOR) {
+        Some((target, opponent)) => (target, Some(opponent)),
+        None => (input, None),
+    };
+
+    Ok(WfrpTest {
+        target: parse_target(target)?,
+        opponent: opponent.map(parse_target).transpose()?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::dice::{Element, Operator};
+
+    #[test]
+    fn target_with_modifier_test() {
+        let result = parse_wfrp_test("melee + 20");
+        assert!(result.is_ok());
+        assert_eq!(
+            result.unwrap(),
+            WfrpTest {
+                target: vec![
+                    Amount {
+                        operator: Operator::Plus,
+                        element: Element::Variable("melee".to_owned()),
+                    },
+                    Amount {
+                        operator: Operator::Plus,
+                        element: Element::Number(20),
+                    },
+                ],
+                opponent: None,
+            }
+        );
+    }
+
+    #[test]
+    fn opposed_test_test() {
+        let result = parse_wfrp_test("45 vs 38");
+        assert!(result.is_ok());
+        assert!(result.unwrap().opponent.is_some());
+    }
+
+    #[test]
+    fn trailing_input_test() {
+        assert_eq!(
+            Err(DiceParsingError::UnconsumedInput),
+            parse_wfrp_test("45 38")
+        );
+    }
+}
@@ -0,0 +1,446 @@
+use crate::dice::{DieRoller, RngDieRoller};
+use itertools::Itertools;
+use std::fmt;
+
+/// Year Zero Engine games only use six-sided dice.
+const DIE_SIDES: u32 = 6;
+
+/// A 6 on any die is a success.
+const SUCCESS: u32 = 6;
+
+/// A 1 on a base, gear or stress die is a bane. Banes only matter
+/// when a roll is pushed, or on stress dice.
+const BANE: u32 = 1;
+
+/// A planned Year Zero Engine roll. Base, skill and gear dice are
+/// rolled as separate pools. Stress dice are only used by Alien; a
+/// roll with stress dice (even zero of them) is treated as an Alien
+/// roll.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct YzeRoll {
+    pub base: u32,
+    pub skill: u32,
+    pub gear: u32,
+    pub stress: Option<u32>,
+}
+
+impl fmt::Display for YzeRoll {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} base, {} skill, {} gear",
+            self.base, self.skill, self.gear
+        )?;
+
+        if let Some(stress) = self.stress {
+            write!(f, ", {} stress", stress)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The separate pools of dice in a roll. Results are grouped by pool
+/// when displayed, using the colour of the physical dice.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Pool {
+    Base,
+    Skill,
+    Gear,
+    Stress,
+}
+
+impl Pool {
+    pub fn colour(&self) -> &'static str {
+        match self {
+            Self::Base => "yellow",
+            Self::Skill => "green",
+            Self::Gear => "black",
+            Self::Stress => "red",
+        }
+    }
+}
+
+impl fmt::Display for Pool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Base => "Base",
+            Self::Skill => "Skill",
+            Self::Gear => "Gear",
+            Self::Stress => "Stress",
+        };
+
+        write!(f, "{} ({})", name, self.colour())
+    }
+}
+
+/// Result of a panic check, made when a stress die shows a 1.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PanicCheck {
+    /// The d6 rolled for the panic check.
+    pub roll: u32,
+
+    /// The stress level added to the roll.
+    pub stress: u32,
+}
+
+impl PanicCheck {
+    pub fn total(&self) -> u32 {
+        self.roll + self.stress
+    }
+
+    /// What happens to the character, according to the panic table.
+    pub fn effect(&self) -> &'static str {
+        match self.total() {
+            0..=6 => "keeping it together",
+            7 => "nervous twitch: stress +1, and for every other PC nearby",
+            8 => "tremble: -2 to all Agility skill rolls until stress drops",
+            9 => "drop item: drop a weapon or other important item",
+            10 => "freeze: lose your next slow action",
+            11 => "seek cover: use your next action to move away from danger",
+            12 => "scream: lose your next slow action, stress +1 for all nearby",
+            13 => "flee: run from the danger, cannot do anything else",
+            14 => "frenzy: attack the nearest person or creature",
+            _ => "catatonic: collapse and cannot move or speak",
+        }
+    }
+}
+
+impl fmt::Display for PanicCheck {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} + {} stress = {}, {}",
+            self.roll,
+            self.stress,
+            self.total(),
+            self.effect()
+        )
+    }
+}
+
+/// A completed Year Zero Engine roll. This is what is stored per user
+/// and room, so that it can later be pushed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RolledYze {
+    pub base: Vec<u32>,
+    pub skill: Vec<u32>,
+    pub gear: Vec<u32>,
+    pub stress: Option<Vec<u32>>,
+    pub pushed: bool,
+}
+
+fn count(rolls: &[u32], value: u32) -> u32 {
+    rolls.iter().filter(|&&roll| roll == value).count() as u32
+}
+
+impl RolledYze {
+    fn pools(&self) -> Vec<(Pool, &[u32])> {
+        let mut pools = vec![
+            (Pool::Base, self.base.as_slice()),
+            (Pool::Skill, self.skill.as_slice()),
+            (Pool::Gear, self.gear.as_slice()),
+        ];
+
+        if let Some(stress) = &self.stress {
+            pools.push((Pool::Stress, stress.as_slice()));
+        }
+
+        pools
+    }
+
+    /// Alien rolls are the ones that include stress dice.
+    pub fn is_alien(&self) -> bool {
+        self.stress.is_some()
+    }
+
+    /// Every 6 in every pool is a success.
+    pub fn successes(&self) -> u32 {
+        self.pools()
+            .iter()
+            .map(|(_, rolls)| count(rolls, SUCCESS))
+            .sum()
+    }
+
+    /// Pushing a roll damages the attribute once for every 1 on the
+    /// base dice. Alien uses stress instead of attribute damage.
+    pub fn attribute_damage(&self) -> u32 {
+        if self.pushed && !self.is_alien() {
+            count(&self.base, BANE)
+        } else {
+            0
+        }
+    }
+
+    /// Pushing a roll reduces the gear bonus once for every 1 on the
+    /// gear dice.
+    pub fn gear_damage(&self) -> u32 {
+        if self.pushed && !self.is_alien() {
+            count(&self.gear, BANE)
+        } else {
+            0
+        }
+    }
+
+    /// Any 1 on a stress die means the character must make a panic
+    /// check.
+    pub fn should_panic(&self) -> bool {
+        self.stress
+            .as_ref()
+            .map(|stress| count(stress, BANE) > 0)
+            .unwrap_or(false)
+    }
+
+    /// The current stress level, which is the number of stress dice.
+    pub fn stress_level(&self) -> u32 {
+        self.stress
+            .as_ref()
+            .map(|stress| stress.len() as u32)
+            .unwrap_or(0)
+    }
+}
+
+impl fmt::Display for RolledYze {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pools = self
+            .pools()
+            .into_iter()
+            .filter(|(_, rolls)| !rolls.is_empty())
+            .map(|(pool, rolls)| format!("{}: {}", pool, rolls.iter().join(", ")))
+            .join("; ");
+
+        write!(f, "{}. ", pools)?;
+
+        match self.successes() {
+            0 => write!(f, "failure!")?,
+            1 => write!(f, "1 success!")?,
+            successes => write!(f, "{} successes!", successes)?,
+        }
+
+        if self.attribute_damage() > 0 {
+            write!(f, " Attribute damage: {}.", self.attribute_damage())?;
+        }
+
+        if self.gear_damage() > 0 {
+            write!(f, " Gear damage: {}.", self.gear_damage())?;
+        }
+
+        Ok(())
+    }
+}
+
+fn roll_dice<R: DieRoller>(roller: &mut R, amount: u32) -> Vec<u32> {
+    (0..amount).map(|_| roller.roll_number(DIE_SIDES)).collect()
+}
+
+///Re-roll every die that is not locked in as a success or a bane.
+fn reroll_dice<R: DieRoller>(roller: &mut R, rolls: &[u32]) -> Vec<u32> {
+    rolls
+        .iter()
+        .map(|&roll| match roll {
+            SUCCESS | BANE => roll,
+            _ => roller.roll_number(DIE_SIDES),
+        })
+        .collect()
+}
+
+fn roll_yze_dice<R: DieRoller>(roll: &YzeRoll, roller: &mut R) -> RolledYze {
+    RolledYze {
+        base: roll_dice(roller, roll.base),
+        skill: roll_dice(roller, roll.skill),
+        gear: roll_dice(roller, roll.gear),
+        stress: roll.stress.map(|stress| roll_dice(roller, stress)),
+        pushed: false,
+    }
+}
+
+/// Push a roll. Pushing in Alien raises the stress level by one, which
+/// adds another stress die to the re-roll.
+fn push_yze_dice<R: DieRoller>(rolled: &RolledYze, roller: &mut R) -> RolledYze {
+    let base = reroll_dice(roller, &rolled.base);
+    let skill = reroll_dice(roller, &rolled.skill);
+    let gear = reroll_dice(roller, &rolled.gear);
+    let stress = rolled.stress.as_ref().map(|stress| {
+        let mut stress = reroll_dice(roller, stress);
+        stress.push(roller.roll_number(DIE_SIDES));
+        stress
+    });
+
+    RolledYze {
+        base,
+        skill,
+        gear,
+        stress,
+        pushed: true,
+    }
+}
+
+fn panic_check_with<R: DieRoller>(rolled: &RolledYze, roller: &mut R) -> Option<PanicCheck> {
+    if rolled.should_panic() {
+        Some(PanicCheck {
+            roll: roller.roll_number(DIE_SIDES),
+            stress: rolled.stress_level(),
+        })
+    } else {
+        None
+    }
+}
+
+/// Roll the base, skill, gear and (optionally) stress dice.
+pub fn roll_yze(roll: &YzeRoll) -> RolledYze {
+    let mut roller = RngDieRoller(rand::thread_rng());
+    roll_yze_dice(roll, &mut roller)
+}
+
+/// Push a previous roll: every die that is not a 6 or a 1 is rolled
+/// again.
+pub fn push_yze(rolled: &RolledYze) -> RolledYze {
+    let mut roller = RngDieRoller(rand::thread_rng());
+    push_yze_dice(rolled, &mut roller)
+}
+
+/// Make a panic check if any stress die came up as a 1.
+pub fn panic_check(rolled: &RolledYze) -> Option<PanicCheck> {
+    let mut roller = RngDieRoller(rand::thread_rng());
+    panic_check_with(rolled, &mut roller)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dice::SequentialDieRoller;
+
+    fn yze_roll(base: u32, skill: u32, gear: u32, stress: Option<u32>) -> YzeRoll {
+        YzeRoll {
+            base,
+            skill,
+            gear,
+            stress,
+        }
+    }
+
+    #[test]
+    fn sixes_are_successes() {
+        let mut roller = SequentialDieRoller::new(vec![6, 3, 6, 6, 2]);
+        let rolled = roll_yze_dice(&yze_roll(2, 2, 1, None), &mut roller);
+        assert_eq!(vec![6, 3], rolled.base);
+        assert_eq!(vec![6, 6], rolled.skill);
+        assert_eq!(vec![2], rolled.gear);
+        assert_eq!(3, rolled.successes());
+    }
+
+    #[test]
+    fn unpushed_roll_causes_no_damage() {
+        let mut roller = SequentialDieRoller::new(vec![1, 1, 1]);
+        let rolled = roll_yze_dice(&yze_roll(2, 0, 1, None), &mut roller);
+        assert_eq!(0, rolled.attribute_damage());
+        assert_eq!(0, rolled.gear_damage());
+    }
+
+    #[test]
+    fn push_keeps_sixes_and_ones() {
+        let rolled = RolledYze {
+            base: vec![6, 1, 3],
+            skill: vec![2],
+            gear: vec![1, 4],
+            stress: None,
+            pushed: false,
+        };
+
+        let mut roller = SequentialDieRoller::new(vec![1, 6, 5]);
+        let pushed = push_yze_dice(&rolled, &mut roller);
+        assert_eq!(vec![6, 1, 1], pushed.base);
+        assert_eq!(vec![6], pushed.skill);
+        assert_eq!(vec![1, 5], pushed.gear);
+        assert!(pushed.pushed);
+    }
+
+    #[test]
+    fn pushed_roll_damages_attribute_and_gear() {
+        let rolled = RolledYze {
+            base: vec![1, 1, 6],
+            skill: vec![1],
+            gear: vec![1, 3],
+            stress: None,
+            pushed: true,
+        };
+
+        assert_eq!(2, rolled.attribute_damage());
+        assert_eq!(1, rolled.gear_damage());
+        assert_eq!(1, rolled.successes());
+    }
+
+    #[test]
+    fn alien_push_adds_stress_die() {
+        let mut roller = SequentialDieRoller::new(vec![3, 4]);
+        let rolled = roll_yze_dice(&yze_roll(1, 0, 0, Some(1)), &mut roller);
+        assert_eq!(Some(vec![4]), rolled.stress);
+
+        let mut roller = SequentialDieRoller::new(vec![5, 6, 2]);
+        let pushed = push_yze_dice(&rolled, &mut roller);
+        assert_eq!(vec![5], pushed.base);
+        assert_eq!(Some(vec![6, 2]), pushed.stress);
+        assert_eq!(2, pushed.stress_level());
+        assert_eq!(1, pushed.successes());
+    }
+
+    #[test]
+    fn alien_push_does_not_damage_attribute() {
+        let rolled = RolledYze {
+            base: vec![1],
+            skill: vec![],
+            gear: vec![1],
+            stress: Some(vec![3]),
+            pushed: true,
+        };
+
+        assert_eq!(0, rolled.attribute_damage());
+        assert_eq!(0, rolled.gear_damage());
+    }
+
+    #[test]
+    fn stress_die_one_triggers_panic() {
+        let rolled = RolledYze {
+            base: vec![4],
+            skill: vec![],
+            gear: vec![],
+            stress: Some(vec![1, 5, 3]),
+            pushed: false,
+        };
+
+        let mut roller = SequentialDieRoller::new(vec![5]);
+        let check = panic_check_with(&rolled, &mut roller).expect("no panic check");
+        assert_eq!(8, check.total());
+        assert!(check.effect().starts_with("tremble"));
+    }
+
+    #[test]
+    fn no_panic_without_stress_one() {
+        let rolled = RolledYze {
+            base: vec![1],
+            skill: vec![],
+            gear: vec![],
+            stress: Some(vec![2]),
+            pushed: false,
+        };
+
+        let mut roller = SequentialDieRoller::new(vec![]);
+        assert_eq!(None, panic_check_with(&rolled, &mut roller));
+    }
+
+    #[test]
+    fn formats_by_pool() {
+        let rolled = RolledYze {
+            base: vec![6, 1],
+            skill: vec![],
+            gear: vec![1],
+            stress: None,
+            pushed: true,
+        };
+
+        assert_eq!(
+            "Base (yellow): 6, 1; Gear (black): 1. 1 success! Attribute damage: 1. Gear damage: 1.",
+            rolled.to_string()
+        );
+    }
+}
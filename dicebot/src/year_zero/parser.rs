@@ -0,0 +1,117 @@
+use super::dice::YzeRoll;
+use crate::parser::dice::DiceParsingError;
+
+/// Upper limit on the total amount of dice in a single roll, so the
+/// room is not spammed with huge messages.
+const MAX_DICE: u32 = 100;
+
+/// Parse the amount of dice in one pool. Each pool is checked
+/// against the limit on its own, so adding them up cannot overflow.
+fn parse_pool(amount: &str) -> Result<u32, DiceParsingError> {
+    match amount.parse::<u32>()? {
+        amount if amount > MAX_DICE => Err(DiceParsingError::InvalidAmount),
+        amount => Ok(amount),
+    }
+}
+
+/// Parse a Year Zero Engine roll, in the form of `<base> [skill]
+/// [gear] [stress <amount>]`, e.g. "3", "3 2 1" or "4 2 stress 3".
+/// Including the stress keyword (even with 0 stress dice) makes the
+/// roll an Alien roll.
+pub fn parse_yze_roll(input: &str) -> Result<YzeRoll, DiceParsingError> {
+    let parts: Vec<&str> = input.split_whitespace().collect();
+
+    let (pools, stress) = match parts.iter().position(|&part| part == "stress") {
+        Some(index) => match parts[index..] {
+            [_, stress] => (&parts[..index], Some(parse_pool(stress)?)),
+            [_] => return Err(DiceParsingError::InvalidAmount),
+            _ => return Err(DiceParsingError::UnconsumedInput),
+        },
+        None => (&parts[..], None),
+    };
+
+    let (base, skill, gear) = match pools {
+        [base] => (parse_pool(base)?, 0, 0),
+        [base, skill] => (parse_pool(base)?, parse_pool(skill)?, 0),
+        [base, skill, gear] => (parse_pool(base)?, parse_pool(skill)?, parse_pool(gear)?),
+        [] => return Err(DiceParsingError::InvalidAmount),
+        _ => return Err(DiceParsingError::UnconsumedInput),
+    };
+
+    let total = base + skill + gear + stress.unwrap_or(0);
+    if total == 0 || total > MAX_DICE {
+        return Err(DiceParsingError::InvalidAmount);
+    }
+
+    Ok(YzeRoll {
+        base,
+        skill,
+        gear,
+        stress,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn yze_roll(base: u32, skill: u32, gear: u32, stress: Option<u32>) -> YzeRoll {
+        YzeRoll {
+            base,
+            skill,
+            gear,
+            stress,
+        }
+    }
+
+    #[test]
+    fn parses_base_only() {
+        assert_eq!(parse_yze_roll("3"), Ok(yze_roll(3, 0, 0, None)));
+    }
+
+    #[test]
+    fn parses_all_pools() {
+        assert_eq!(parse_yze_roll("3 2"), Ok(yze_roll(3, 2, 0, None)));
+        assert_eq!(parse_yze_roll(" 3  2 1 "), Ok(yze_roll(3, 2, 1, None)));
+    }
+
+    #[test]
+    fn parses_stress_dice() {
+        assert_eq!(
+            parse_yze_roll("4 2 stress 3"),
+            Ok(yze_roll(4, 2, 0, Some(3)))
+        );
+        assert_eq!(parse_yze_roll("4 stress 0"), Ok(yze_roll(4, 0, 0, Some(0))));
+    }
+
+    #[test]
+    fn rejects_missing_stress_amount() {
+        assert_eq!(
+            parse_yze_roll("4 stress"),
+            Err(DiceParsingError::InvalidAmount)
+        );
+    }
+
+    #[test]
+    fn rejects_extraneous_input() {
+        assert_eq!(
+            parse_yze_roll("1 2 3 4"),
+            Err(DiceParsingError::UnconsumedInput)
+        );
+        assert_eq!(
+            parse_yze_roll("1 stress 2 3"),
+            Err(DiceParsingError::UnconsumedInput)
+        );
+    }
+
+    #[test]
+    fn rejects_bad_amounts() {
+        assert!(parse_yze_roll("").is_err());
+        assert!(parse_yze_roll("0").is_err());
+        assert!(parse_yze_roll("abc").is_err());
+        assert!(parse_yze_roll("-1").is_err());
+        assert!(parse_yze_roll("101").is_err());
+        assert!(parse_yze_roll("4294967295 1").is_err());
+        assert!(parse_yze_roll("1 1 1 stress 4294967295").is_err());
+    }
+}